@@ -0,0 +1,213 @@
+//! Vault logic shared by the CSPR and stCSPR branches.
+//!
+//! Guarded-launch caps, borrowing limits, debt floors, self-close, safe-mode
+//! and adjustment checks that both branch contracts apply to their vaults.
+
+use odra::casper_types::U256;
+use crate::errors::CdpError;
+use crate::interfaces::AdjustVaultParams;
+use crate::types::{BranchTotals, VaultData, VaultKey};
+
+/// Maximum vaults in one branch `poke_vaults` call
+pub const MAX_POKE_BATCH: usize = 50;
+
+/// Guarded-launch limits of a branch and current usage
+#[odra::odra_type]
+pub struct LaunchLimits {
+    /// Maximum open vaults (0 = uncapped)
+    pub max_vault_count: u64,
+    /// Maximum branch collateral value in USD, 18 decimals (0 = uncapped)
+    pub max_tvl_usd: U256,
+    /// Currently open vaults
+    pub vault_count: u64,
+    /// Current branch collateral value in USD (last good price)
+    pub tvl_usd: U256,
+}
+
+/// Whether a launch limit is exceeded once usage reaches `value_after`
+///
+/// A zero cap means uncapped.
+pub fn exceeds_launch_cap(value_after: U256, cap: U256) -> bool {
+    !cap.is_zero() && value_after > cap
+}
+
+/// Check that minting `amount` more gUSD keeps supply within `cap`
+///
+/// A zero cap means uncapped, matching the stablecoin's own mint check.
+pub fn check_supply_cap(total_supply: U256, amount: U256, cap: U256) -> Result<(), CdpError> {
+    if exceeds_launch_cap(total_supply.saturating_add(amount), cap) {
+        return Err(CdpError::DebtCeilingReached);
+    }
+    Ok(())
+}
+
+/// Largest debt (18 dec) that keeps `collateral_value` at `mcr_bps`, less `buffer_bps`
+///
+/// With a zero buffer the result sits exactly at MCR (rounded down, so ICR
+/// never falls below it). The buffer leaves headroom for small price moves.
+pub fn max_borrow(collateral_value: U256, mcr_bps: u32, buffer_bps: u32) -> U256 {
+    let bps = U256::from(crate::interest::BPS_SCALE);
+    let at_mcr = collateral_value * bps / U256::from(mcr_bps);
+    at_mcr - at_mcr * U256::from(buffer_bps) / bps
+}
+
+/// Collateral surrendered when a vault self-closes with collateral
+///
+/// Returns `(seized, surplus)` in collateral units: `seized` is worth the debt
+/// (plus `penalty_bps` when the vault is liquidatable), rounded up and capped at
+/// the vault's collateral. `None` if the collateral cannot cover the debt itself.
+pub fn self_close_collateral(
+    collateral: U256,
+    collateral_value: U256,
+    debt: U256,
+    penalty_bps: u32,
+) -> Option<(U256, U256)> {
+    if debt.is_zero() {
+        return Some((U256::zero(), collateral));
+    }
+    if collateral_value < debt {
+        return None;
+    }
+    let bps = U256::from(crate::interest::BPS_SCALE);
+    let value_owed = debt * (bps + U256::from(penalty_bps)) / bps;
+    let seized = (collateral * value_owed + collateral_value - U256::one()) / collateral_value;
+    let seized = seized.min(collateral);
+    Some((seized, collateral - seized))
+}
+
+/// Vault (collateral, debt) after applying an adjustment
+///
+/// `fee` is the upfront borrowing fee added to debt increases. Fails if the
+/// withdrawal or repayment exceeds the current position.
+pub fn adjusted_position(
+    collateral: U256,
+    debt: U256,
+    params: &AdjustVaultParams,
+    fee: U256,
+) -> Result<(U256, U256), CdpError> {
+    let new_collateral = if params.collateral_is_withdraw {
+        if collateral < params.collateral_delta {
+            return Err(CdpError::InsufficientCollateral);
+        }
+        collateral - params.collateral_delta
+    } else {
+        collateral + params.collateral_delta
+    };
+
+    let new_debt = if params.debt_is_repay {
+        if debt < params.debt_delta {
+            return Err(CdpError::RepayExceedsDebt);
+        }
+        debt - params.debt_delta
+    } else {
+        debt + params.debt_delta + fee
+    };
+
+    Ok((new_collateral, new_debt))
+}
+
+/// Whether a remaining vault debt violates the minimum debt floor
+///
+/// Zero debt is allowed (fully repaid vaults are not subject to the floor).
+pub fn is_below_min_debt(debt: U256, min_debt: U256) -> bool {
+    !debt.is_zero() && debt < min_debt
+}
+
+/// Whether a vault position is dust: debt remains but collateral is under the floor
+///
+/// Such vaults cost more to liquidate than they pay out. A zero floor disables
+/// the check.
+pub fn is_dust_position(collateral: U256, debt: U256, min_collateral: U256) -> bool {
+    !min_collateral.is_zero() && !debt.is_zero() && collateral < min_collateral
+}
+
+/// Vault debt left after repaying `amount`
+///
+/// A repayment may clear the debt entirely but cannot leave it under
+/// `min_debt`. Zero amounts and debt-free vaults have nothing to repay.
+pub fn debt_after_repayment(debt: U256, amount: U256, min_debt: U256) -> Result<U256, CdpError> {
+    if amount.is_zero() || debt.is_zero() {
+        return Err(CdpError::InsufficientDebt);
+    }
+    if amount > debt {
+        return Err(CdpError::RepayExceedsDebt);
+    }
+    let remaining = debt - amount;
+    if is_below_min_debt(remaining, min_debt) {
+        return Err(CdpError::BelowMinDebt);
+    }
+    Ok(remaining)
+}
+
+/// Whether an adjustment is subject to the branch TVL cap
+///
+/// Only collateral deposited alongside new borrowing counts; pure top-ups,
+/// repayments and withdrawals reduce risk and are allowed past the cap.
+pub fn adjustment_hits_tvl_cap(params: &AdjustVaultParams) -> bool {
+    !params.collateral_is_withdraw
+        && !params.collateral_delta.is_zero()
+        && !params.debt_is_repay
+        && !params.debt_delta.is_zero()
+}
+
+/// Whether safe mode blocks an adjustment
+///
+/// While safe mode is active only risk-reducing changes go through: no new
+/// borrowing and no collateral withdrawals.
+pub fn blocked_in_safe_mode(params: &AdjustVaultParams) -> bool {
+    safe_mode_blocks_adjustment(params, true, true)
+}
+
+/// Whether safe mode blocks an adjustment, given its phase
+///
+/// Withdrawals are blocked as soon as safe mode is active; new borrowing only
+/// once the grace period has elapsed (`borrow_block_effective`).
+pub fn safe_mode_blocks_adjustment(params: &AdjustVaultParams, is_active: bool, borrow_block_effective: bool) -> bool {
+    let is_borrowing = !params.debt_is_repay && !params.debt_delta.is_zero();
+    let is_withdrawing = params.collateral_is_withdraw && !params.collateral_delta.is_zero();
+    (is_active && is_withdrawing) || (borrow_block_effective && is_borrowing)
+}
+
+/// Whether a branch may accept a migrated vault
+///
+/// Only while migrating, only from the configured peer branch, and never over
+/// an open vault.
+pub fn check_vault_import(migrating: bool, caller_is_peer: bool, vault_exists: bool) -> Result<(), CdpError> {
+    if !migrating {
+        Err(CdpError::MigrationNotActive)
+    } else if !caller_is_peer {
+        Err(CdpError::UnauthorizedProtocol)
+    } else if vault_exists {
+        Err(CdpError::VaultAlreadyExists)
+    } else {
+        Ok(())
+    }
+}
+
+impl BranchTotals {
+    /// Totals once `vault` is added
+    pub fn with_vault(self, vault: &VaultData) -> Self {
+        Self {
+            total_collateral: self.total_collateral + vault.collateral,
+            total_debt: self.total_debt + vault.debt,
+            vault_count: self.vault_count + 1,
+        }
+    }
+
+    /// Totals once `vault` is removed
+    pub fn without_vault(self, vault: &VaultData) -> Self {
+        Self {
+            total_collateral: self.total_collateral - vault.collateral,
+            total_debt: self.total_debt - vault.debt,
+            vault_count: self.vault_count.saturating_sub(1),
+        }
+    }
+}
+
+/// Reject a `poke_vaults` batch of more than `MAX_POKE_BATCH` vaults
+pub fn check_poke_batch(vault_keys: &[VaultKey]) -> Result<(), CdpError> {
+    if vault_keys.len() > MAX_POKE_BATCH {
+        return Err(CdpError::BatchTooLarge);
+    }
+    Ok(())
+}
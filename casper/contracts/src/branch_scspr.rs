@@ -1,22 +1,53 @@
 //! Branch contract for stCSPR (staked CSPR) collateral.
 
 use odra::prelude::*;
-use odra::casper_types::U256;
-use crate::types::{CollateralId, VaultData, VaultKey, UserVaultIndex, SafeModeState, OracleStatus};
+use odra::casper_types::{U256, runtime_args};
+use odra::CallDef;
+use crate::types::{
+    CollateralId, ComponentId, VaultData, VaultKey, UserVaultIndex, SafeModeState, OracleStatus,
+    CollateralBalanceDelta, TotalsChanged, TotalsChangeReason, BranchTotals,
+};
+use crate::math::{apply_rate, balance_delta, collateral_to_value, PRICE_SCALE};
 use crate::interfaces::{VaultInfo, BranchStatus, AdjustVaultParams};
+use crate::branch::{
+    LaunchLimits, exceeds_launch_cap, check_supply_cap, max_borrow, self_close_collateral, adjusted_position,
+    is_below_min_debt, is_dust_position, debt_after_repayment, check_vault_import, adjustment_hits_tvl_cap,
+    blocked_in_safe_mode, safe_mode_blocks_adjustment, check_poke_batch,
+};
+use crate::events::next_event_seq;
+use crate::liquidation_engine::{liquidation_price, health_factor};
 use crate::errors::CdpError;
-use crate::interest::accrue_interest;
-
-/// Minimum Collateralization Ratio in basis points (110% = 11000 bps)
+use crate::registry;
+use crate::interest::{
+    accrual_start, accrue_interest, borrow_fee, rate_adjust_fee, rate_change_allowed, rate_distribution,
+    weighted_average_rate_bps, realizable_interest, pending_interest, debt_in_rate_range, withdrawal_fee,
+    InterestAccrued,
+};
+use crate::redistribution::{RedistributionSnapshot, pending_reward, tracker_increment};
+use crate::sorted_list::{LinkView, verify_links, lowest_icr_first, redeemed_before, relink};
+use crate::recovery::{self, TokenSwept};
+use crate::token_adapter::{BalanceSnapshot, net_received};
+
+/// Default Minimum Collateralization Ratio in basis points (110% = 11000 bps)
 const MCR_BPS: u32 = 11000;
-/// Minimum debt in smallest unit (1 gUSD)
+/// Lowest MCR governance may configure (105% = 10500 bps)
+const MIN_MCR_BPS: u32 = 10500;
+/// Default minimum debt in whole gUSD (1 gUSD)
 const MIN_DEBT_WHOLE: u64 = 1;
-/// Price scale (1e18) - prices and debt are in 18 decimals
-const PRICE_SCALE: u64 = 1_000_000_000_000_000_000;
-/// Collateral decimals (stCSPR uses 9 decimals)
-const COLLATERAL_DECIMALS: u64 = 1_000_000_000;
 /// Maximum interest rate in basis points (40% = 4000 bps)
 const MAX_INTEREST_RATE_BPS: u32 = 4000;
+/// Maximum vaults scanned by bounded sorted-list queries (risk, rate stats)
+const MAX_RISK_SCAN: u32 = 200;
+/// Maximum upfront borrowing fee in basis points (5% = 500 bps)
+const MAX_BORROW_FEE_BPS: u32 = 500;
+/// Maximum interest rate change cooldown (30 days)
+const MAX_RATE_CHANGE_COOLDOWN_SECONDS: u64 = 2_592_000;
+/// Maximum interest-free grace period for new vaults (30 days)
+const MAX_GRACE_PERIOD_SECONDS: u64 = 2_592_000;
+/// Default safety buffer below MCR for max-borrow quotes (0.5% = 50 bps)
+const DEFAULT_MAX_BORROW_BUFFER_BPS: u32 = 50;
+/// Maximum safety buffer for max-borrow quotes (10% = 1000 bps)
+const MAX_MAX_BORROW_BUFFER_BPS: u32 = 1000;
 /// Exchange rate scale (1e18) - must match ScsprYbToken's SCALE
 const RATE_SCALE: u64 = 1_000_000_000_000_000_000;
 
@@ -34,7 +65,7 @@ pub struct SortedVaultEntry {
 }
 
 /// Branch contract for stCSPR collateral
-#[odra::module]
+#[odra::module(events = [TokenSwept, InterestAccrued, TotalsChanged])]
 pub struct BranchScspr {
     /// Registry contract address
     registry: Var<Address>,
@@ -64,8 +95,83 @@ pub struct BranchScspr {
     user_vault_count: Mapping<Address, u64>,
     /// Mapping from (owner, index) to vault id for enumeration
     user_vault_ids: Mapping<UserVaultIndex, u64>,
+    /// Vault bookkeeping beyond the core fields (nested: a module holds at most 15 fields)
+    state: SubModule<BranchScsprState>,
+}
+
+/// Branch state added on top of the core vault storage
+#[odra::module]
+pub struct BranchScsprState {
+    /// Governance-set parameters
+    params: SubModule<BranchScsprParams>,
+    /// Pro-rata redistribution trackers and per-vault snapshots
+    redistribution: SubModule<BranchScsprRedistribution>,
+    /// Every vault key ever opened, by open order (for sorted list rebuilds)
+    all_vault_keys: Mapping<u64, VaultKey>,
+    /// Number of entries in `all_vault_keys`
+    all_vault_count: Var<u64>,
+    /// Timestamp of each vault's last interest rate change (or open)
+    last_rate_change_timestamp: Mapping<VaultKey, u64>,
+    /// Collateral left over from full liquidations, claimable by the owner
+    claimable_surplus: Mapping<Address, U256>,
+    /// Sum of all unclaimed liquidation surplus
+    total_claimable_surplus: Var<U256>,
+    /// Sequence number of the last emitted vault event
+    event_seq: Var<u64>,
+    /// Interest accrued into debt and not yet minted as gUSD
+    total_accrued_interest: Var<U256>,
+    /// Whether a vault migration window is open
+    migrating: Var<bool>,
+    /// Branch on the other side of the migration (export target / import source)
+    migration_peer: Var<Address>,
     /// Mapping from vault key to its index in the owner's list
     vault_indices: Mapping<VaultKey, u64>,
+    /// stCSPR token contract (collateral token)
+    scspr_token: Var<Address>,
+    /// Open timestamp of each vault (start of the interest-free grace window)
+    vault_created_at: Mapping<VaultKey, u64>,
+}
+
+/// Governance-set branch parameters
+#[odra::module]
+pub struct BranchScsprParams {
+    /// Minimum vault debt (gUSD, 18 decimals); defaults to MIN_DEBT_WHOLE
+    min_debt: Var<U256>,
+    /// Minimum collateral for a vault carrying debt (9 decimals, 0 = disabled)
+    min_collateral: Var<U256>,
+    /// Launch limit on open vaults (0 = uncapped)
+    max_vault_count: Var<u64>,
+    /// Launch limit on branch collateral value in USD (0 = uncapped)
+    max_tvl_usd: Var<U256>,
+    /// Upfront borrowing fee in bps on newly borrowed debt (default 0)
+    borrow_fee_bps: Var<u32>,
+    /// Minimum seconds between interest rate changes per vault (default 0)
+    rate_change_cooldown_seconds: Var<u64>,
+    /// Interest-free seconds after a vault is opened (default 0)
+    grace_period_seconds: Var<u64>,
+    /// Safety buffer in bps below MCR for max-borrow quotes (default 50)
+    max_borrow_buffer_bps: Var<u32>,
+    /// Fee in bps on debt charged when a vault lowers its rate (default 0)
+    rate_adjust_fee_bps: Var<u32>,
+    /// Fee in bps on withdrawn collateral, sent to the treasury (default 0)
+    withdrawal_fee_bps: Var<u32>,
+    /// Minimum collateralization ratio in bps; defaults to MCR_BPS
+    mcr_bps: Var<u32>,
+    /// Recipient of realized interest (defaults to the treasury)
+    interest_recipient: Var<Address>,
+}
+
+/// Redistribution trackers for debt and collateral spread across active vaults
+#[odra::module]
+pub struct BranchScsprRedistribution {
+    /// Cumulative redistributed collateral per unit of stake
+    l_collateral: Var<U256>,
+    /// Cumulative redistributed debt per unit of stake
+    l_debt: Var<U256>,
+    /// Sum of active vault stakes
+    total_stakes: Var<U256>,
+    /// Redistribution snapshot per vault (stake and trackers at last touch)
+    redistribution_snapshots: Mapping<VaultKey, RedistributionSnapshot>,
 }
 
 #[odra::module]
@@ -74,7 +180,7 @@ impl BranchScspr {
     pub fn init(&mut self, registry: Address, router: Address, scspr_token: Address) {
         self.registry.set(registry);
         self.router.set(router);
-        let _ = scspr_token;
+        self.state.scspr_token.set(scspr_token);
         self.total_collateral.set(U256::zero());
         self.total_debt.set(U256::zero());
         self.vault_count.set(0);
@@ -86,6 +192,8 @@ impl BranchScspr {
 
     /// Open a new vault with stCSPR collateral
     ///
+    /// Pulls `collateral_amount` stCSPR from the owner (who must have approved
+    /// the branch) and credits the amount actually received.
     /// Returns the newly created vault id (unique per owner, per branch).
     pub fn open_vault(
         &mut self,
@@ -95,6 +203,32 @@ impl BranchScspr {
         interest_rate_bps: u32,
     ) -> u64 {
         self.require_router();
+        let collateral_amount = self.receive_collateral(owner, collateral_amount);
+        self.open_vault_internal(owner, collateral_amount, debt_amount, interest_rate_bps)
+    }
+
+    /// Open a vault whose stCSPR the router has already transferred to the branch
+    ///
+    /// Used by the router's zap, which mints the shares and sends them here
+    /// before opening the vault.
+    pub fn open_vault_prefunded(
+        &mut self,
+        owner: Address,
+        collateral_amount: U256,
+        debt_amount: U256,
+        interest_rate_bps: u32,
+    ) -> u64 {
+        self.require_router();
+        self.open_vault_internal(owner, collateral_amount, debt_amount, interest_rate_bps)
+    }
+
+    fn open_vault_internal(
+        &mut self,
+        owner: Address,
+        collateral_amount: U256,
+        debt_amount: U256,
+        interest_rate_bps: u32,
+    ) -> u64 {
         let caller = owner;
 
         // Defensive check (router validates too).
@@ -102,11 +236,31 @@ impl BranchScspr {
             self.env().revert(CdpError::InterestRateOutOfBounds);
         }
 
-        // Check minimum debt
-        let min_debt = U256::from(MIN_DEBT_WHOLE) * U256::from(PRICE_SCALE);
+        // The gUSD handed to the borrower (net) must meet the floor on its own;
+        // the fee must not be what lifts a vault over it.
+        let min_debt = self.get_min_debt();
+        if debt_amount < min_debt {
+            self.env().revert(CdpError::BelowMinNetDebt);
+        }
+
+        // Upfront borrowing fee is added to the recorded (gross) debt
+        let fee = borrow_fee(debt_amount, self.get_borrow_fee_bps());
+        let debt_amount = debt_amount + fee;
         if debt_amount < min_debt {
             self.env().revert(CdpError::BelowMinDebt);
         }
+        if is_dust_position(collateral_amount, debt_amount, self.get_min_collateral()) {
+            self.env().revert(CdpError::BelowMinCollateral);
+        }
+
+        // Guarded launch: no new vaults past the count cap or the TVL cap
+        let vault_count_after = self.vault_count.get().unwrap_or(0).saturating_add(1);
+        if exceeds_launch_cap(U256::from(vault_count_after), U256::from(self.state.params.max_vault_count.get().unwrap_or(0))) {
+            self.env().revert(CdpError::VaultCountCapReached);
+        }
+        self.require_within_tvl_cap(collateral_amount);
+        // Pre-check the gUSD supply cap so the mint cannot fail after collateral is taken
+        self.require_within_supply_cap(debt_amount);
 
         // Check MCR (using composite pricing)
         let collateral_value = self.get_collateral_value(collateral_amount);
@@ -127,30 +281,11 @@ impl BranchScspr {
             last_accrual_timestamp: self.env().get_block_time(),
         };
 
-        self.vaults.set(&vault_key, vault);
-
-        // Add to sorted list
-        self.insert_into_sorted_list(vault_key, interest_rate_bps);
-
-        // Update totals
-        let current_collateral = self.total_collateral.get().unwrap_or(U256::zero());
-        let current_debt = self.total_debt.get().unwrap_or(U256::zero());
-        let current_count = self.vault_count.get().unwrap_or(0);
-
-        self.total_collateral.set(current_collateral + collateral_amount);
-        self.total_debt.set(current_debt + debt_amount);
-        self.vault_count.set(current_count + 1);
-
-        // Track per-user vault list for enumeration.
-        let user_count = self.user_vault_count.get(&caller).unwrap_or(0);
-        let idx_key = UserVaultIndex { owner: caller, index: user_count };
-        self.user_vault_ids.set(&idx_key, next_id);
-        self.vault_indices.set(&vault_key, user_count);
-        self.user_vault_count.set(&caller, user_count + 1);
-
-        // TODO: Transfer stCSPR from caller (CEP-18 transfer_from)
-        // TODO: Mint gUSD to caller
+        let created_at = self.env().get_block_time();
+        self.insert_vault(vault_key, vault, created_at);
+        self.credit_borrow_fee(fee);
 
+        self.emit_totals_changed(TotalsChangeReason::Open);
         next_id
     }
 
@@ -167,7 +302,7 @@ impl BranchScspr {
         self.require_router();
         let caller = owner;
         let vault_key = VaultKey { owner: caller, id: vault_id };
-        let params = AdjustVaultParams {
+        let mut params = AdjustVaultParams {
             collateral_delta,
             collateral_is_withdraw,
             debt_delta,
@@ -184,12 +319,17 @@ impl BranchScspr {
             self.env().revert(CdpError::VaultNotFound);
         }
 
+        // Pull deposits first so the checks and totals use the amount received
+        if !params.collateral_is_withdraw {
+            params.collateral_delta = self.receive_collateral(owner, params.collateral_delta);
+        }
+
         // Accrue interest before adjustment
         let current_time = self.env().get_block_time();
         let accrual = accrue_interest(
             vault.debt,
             vault.interest_rate_bps,
-            vault.last_accrual_timestamp,
+            self.accrual_start_for(&vault_key, &vault),
             current_time,
         );
 
@@ -200,49 +340,39 @@ impl BranchScspr {
         // Track total accrued interest
         if accrual.interest_accrued > U256::zero() {
             // Update total debt with interest
-            let current_debt = self.total_debt.get().unwrap_or(U256::zero());
-            self.total_debt.set(current_debt + accrual.interest_accrued);
+            self.book_accrued_interest(accrual.interest_accrued);
+            let seq = self.next_event_seq();
+            self.env().emit_event(InterestAccrued {
+                seq,
+                owner: vault_key.owner,
+                vault_id: vault_key.id,
+                amount: accrual.interest_accrued,
+                new_debt: accrual.new_debt,
+            });
         }
 
-        // Calculate new collateral
-        let new_collateral = if params.collateral_is_withdraw {
-            if vault.collateral < params.collateral_delta {
-                self.env().revert(CdpError::InsufficientCollateral);
-            }
-            vault.collateral - params.collateral_delta
-        } else {
-            vault.collateral + params.collateral_delta
-        };
+        // Apply pending redistribution share (already counted in totals)
+        self.apply_pending_redistribution(vault_key, &mut vault);
 
-        // Calculate new debt
-        let new_debt = if params.debt_is_repay {
-            if vault.debt < params.debt_delta {
-                self.env().revert(CdpError::RepayExceedsDebt);
-            }
-            vault.debt - params.debt_delta
-        } else {
-            vault.debt + params.debt_delta
+        // Upfront borrowing fee on debt increases
+        let fee = self.adjustment_fee(&params);
+
+        // New position, checked against min debt, dust, TVL cap and MCR
+        let (new_collateral, new_debt) = match self.check_adjustment(&vault, &params, fee) {
+            Ok(position) => position,
+            Err(err) => self.env().revert(err),
         };
 
         // Check if this results in closing the vault
         if new_collateral.is_zero() && new_debt.is_zero() {
             // Effectively closing the vault
+            let collateral_out = vault.collateral;
             self.close_vault_internal(vault_key, vault);
+            self.transfer_collateral(owner, collateral_out);
+            self.emit_totals_changed(TotalsChangeReason::Close);
             return;
         }
 
-        // Check minimum debt (if any debt remains)
-        if !new_debt.is_zero() {
-            let min_debt = U256::from(MIN_DEBT_WHOLE) * U256::from(PRICE_SCALE);
-            if new_debt < min_debt {
-                self.env().revert(CdpError::BelowMinDebt);
-            }
-        }
-
-        // Check MCR
-        let collateral_value = self.get_collateral_value(new_collateral);
-        self.check_mcr(collateral_value, new_debt);
-
         // Update totals
         let current_collateral = self.total_collateral.get().unwrap_or(U256::zero());
         let current_debt = self.total_debt.get().unwrap_or(U256::zero());
@@ -256,7 +386,7 @@ impl BranchScspr {
         let debt_diff = if params.debt_is_repay {
             current_debt - params.debt_delta
         } else {
-            current_debt + params.debt_delta
+            current_debt + params.debt_delta + fee
         };
 
         self.total_collateral.set(collateral_diff);
@@ -268,8 +398,48 @@ impl BranchScspr {
         vault.last_accrual_timestamp = self.env().get_block_time();
 
         self.vaults.set(&vault_key, vault);
+        self.update_stake(vault_key, new_collateral);
+        self.credit_borrow_fee(fee);
+        if params.collateral_is_withdraw {
+            self.pay_out_withdrawal(owner, params.collateral_delta);
+        }
+        self.emit_totals_changed(TotalsChangeReason::Adjust);
+    }
+
+    /// Preview an `adjust_vault` call without mutating state
+    ///
+    /// Simulates the adjustment on the vault's current position (pending
+    /// interest and redistribution included) and returns the resulting ICR in
+    /// bps and whether the adjustment would pass the min debt, dust, TVL cap,
+    /// MCR and safe-mode checks. Returns `(0, false)` for a missing vault or an
+    /// over-withdrawal / over-repayment.
+    pub fn preview_adjust(
+        &self,
+        owner: Address,
+        vault_id: u64,
+        collateral_delta: U256,
+        is_withdraw: bool,
+        debt_delta: U256,
+        is_repay: bool,
+    ) -> (u32, bool) {
+        let params = AdjustVaultParams {
+            collateral_delta,
+            collateral_is_withdraw: is_withdraw,
+            debt_delta,
+            debt_is_repay: is_repay,
+        };
+        let info = match self.get_vault(owner, vault_id) {
+            Some(info) => info,
+            None => return (0, false),
+        };
 
-        // TODO: Handle token transfers (CEP-18)
+        let fee = self.adjustment_fee(&params);
+        let new_icr_bps = match adjusted_position(info.vault.collateral, info.vault.debt, &params, fee) {
+            Ok((collateral, debt)) => self.calculate_icr(self.get_collateral_value(collateral), debt),
+            Err(_) => return (0, false),
+        };
+        let allowed = self.check_adjustment(&info.vault, &params, fee).is_ok() && !self.is_blocked_by_safe_mode(&params);
+        (new_icr_bps, allowed)
     }
 
     /// Adjust the interest rate for an existing vault.
@@ -295,7 +465,7 @@ impl BranchScspr {
         let accrual = accrue_interest(
             vault.debt,
             vault.interest_rate_bps,
-            vault.last_accrual_timestamp,
+            self.accrual_start_for(&vault_key, &vault),
             current_time,
         );
         vault.debt = accrual.new_debt;
@@ -303,17 +473,55 @@ impl BranchScspr {
 
         // Update total debt with accrued interest
         if accrual.interest_accrued > U256::zero() {
-            let current_debt = self.total_debt.get().unwrap_or(U256::zero());
-            self.total_debt.set(current_debt + accrual.interest_accrued);
+            self.book_accrued_interest(accrual.interest_accrued);
+            let seq = self.next_event_seq();
+            self.env().emit_event(InterestAccrued {
+                seq,
+                owner: vault_key.owner,
+                vault_id: vault_key.id,
+                amount: accrual.interest_accrued,
+                new_debt: accrual.new_debt,
+            });
         }
 
-        if vault.interest_rate_bps != interest_rate_bps {
+        self.apply_pending_redistribution(vault_key, &mut vault);
+
+        let mut fee = U256::zero();
+        let rate_changed = vault.interest_rate_bps != interest_rate_bps;
+        if rate_changed {
+            let last_change = self.state.last_rate_change_timestamp.get(&vault_key).unwrap_or(0);
+            if !rate_change_allowed(last_change, current_time, self.get_rate_change_cooldown_seconds()) {
+                self.env().revert(CdpError::RateChangeTooSoon);
+            }
+            self.state.last_rate_change_timestamp.set(&vault_key, current_time);
+
+            // Lowering the rate costs an upfront fee added to debt
+            fee = rate_adjust_fee(
+                vault.debt,
+                vault.interest_rate_bps,
+                interest_rate_bps,
+                self.get_rate_adjust_fee_bps(),
+            );
+            if !fee.is_zero() {
+                vault.debt += fee;
+                let collateral_value = self.get_collateral_value(vault.collateral);
+                self.check_mcr(collateral_value, vault.debt);
+                let current_debt = self.total_debt.get().unwrap_or(U256::zero());
+                self.total_debt.set(current_debt + fee);
+            }
+
             self.remove_from_sorted_list(vault_key);
             vault.interest_rate_bps = interest_rate_bps;
-            self.insert_into_sorted_list(vault_key, interest_rate_bps);
+            let created_at = self.vault_created_at(&vault_key);
+            self.insert_into_sorted_list(vault_key, interest_rate_bps, created_at);
         }
 
         self.vaults.set(&vault_key, vault);
+        self.credit_borrow_fee(fee);
+        // Re-submitting the current rate with nothing accrued leaves the totals as they were
+        if rate_changed || !accrual.interest_accrued.is_zero() {
+            self.emit_totals_changed(TotalsChangeReason::Interest);
+        }
     }
 
     /// Close vault and withdraw all collateral
@@ -322,7 +530,7 @@ impl BranchScspr {
         let caller = owner;
         let vault_key = VaultKey { owner: caller, id: vault_id };
 
-        let vault = match self.vaults.get(&vault_key) {
+        let mut vault = match self.vaults.get(&vault_key) {
             Some(v) => v,
             None => {
                 self.env().revert(CdpError::VaultNotFound);
@@ -331,20 +539,148 @@ impl BranchScspr {
         if vault.collateral.is_zero() && vault.debt.is_zero() {
             self.env().revert(CdpError::VaultNotFound);
         }
+        self.apply_pending_redistribution(vault_key, &mut vault);
+
+        let collateral_out = vault.collateral;
+        self.close_vault_internal(vault_key, vault);
+        self.transfer_collateral(owner, collateral_out);
+        self.emit_totals_changed(TotalsChangeReason::Close);
+    }
+
+    /// Close a vault by surrendering collateral instead of repaying gUSD
+    ///
+    /// Collateral worth the debt at the last good price goes to the treasury and
+    /// the rest is credited as claimable surplus. The treasury buys that
+    /// collateral with gUSD: the debt is burned from the treasury through the
+    /// allowance it granted this branch. A vault below MCR also surrenders the
+    /// liquidation penalty, so self-closing never undercuts liquidation; a
+    /// vault whose collateral cannot cover its debt reverts.
+    /// Returns the surplus credited to the owner.
+    pub fn close_with_collateral(&mut self, owner: Address, vault_id: u64) -> U256 {
+        self.require_router();
+        let vault_key = VaultKey { owner, id: vault_id };
+
+        let mut vault = match self.vaults.get(&vault_key) {
+            Some(v) => v,
+            None => self.env().revert(CdpError::VaultNotFound),
+        };
+        if vault.collateral.is_zero() && vault.debt.is_zero() {
+            self.env().revert(CdpError::VaultNotFound);
+        }
+
+        // Accrue interest before valuing the debt
+        self.accrue_vault_interest(vault_key, &mut vault);
+        self.apply_pending_redistribution(vault_key, &mut vault);
+
+        let collateral_value = self.get_collateral_value(vault.collateral);
+        let penalty_bps = if self.calculate_icr(collateral_value, vault.debt) < self.get_mcr() {
+            self.get_liquidation_penalty_bps()
+        } else {
+            0
+        };
+        let (seized, surplus) =
+            match self_close_collateral(vault.collateral, collateral_value, vault.debt, penalty_bps) {
+                Some(split) => split,
+                None => self.env().revert(CdpError::InsufficientCollateral),
+            };
 
+        let debt = vault.debt;
         self.close_vault_internal(vault_key, vault);
+        self.credit_surplus(owner, surplus);
+        let treasury = self.get_treasury_address();
+        if !seized.is_zero() {
+            self.transfer_collateral(treasury, seized);
+        }
+        if !debt.is_zero() {
+            let stablecoin_addr = self.get_stablecoin_address();
+            let burn_args = runtime_args! {
+                "from" => treasury,
+                "amount" => debt
+            };
+            let burn_call = CallDef::new("burn_with_allowance", true, burn_args);
+            self.env().call_contract::<()>(stablecoin_addr, burn_call);
+        }
+        self.emit_totals_changed(TotalsChangeReason::Close);
+        surplus
+    }
+
+    /// Repay part or all of another account's vault debt
+    ///
+    /// Anyone may repay: the caller's gUSD is burned through the allowance they
+    /// granted this branch. Interest is accrued first. Only debt is reduced;
+    /// collateral stays with the vault owner.
+    pub fn repay_for(&mut self, owner: Address, vault_id: u64, amount: U256) {
+        let payer = self.env().caller();
+        let vault_key = VaultKey { owner, id: vault_id };
+
+        let mut vault = match self.vaults.get(&vault_key) {
+            Some(v) => v,
+            None => self.env().revert(CdpError::VaultNotFound),
+        };
+        if vault.collateral.is_zero() && vault.debt.is_zero() {
+            self.env().revert(CdpError::VaultNotFound);
+        }
+
+        self.accrue_vault_interest(vault_key, &mut vault);
+        self.apply_pending_redistribution(vault_key, &mut vault);
+
+        vault.debt = match debt_after_repayment(vault.debt, amount, self.get_min_debt()) {
+            Ok(debt) => debt,
+            Err(err) => self.env().revert(err),
+        };
+        let total_debt = self.total_debt.get().unwrap_or(U256::zero());
+        self.total_debt.set(total_debt - amount);
+        self.vaults.set(&vault_key, vault);
+
+        let stablecoin_addr = self.get_stablecoin_address();
+        let burn_args = runtime_args! {
+            "from" => payer,
+            "amount" => amount
+        };
+        let burn_call = CallDef::new("burn_with_allowance", true, burn_args);
+        self.env().call_contract::<()>(stablecoin_addr, burn_call);
+        self.emit_totals_changed(TotalsChangeReason::Adjust);
+    }
+
+    /// Record a new vault: storage, stake, sorted list, totals and indexes
+    fn insert_vault(&mut self, vault_key: VaultKey, vault: VaultData, created_at: u64) {
+        let owner = vault_key.owner;
+        let interest_rate_bps = vault.interest_rate_bps;
+        let totals = self.totals().with_vault(&vault);
+
+        self.update_stake(vault_key, vault.collateral);
+        self.vaults.set(&vault_key, vault);
+        let now = self.env().get_block_time();
+        self.state.last_rate_change_timestamp.set(&vault_key, now);
+        self.state.vault_created_at.set(&vault_key, created_at);
+
+        // Add to sorted list
+        self.insert_into_sorted_list(vault_key, interest_rate_bps, created_at);
+
+        // Update totals
+        self.set_totals(totals);
+
+        // Track per-user vault list for enumeration.
+        let user_count = self.user_vault_count.get(&owner).unwrap_or(0);
+        let idx_key = UserVaultIndex { owner, index: user_count };
+        self.user_vault_ids.set(&idx_key, vault_key.id);
+        self.state.vault_indices.set(&vault_key, user_count);
+        self.user_vault_count.set(&owner, user_count + 1);
+
+        // Track globally for sorted list rebuilds.
+        let all_count = self.state.all_vault_count.get().unwrap_or(0);
+        self.state.all_vault_keys.set(&all_count, vault_key);
+        self.state.all_vault_count.set(all_count + 1);
     }
 
     /// Internal vault closing logic
+    ///
+    /// Does not move collateral; callers pay it out to wherever it belongs.
     fn close_vault_internal(&mut self, vault_key: VaultKey, vault: VaultData) {
         let owner = vault_key.owner;
-        let current_collateral = self.total_collateral.get().unwrap_or(U256::zero());
-        let current_debt = self.total_debt.get().unwrap_or(U256::zero());
-        let current_count = self.vault_count.get().unwrap_or(0);
-
-        self.total_collateral.set(current_collateral - vault.collateral);
-        self.total_debt.set(current_debt - vault.debt);
-        self.vault_count.set(current_count.saturating_sub(1));
+        let totals = self.totals().without_vault(&vault);
+        self.set_totals(totals);
+        self.update_stake(vault_key, U256::zero());
 
         // Remove from sorted list
         self.remove_from_sorted_list(vault_key);
@@ -361,7 +697,6 @@ impl BranchScspr {
         self.vaults.set(&vault_key, empty_vault);
         self.remove_vault_from_owner_list(vault_key);
 
-        // TODO: Transfer stCSPR back to owner (CEP-18 transfer)
         // TODO: Require debt repayment (burn gUSD)
     }
 
@@ -370,6 +705,65 @@ impl BranchScspr {
         self.user_vault_count.get(owner).unwrap_or(0) > 0
     }
 
+    /// Get interest that would accrue on a vault if it were touched now
+    pub fn get_pending_interest(&self, owner: Address, vault_id: u64) -> U256 {
+        let vault_key = VaultKey { owner, id: vault_id };
+        let vault = match self.vaults.get(&vault_key) {
+            Some(v) => v,
+            None => return U256::zero(),
+        };
+        accrue_interest(
+            vault.debt,
+            vault.interest_rate_bps,
+            self.accrual_start_for(&vault_key, &vault),
+            self.env().get_block_time(),
+        )
+        .interest_accrued
+    }
+
+    /// Get the CSPR/USD price at which a vault becomes liquidatable
+    ///
+    /// stCSPR collateral is converted at the current exchange rate, so a rising
+    /// rate lowers the liquidation price. Based on current debt (pending interest
+    /// and redistribution included); zero for debt-free or missing vaults.
+    pub fn get_liquidation_price(&self, owner: Address, vault_id: u64) -> U256 {
+        match self.get_vault(owner, vault_id) {
+            Some(info) => liquidation_price(info.vault.debt, apply_rate(info.vault.collateral, self.get_exchange_rate()), self.get_mcr()),
+            None => U256::zero(),
+        }
+    }
+
+    /// Get the largest debt `collateral_amount` can back at the current price
+    ///
+    /// Quote for frontends: recorded (gross) debt keeping ICR at MCR at the
+    /// last good price and exchange rate, less the max-borrow safety buffer.
+    pub fn get_max_borrow(&self, _owner: Address, collateral_amount: U256) -> U256 {
+        let collateral_value = self.get_collateral_value(collateral_amount);
+        max_borrow(collateral_value, self.get_mcr(), self.get_max_borrow_buffer_bps())
+    }
+
+    /// Get a vault's health factor and its decimals
+    ///
+    /// ICR / MCR scaled to 18 decimals: 1.0e18 is exactly at MCR, below it
+    /// the vault is liquidatable. Debt-free vaults return `U256::MAX`; missing
+    /// vaults return zero.
+    pub fn get_health_factor(&self, owner: Address, vault_id: u64) -> (U256, u8) {
+        match self.get_vault(owner, vault_id) {
+            Some(info) => (health_factor(info.collateral_value_usd, info.vault.debt, self.get_mcr()), 18),
+            None => (U256::zero(), 18),
+        }
+    }
+
+    /// Sequence number of the last emitted vault event (0 if none yet)
+    pub fn get_event_seq(&self) -> u64 {
+        self.state.event_seq.get_or_default()
+    }
+
+    /// Get vault info by key; same as `get_vault(key.owner, key.id)`
+    pub fn get_vault_by_key(&self, vault_key: VaultKey) -> Option<VaultInfo> {
+        self.get_vault(vault_key.owner, vault_key.id)
+    }
+
     /// Get vault info for an owner (includes pending accrued interest)
     pub fn get_vault(&self, owner: Address, vault_id: u64) -> Option<VaultInfo> {
         let vault_key = VaultKey { owner, id: vault_id };
@@ -384,16 +778,18 @@ impl BranchScspr {
         let accrual = accrue_interest(
             vault.debt,
             vault.interest_rate_bps,
-            vault.last_accrual_timestamp,
+            self.accrual_start_for(&vault_key, &vault),
             current_time,
         );
 
-        // Create vault info with current debt (including pending interest)
+        // Create vault info with current debt (including pending interest and redistribution)
+        let (pending_collateral, pending_debt) = self.pending_redistribution(vault_key);
         let mut vault_with_interest = vault.clone();
-        vault_with_interest.debt = accrual.new_debt;
+        vault_with_interest.collateral = vault.collateral + pending_collateral;
+        vault_with_interest.debt = accrual.new_debt + pending_debt;
 
-        let collateral_value = self.get_collateral_value(vault.collateral);
-        let icr_bps = self.calculate_icr(collateral_value, accrual.new_debt);
+        let collateral_value = self.get_collateral_value(vault_with_interest.collateral);
+        let icr_bps = self.calculate_icr(collateral_value, vault_with_interest.debt);
 
         Some(VaultInfo {
             vault: vault_with_interest,
@@ -452,6 +848,68 @@ impl BranchScspr {
         result
     }
 
+    /// Get vaults closest to liquidation for keepers
+    ///
+    /// Returns up to `max_count` (vault key, ICR bps) pairs ascending by ICR,
+    /// using pending interest, pending redistribution and the cached price.
+    /// Only the first MAX_RISK_SCAN vaults of the sorted list are scanned, so
+    /// this is a bounded approximation rather than a global sort.
+    pub fn get_riskiest_vaults(&self, max_count: u32) -> Vec<(VaultKey, u32)> {
+        let scanned: Vec<(VaultKey, u32)> = self
+            .get_sorted_vault_owners(MAX_RISK_SCAN)
+            .into_iter()
+            .filter_map(|key| self.get_vault(key.owner, key.id).map(|info| (key, info.icr_bps)))
+            .collect();
+        lowest_icr_first(scanned, max_count)
+    }
+
+    /// Get the debt-weighted average interest rate in bps
+    ///
+    /// Only the first MAX_RISK_SCAN vaults of the sorted list are scanned, so
+    /// this is a bounded approximation for large vault counts.
+    pub fn get_average_interest_rate_bps(&self) -> u32 {
+        weighted_average_rate_bps(&self.scan_rate_entries())
+    }
+
+    /// Get debt per interest rate bucket as (bucket lower bound bps, debt)
+    ///
+    /// Buckets split 0..MAX_INTEREST_RATE_BPS evenly. Bounded to the first
+    /// MAX_RISK_SCAN vaults like `get_average_interest_rate_bps`.
+    pub fn get_rate_distribution(&self, buckets: u32) -> Vec<(u32, U256)> {
+        rate_distribution(&self.scan_rate_entries(), buckets, MAX_INTEREST_RATE_BPS)
+    }
+
+    /// Get (debt, vault count) of vaults with a rate in `min_bps..=max_bps`
+    ///
+    /// Walks the sorted list from the lowest rate and stops past `max_bps`, so
+    /// redeemers can size a redemption to the cheapest vaults. Bounded to the
+    /// first MAX_RISK_SCAN vaults like `get_rate_distribution`.
+    pub fn get_debt_in_rate_range(&self, min_bps: u32, max_bps: u32) -> (U256, u64) {
+        let mut entries = Vec::new();
+        let mut current = self.sorted_head.get().flatten();
+        let mut scanned = 0u32;
+        while let Some(key) = current {
+            if scanned >= MAX_RISK_SCAN {
+                break;
+            }
+            scanned += 1;
+            let entry = match self.sorted_vaults.get(&key) {
+                Some(entry) => entry,
+                None => break,
+            };
+            if entry.interest_rate_bps > max_bps {
+                break;
+            }
+            if entry.interest_rate_bps >= min_bps {
+                if let Some(info) = self.get_vault(key.owner, key.id) {
+                    entries.push((entry.interest_rate_bps, info.vault.debt));
+                }
+            }
+            current = entry.next;
+        }
+        debt_in_rate_range(&entries, min_bps, max_bps)
+    }
+
     /// Get vault collateral amount (for redemption/liquidation queries)
     pub fn get_collateral(&self, owner: Address, vault_id: u64) -> U256 {
         let key = VaultKey { owner, id: vault_id };
@@ -464,6 +922,31 @@ impl BranchScspr {
         self.vaults.get(&key).map(|v| v.debt).unwrap_or(U256::zero())
     }
 
+    /// Get vault collateral including its pending redistribution share
+    pub fn get_effective_collateral(&self, owner: Address, vault_id: u64) -> U256 {
+        let key = VaultKey { owner, id: vault_id };
+        let stored = self.vaults.get(&key).map(|v| v.collateral).unwrap_or(U256::zero());
+        let (pending_collateral, _) = self.pending_redistribution(key);
+        stored + pending_collateral
+    }
+
+    /// Get vault debt including its pending redistribution share
+    pub fn get_effective_debt(&self, owner: Address, vault_id: u64) -> U256 {
+        let key = VaultKey { owner, id: vault_id };
+        let stored = self.vaults.get(&key).map(|v| v.debt).unwrap_or(U256::zero());
+        let (_, pending_debt) = self.pending_redistribution(key);
+        stored + pending_debt
+    }
+
+    /// Get redistribution trackers (l_collateral, l_debt, total_stakes)
+    pub fn get_redistribution_state(&self) -> (U256, U256, U256) {
+        (
+            self.state.redistribution.l_collateral.get().unwrap_or(U256::zero()),
+            self.state.redistribution.l_debt.get().unwrap_or(U256::zero()),
+            self.state.redistribution.total_stakes.get().unwrap_or(U256::zero()),
+        )
+    }
+
     /// Get vault interest rate in bps (for redemption ordering)
     pub fn get_interest_rate_bps(&self, owner: Address, vault_id: u64) -> u32 {
         let key = VaultKey { owner, id: vault_id };
@@ -498,16 +981,54 @@ impl BranchScspr {
         self.total_collateral.get().unwrap_or(U256::zero())
     }
 
+    /// Reconcile accounted collateral against the branch's actual holdings
+    ///
+    /// Accounted collateral is the vault total plus unclaimed liquidation
+    /// surplus; a non-zero delta means accounting drift (rounding, stuck or
+    /// unsolicited transfers).
+    pub fn get_collateral_balance_delta(&self) -> CollateralBalanceDelta {
+        let accounted = self.get_total_collateral() + self.get_total_claimable_surplus();
+        let actual = self.collateral_balance();
+        let (delta, is_surplus) = balance_delta(accounted, actual);
+        CollateralBalanceDelta { accounted, actual, delta, is_surplus }
+    }
+
     /// Get total debt in branch
     pub fn get_total_debt(&self) -> U256 {
         self.total_debt.get().unwrap_or(U256::zero())
     }
 
+    /// Get total debt including interest accrued since vaults were last touched
+    ///
+    /// `get_total_debt` returns the stored total, which lags until each vault
+    /// is touched. Pending interest is summed over the first MAX_RISK_SCAN
+    /// vaults of the sorted list, so for larger branches this is a lower bound.
+    pub fn get_total_debt_live(&self) -> U256 {
+        let now = self.env().get_block_time();
+        self.get_total_debt() + pending_interest(&self.scan_accrual_entries(), now)
+    }
+
+    /// Get USD value of total branch collateral at the cached price
+    pub fn get_total_collateral_value(&self) -> U256 {
+        self.get_collateral_value(self.total_collateral.get().unwrap_or(U256::zero()))
+    }
+
     /// Get vault count
     pub fn get_vault_count(&self) -> u64 {
         self.vault_count.get().unwrap_or(0)
     }
 
+    /// Key-based `reduce_collateral_for_redemption`, the form the RedemptionEngine
+    /// uses for both branches
+    pub fn reduce_collateral_for_redemption_by_key(
+        &mut self,
+        vault_key: VaultKey,
+        collateral_amount: U256,
+        debt_amount: U256,
+    ) {
+        self.reduce_collateral_for_redemption(vault_key.owner, vault_key.id, collateral_amount, debt_amount);
+    }
+
     /// Reduce vault collateral and debt during redemption
     /// Called by RedemptionEngine
     pub fn reduce_collateral_for_redemption(
@@ -517,7 +1038,7 @@ impl BranchScspr {
         collateral_amount: U256,
         debt_amount: U256,
     ) {
-        // TODO: Add caller authorization (only RedemptionEngine)
+        self.require_redemption_engine();
 
         let vault_key = VaultKey { owner, id: vault_id };
         let mut vault = match self.vaults.get(&vault_key) {
@@ -527,6 +1048,7 @@ impl BranchScspr {
         if vault.collateral.is_zero() && vault.debt.is_zero() {
             self.env().revert(CdpError::VaultNotFound);
         }
+        self.apply_pending_redistribution(vault_key, &mut vault);
 
         if collateral_amount > vault.collateral {
             self.env().revert(CdpError::InsufficientCollateral);
@@ -538,6 +1060,11 @@ impl BranchScspr {
         vault.collateral = vault.collateral - collateral_amount;
         vault.debt = vault.debt - debt_amount;
 
+        // The engine redeems dust-leaving vaults in full (or skips them)
+        if is_dust_position(vault.collateral, vault.debt, self.get_min_collateral()) {
+            self.env().revert(CdpError::BelowMinCollateral);
+        }
+
         let total_coll = self.total_collateral.get().unwrap_or(U256::zero());
         let total_debt = self.total_debt.get().unwrap_or(U256::zero());
         self.total_collateral.set(total_coll - collateral_amount);
@@ -550,14 +1077,15 @@ impl BranchScspr {
             self.remove_vault_from_owner_list(vault_key);
         }
 
+        self.update_stake(vault_key, vault.collateral);
         self.vaults.set(&vault_key, vault);
+        self.emit_totals_changed(TotalsChangeReason::Redeem);
     }
 
     /// Seize collateral from a vault during liquidation
     /// Called by LiquidationEngine
     pub fn seize_collateral(&mut self, owner: Address, vault_id: u64, amount: U256) {
-        // TODO: Add caller authorization (only LiquidationEngine)
-
+        self.require_liquidation_engine();
         let vault_key = VaultKey { owner, id: vault_id };
         let mut vault = match self.vaults.get(&vault_key) {
             Some(v) => v,
@@ -566,6 +1094,7 @@ impl BranchScspr {
         if vault.collateral.is_zero() && vault.debt.is_zero() {
             self.env().revert(CdpError::VaultNotFound);
         }
+        self.apply_pending_redistribution(vault_key, &mut vault);
 
         if amount > vault.collateral {
             self.env().revert(CdpError::InsufficientCollateral);
@@ -576,14 +1105,15 @@ impl BranchScspr {
         let total_coll = self.total_collateral.get().unwrap_or(U256::zero());
         self.total_collateral.set(total_coll - amount);
 
+        self.update_stake(vault_key, vault.collateral);
         self.vaults.set(&vault_key, vault);
+        self.emit_totals_changed(TotalsChangeReason::Liquidate);
     }
 
     /// Reduce debt on a vault during liquidation
     /// Called by LiquidationEngine (when SP absorbs debt)
     pub fn reduce_debt(&mut self, owner: Address, vault_id: u64, amount: U256) {
-        // TODO: Add caller authorization (only LiquidationEngine)
-
+        self.require_liquidation_engine();
         let vault_key = VaultKey { owner, id: vault_id };
         let mut vault = match self.vaults.get(&vault_key) {
             Some(v) => v,
@@ -592,6 +1122,7 @@ impl BranchScspr {
         if vault.collateral.is_zero() && vault.debt.is_zero() {
             self.env().revert(CdpError::VaultNotFound);
         }
+        self.apply_pending_redistribution(vault_key, &mut vault);
 
         if amount > vault.debt {
             self.env().revert(CdpError::RepayExceedsDebt);
@@ -603,21 +1134,22 @@ impl BranchScspr {
         self.total_debt.set(total_debt - amount);
 
         self.vaults.set(&vault_key, vault);
+        self.emit_totals_changed(TotalsChangeReason::Liquidate);
     }
 
     /// Close a vault during liquidation (full liquidation)
     /// Called by LiquidationEngine
     pub fn close_vault_for_liquidation(&mut self, owner: Address, vault_id: u64) {
-        // TODO: Add caller authorization (only LiquidationEngine)
-
+        self.require_liquidation_engine();
         let vault_key = VaultKey { owner, id: vault_id };
-        let vault = match self.vaults.get(&vault_key) {
+        let mut vault = match self.vaults.get(&vault_key) {
             Some(v) => v,
             None => self.env().revert(CdpError::VaultNotFound),
         };
         if vault.collateral.is_zero() && vault.debt.is_zero() {
             self.env().revert(CdpError::VaultNotFound);
         }
+        self.apply_pending_redistribution(vault_key, &mut vault);
 
         let total_coll = self.total_collateral.get().unwrap_or(U256::zero());
         let total_debt = self.total_debt.get().unwrap_or(U256::zero());
@@ -627,6 +1159,9 @@ impl BranchScspr {
         self.total_debt.set(total_debt - vault.debt);
         self.vault_count.set(count.saturating_sub(1));
 
+        // Collateral left after seizure belongs to the owner
+        self.credit_surplus(vault_key.owner, vault.collateral);
+
         self.remove_from_sorted_list(vault_key);
 
         let empty_vault = VaultData {
@@ -638,7 +1173,31 @@ impl BranchScspr {
             last_accrual_timestamp: 0,
         };
         self.vaults.set(&vault_key, empty_vault);
+        self.update_stake(vault_key, U256::zero());
         self.remove_vault_from_owner_list(vault_key);
+        self.emit_totals_changed(TotalsChangeReason::Liquidate);
+    }
+
+    /// Redistribute debt and collateral across active vaults pro-rata by stake
+    /// Called by LiquidationEngine when the Stability Pool cannot absorb the debt
+    pub fn redistribute_debt_and_collateral(&mut self, debt: U256, collateral: U256) {
+        self.require_liquidation_engine();
+        let total_stakes = self.state.redistribution.total_stakes.get().unwrap_or(U256::zero());
+        if total_stakes.is_zero() {
+            self.env().revert(CdpError::InvalidConfig);
+        }
+
+        let l_collateral = self.state.redistribution.l_collateral.get().unwrap_or(U256::zero());
+        let l_debt = self.state.redistribution.l_debt.get().unwrap_or(U256::zero());
+        self.state.redistribution.l_collateral.set(l_collateral + tracker_increment(collateral, total_stakes));
+        self.state.redistribution.l_debt.set(l_debt + tracker_increment(debt, total_stakes));
+
+        // Redistributed amounts stay in the branch and are applied lazily per vault
+        let total_coll = self.total_collateral.get().unwrap_or(U256::zero());
+        let total_debt = self.total_debt.get().unwrap_or(U256::zero());
+        self.total_collateral.set(total_coll + collateral);
+        self.total_debt.set(total_debt + debt);
+        self.emit_totals_changed(TotalsChangeReason::Liquidate);
     }
 
     /// Update CSPR/USD price (called by oracle adapter)
@@ -662,48 +1221,950 @@ impl BranchScspr {
         self.exchange_rate.get().unwrap_or(U256::from(RATE_SCALE))
     }
 
-    // ========== Internal helpers ==========
+    // ========== Liquidation Surplus ==========
 
-    fn require_router(&self) {
+    /// Get collateral surplus claimable by an owner after liquidation
+    pub fn get_claimable_surplus(&self, owner: Address) -> U256 {
+        self.state.claimable_surplus.get(&owner).unwrap_or(U256::zero())
+    }
+
+    /// Get total unclaimed liquidation surplus
+    pub fn get_total_claimable_surplus(&self) -> U256 {
+        self.state.total_claimable_surplus.get().unwrap_or(U256::zero())
+    }
+
+    /// Claim the caller's liquidation surplus
+    pub fn claim_surplus(&mut self) -> U256 {
         let caller = self.env().caller();
-        let router = self.router.get().unwrap_or_else(|| self.env().self_address());
-        if caller != router {
-            self.env().revert(CdpError::UnauthorizedProtocol);
+        let amount = self.get_claimable_surplus(caller);
+        if amount.is_zero() {
+            self.env().revert(CdpError::InsufficientCollateral);
         }
+
+        self.state.claimable_surplus.set(&caller, U256::zero());
+        let total_surplus = self.state.total_claimable_surplus.get().unwrap_or(U256::zero());
+        self.state.total_claimable_surplus.set(total_surplus.saturating_sub(amount));
+
+        self.transfer_collateral(caller, amount);
+        amount
     }
 
-    /// Composite pricing: P(stCSPR) = P(CSPR) * R
-    /// Where R is the stCSPR/CSPR exchange rate (CSPR_PER_SCSPR)
-    fn get_collateral_value(&self, collateral: U256) -> U256 {
-        let cspr_price = self.last_good_price.get().unwrap_or(U256::from(PRICE_SCALE));
-        let rate = self.exchange_rate.get().unwrap_or(U256::from(RATE_SCALE));
+    // ========== Configuration ==========
 
-        // stCSPR collateral (9 dec) * rate (18 dec) / RATE_SCALE (18) = CSPR equivalent (9 dec)
-        let cspr_equivalent = collateral * rate / U256::from(RATE_SCALE);
-        // CSPR equivalent (9 dec) * cspr_price (18 dec) / COLLATERAL_DECIMALS (9) = USD value (18 dec)
-        cspr_equivalent * cspr_price / U256::from(COLLATERAL_DECIMALS)
+    /// Get minimum collateralization ratio in bps
+    pub fn get_mcr(&self) -> u32 {
+        self.state.params.mcr_bps.get().unwrap_or(MCR_BPS)
     }
 
-    fn calculate_icr(&self, collateral_value: U256, debt: U256) -> u32 {
-        if debt.is_zero() {
-            return u32::MAX;
-        }
-        // ICR = (collateral_value * 10000) / debt
-        let scaled = collateral_value * U256::from(10000) / debt;
-        if scaled > U256::from(u32::MAX) {
-            u32::MAX
-        } else {
-            scaled.low_u32()
+    /// Set minimum collateralization ratio in bps (admin only)
+    ///
+    /// Raising the MCR can make existing vaults liquidatable immediately.
+    pub fn set_mcr(&mut self, mcr_bps: u32) {
+        self.require_registry_admin();
+
+        if mcr_bps < MIN_MCR_BPS {
+            self.env().revert(CdpError::InvalidConfig);
+        }
+        self.state.params.mcr_bps.set(mcr_bps);
+    }
+
+    /// Get minimum vault debt (gUSD, 18 decimals)
+    pub fn get_min_debt(&self) -> U256 {
+        self.state.params.min_debt
+            .get()
+            .unwrap_or(U256::from(MIN_DEBT_WHOLE) * U256::from(PRICE_SCALE))
+    }
+
+    /// Set minimum vault debt (admin only)
+    ///
+    /// Existing vaults below a raised floor are unaffected until they adjust.
+    pub fn set_min_debt(&mut self, min_debt: U256) {
+        self.require_registry_admin();
+
+        if min_debt.is_zero() {
+            self.env().revert(CdpError::InvalidConfig);
+        }
+        self.state.params.min_debt.set(min_debt);
+    }
+
+    /// Get minimum collateral for a vault carrying debt (0 = disabled)
+    pub fn get_min_collateral(&self) -> U256 {
+        self.state.params.min_collateral.get().unwrap_or(U256::zero())
+    }
+
+    /// Set minimum collateral for a vault carrying debt (admin only, 0 disables)
+    ///
+    /// Existing vaults below a raised floor are unaffected until they adjust.
+    pub fn set_min_collateral(&mut self, min_collateral: U256) {
+        self.require_registry_admin();
+        self.state.params.min_collateral.set(min_collateral);
+    }
+
+    /// Get guarded-launch limits with current vault count and TVL
+    pub fn get_launch_limits(&self) -> LaunchLimits {
+        let total_collateral = self.total_collateral.get().unwrap_or(U256::zero());
+        LaunchLimits {
+            max_vault_count: self.state.params.max_vault_count.get().unwrap_or(0),
+            max_tvl_usd: self.state.params.max_tvl_usd.get().unwrap_or(U256::zero()),
+            vault_count: self.vault_count.get().unwrap_or(0),
+            tvl_usd: self.get_collateral_value(total_collateral),
+        }
+    }
+
+    /// Set guarded-launch limits (admin only, 0 = uncapped)
+    ///
+    /// Caps only gate new vaults and new deposits; existing vaults over a
+    /// lowered cap can still repay, add collateral and close.
+    pub fn set_launch_limits(&mut self, max_vault_count: u64, max_tvl_usd: U256) {
+        self.require_registry_admin();
+        self.state.params.max_vault_count.set(max_vault_count);
+        self.state.params.max_tvl_usd.set(max_tvl_usd);
+    }
+
+    /// Get safety buffer in bps below MCR for max-borrow quotes
+    pub fn get_max_borrow_buffer_bps(&self) -> u32 {
+        self.state.params.max_borrow_buffer_bps.get().unwrap_or(DEFAULT_MAX_BORROW_BUFFER_BPS)
+    }
+
+    /// Set safety buffer in bps below MCR for max-borrow quotes (admin only)
+    pub fn set_max_borrow_buffer_bps(&mut self, buffer_bps: u32) {
+        self.require_registry_admin();
+
+        if buffer_bps > MAX_MAX_BORROW_BUFFER_BPS {
+            self.env().revert(CdpError::InvalidConfig);
+        }
+        self.state.params.max_borrow_buffer_bps.set(buffer_bps);
+    }
+
+    /// Accrue pending interest for a batch of vaults (callable by anyone)
+    ///
+    /// Idle vaults otherwise only accrue when touched, so total debt and
+    /// realizable interest lag. Closed or unknown keys are skipped; at most
+    /// MAX_POKE_BATCH keys per call. Returns the interest accrued.
+    pub fn poke_vaults(&mut self, vault_keys: Vec<VaultKey>) -> U256 {
+        if let Err(err) = check_poke_batch(&vault_keys) {
+            self.env().revert(err);
+        }
+
+        let before = self.state.total_accrued_interest.get_or_default();
+        for vault_key in vault_keys {
+            let mut vault = match self.vaults.get(&vault_key) {
+                Some(v) if !(v.collateral.is_zero() && v.debt.is_zero()) => v,
+                _ => continue,
+            };
+            self.accrue_vault_interest(vault_key, &mut vault);
+            self.vaults.set(&vault_key, vault);
+        }
+
+        let accrued = self.state.total_accrued_interest.get_or_default() - before;
+        if !accrued.is_zero() {
+            self.emit_totals_changed(TotalsChangeReason::Interest);
+        }
+        accrued
+    }
+
+    /// Interest accrued into debt that has not been realized yet
+    pub fn get_total_accrued_interest(&self) -> U256 {
+        self.state.total_accrued_interest.get_or_default()
+    }
+
+    /// Mint accrued, unrealized interest as gUSD to the interest recipient
+    ///
+    /// Callable by anyone. The minted gUSD is matched by the interest already
+    /// in vault debt, so supply stays backed. Revenue sent to the treasury is
+    /// recorded there as interest fees. Returns the amount minted.
+    pub fn realize_interest_to_treasury(&mut self) -> U256 {
+        let unrealized = self.state.total_accrued_interest.get_or_default();
+        let amount = realizable_interest(unrealized, self.total_debt.get().unwrap_or(U256::zero()));
+        if amount.is_zero() {
+            return U256::zero();
+        }
+        self.state.total_accrued_interest.set(unrealized - amount);
+
+        let treasury_addr = self.get_treasury_address();
+        let recipient = self.state.params.interest_recipient.get().unwrap_or(treasury_addr);
+        let mint_args = runtime_args! {
+            "to" => recipient,
+            "amount" => amount
+        };
+        let mint_call = CallDef::new("mint", true, mint_args);
+        self.env().call_contract::<()>(self.get_stablecoin_address(), mint_call);
+
+        if recipient == treasury_addr {
+            let record_call = CallDef::new("record_interest_fee", true, runtime_args! { "amount" => amount });
+            self.env().call_contract::<()>(treasury_addr, record_call);
+        }
+        amount
+    }
+
+    /// Get the configured interest recipient (`None` = treasury)
+    pub fn get_interest_recipient(&self) -> Option<Address> {
+        self.state.params.interest_recipient.get()
+    }
+
+    /// Send realized interest somewhere other than the treasury (admin only)
+    ///
+    /// Borrowing fees keep going to the treasury.
+    pub fn set_interest_recipient(&mut self, recipient: Address) {
+        self.require_registry_admin();
+        self.state.params.interest_recipient.set(recipient);
+    }
+
+    /// Get upfront borrowing fee in bps
+    pub fn get_borrow_fee_bps(&self) -> u32 {
+        self.state.params.borrow_fee_bps.get().unwrap_or(0)
+    }
+
+    /// Set upfront borrowing fee in bps (admin only)
+    ///
+    /// A non-zero fee requires this branch to be an authorized gUSD minter
+    /// and treasury depositor.
+    pub fn set_borrow_fee_bps(&mut self, fee_bps: u32) {
+        self.require_registry_admin();
+
+        if fee_bps > MAX_BORROW_FEE_BPS {
+            self.env().revert(CdpError::InvalidConfig);
+        }
+        self.state.params.borrow_fee_bps.set(fee_bps);
+    }
+
+    /// Get minimum seconds between interest rate changes per vault
+    pub fn get_rate_change_cooldown_seconds(&self) -> u64 {
+        self.state.params.rate_change_cooldown_seconds.get().unwrap_or(0)
+    }
+
+    /// Set minimum seconds between interest rate changes per vault (admin only)
+    ///
+    /// Stops borrowers from dropping their rate just before redemptions and
+    /// raising it straight after.
+    pub fn set_rate_change_cooldown_seconds(&mut self, cooldown_seconds: u64) {
+        self.require_registry_admin();
+
+        if cooldown_seconds > MAX_RATE_CHANGE_COOLDOWN_SECONDS {
+            self.env().revert(CdpError::InvalidConfig);
+        }
+        self.state.params.rate_change_cooldown_seconds.set(cooldown_seconds);
+    }
+
+    /// Get interest-free grace period for newly opened vaults
+    pub fn get_grace_period_seconds(&self) -> u64 {
+        self.state.params.grace_period_seconds.get().unwrap_or(0)
+    }
+
+    /// Set interest-free grace period for newly opened vaults (admin only)
+    ///
+    /// Applies to every vault still inside its window, including ones opened
+    /// before the change.
+    pub fn set_grace_period_seconds(&mut self, grace_period_seconds: u64) {
+        self.require_registry_admin();
+
+        if grace_period_seconds > MAX_GRACE_PERIOD_SECONDS {
+            self.env().revert(CdpError::InvalidConfig);
+        }
+        self.state.params.grace_period_seconds.set(grace_period_seconds);
+    }
+
+    /// Get interest rate decrease fee in bps
+    pub fn get_rate_adjust_fee_bps(&self) -> u32 {
+        self.state.params.rate_adjust_fee_bps.get().unwrap_or(0)
+    }
+
+    /// Set interest rate decrease fee in bps (admin only)
+    ///
+    /// Charged on vault debt whenever a borrower lowers their rate; raising
+    /// the rate is free.
+    pub fn set_rate_adjust_fee_bps(&mut self, fee_bps: u32) {
+        self.require_registry_admin();
+
+        if fee_bps > MAX_BORROW_FEE_BPS {
+            self.env().revert(CdpError::InvalidConfig);
+        }
+        self.state.params.rate_adjust_fee_bps.set(fee_bps);
+    }
+
+    /// Get collateral withdrawal fee in bps
+    pub fn get_withdrawal_fee_bps(&self) -> u32 {
+        self.state.params.withdrawal_fee_bps.get().unwrap_or(0)
+    }
+
+    /// Set collateral withdrawal fee in bps (admin only)
+    ///
+    /// Charged on collateral withdrawn through `adjust_vault` and sent to the
+    /// treasury, to discourage vault churn. Closing a vault is free.
+    pub fn set_withdrawal_fee_bps(&mut self, fee_bps: u32) {
+        self.require_registry_admin();
+
+        if fee_bps > MAX_BORROW_FEE_BPS {
+            self.env().revert(CdpError::InvalidConfig);
+        }
+        self.state.params.withdrawal_fee_bps.set(fee_bps);
+    }
+
+    /// Get the timestamp of a vault's last interest rate change
+    pub fn get_last_rate_change_timestamp(&self, owner: Address, vault_id: u64) -> u64 {
+        self.state.last_rate_change_timestamp
+            .get(&VaultKey { owner, id: vault_id })
+            .unwrap_or(0)
+    }
+
+    /// Get stCSPR collateral token address
+    pub fn get_scspr_token(&self) -> Option<Address> {
+        self.state.scspr_token.get()
+    }
+
+    /// Set stCSPR collateral token address (admin only)
+    pub fn set_scspr_token(&mut self, scspr_token: Address) {
+        self.require_registry_admin();
+        self.state.scspr_token.set(scspr_token);
+    }
+
+    // ========== Token Recovery ==========
+
+    /// Sweep a token accidentally sent to the branch (admin only)
+    ///
+    /// stCSPR can only be swept above `total_collateral` plus unclaimed
+    /// liquidation surplus; the branch holds no gUSD, so any other token can
+    /// be swept in full.
+    pub fn sweep_token(&mut self, token: Address, to: Address, amount: U256) {
+        self.require_registry_admin();
+
+        let collateral_token = match self.state.scspr_token.get() {
+            Some(addr) => addr,
+            None => self.env().revert(CdpError::InvalidConfig),
+        };
+        let accounted = if token == collateral_token {
+            self.total_collateral.get().unwrap_or(U256::zero()) + self.get_total_claimable_surplus()
+        } else {
+            U256::zero()
+        };
+        recovery::sweep_token(&self.env(), token, to, amount, accounted);
+    }
+
+    // ========== Vault Migration ==========
+
+    /// Open a vault migration window with another branch version (admin only)
+    ///
+    /// On the old branch `peer` is the new branch vaults are exported to; on the
+    /// new branch it is the old branch imports are accepted from.
+    pub fn begin_migration(&mut self, peer: Address) {
+        self.require_registry_admin();
+        self.state.migration_peer.set(peer);
+        self.state.migrating.set(true);
+    }
+
+    /// Close the vault migration window (admin only)
+    pub fn end_migration(&mut self) {
+        self.require_registry_admin();
+        self.state.migrating.set(false);
+    }
+
+    /// Whether a vault migration window is open
+    pub fn is_migrating(&self) -> bool {
+        self.state.migrating.get_or_default()
+    }
+
+    /// Branch on the other side of the current or last migration
+    pub fn get_migration_peer(&self) -> Option<Address> {
+        self.state.migration_peer.get()
+    }
+
+    /// Move a vault to the migration peer (admin only, while migrating)
+    ///
+    /// Interest and pending redistribution are settled first, then the vault is
+    /// removed here, its collateral sent to the peer and the peer's
+    /// `import_vault` called with the same key. Returns the exported vault.
+    pub fn export_vault(&mut self, vault_key: VaultKey) -> VaultData {
+        self.require_registry_admin();
+        if !self.is_migrating() {
+            self.env().revert(CdpError::MigrationNotActive);
+        }
+        let peer = match self.state.migration_peer.get() {
+            Some(addr) => addr,
+            None => self.env().revert(CdpError::InvalidConfig),
+        };
+
+        let mut vault = match self.vaults.get(&vault_key) {
+            Some(v) => v,
+            None => self.env().revert(CdpError::VaultNotFound),
+        };
+        if vault.collateral.is_zero() && vault.debt.is_zero() {
+            self.env().revert(CdpError::VaultNotFound);
+        }
+        self.accrue_vault_interest(vault_key, &mut vault);
+        self.apply_pending_redistribution(vault_key, &mut vault);
+
+        let created_at = self.vault_created_at(&vault_key);
+        self.close_vault_internal(vault_key, vault.clone());
+        if !vault.collateral.is_zero() {
+            self.transfer_collateral(peer, vault.collateral);
+        }
+        let import_args = runtime_args! {
+            "vault_key" => vault_key,
+            "vault" => vault.clone(),
+            "created_at" => created_at
+        };
+        self.env().call_contract::<()>(peer, CallDef::new("import_vault", true, import_args));
+        self.emit_totals_changed(TotalsChangeReason::Migrate);
+        vault
+    }
+
+    /// Accept a vault exported by the migration peer (peer only, while migrating)
+    ///
+    /// The vault keeps its key, position, rate, accrual and open timestamps;
+    /// the owner's next vault id is bumped past it so new ids never collide.
+    pub fn import_vault(&mut self, vault_key: VaultKey, vault: VaultData, created_at: u64) {
+        let caller_is_peer = self.state.migration_peer.get() == Some(self.env().caller());
+        let vault_exists = self
+            .vaults
+            .get(&vault_key)
+            .map(|v| !v.collateral.is_zero() || !v.debt.is_zero())
+            .unwrap_or(false);
+        if let Err(err) = check_vault_import(self.is_migrating(), caller_is_peer, vault_exists) {
+            self.env().revert(err);
+        }
+
+        let next_id = self.next_vault_id.get(&vault_key.owner).unwrap_or(1);
+        if vault_key.id >= next_id {
+            self.next_vault_id.set(&vault_key.owner, vault_key.id.saturating_add(1));
+        }
+        self.insert_vault(vault_key, VaultData { owner: vault_key.owner, collateral_id: CollateralId::SCSPR, ..vault }, created_at);
+        self.emit_totals_changed(TotalsChangeReason::Migrate);
+    }
+
+    // ========== Sorted List Maintenance ==========
+
+    /// Verify sorted list integrity (admin only)
+    ///
+    /// Walks head→tail and tail→head checking link symmetry, monotonic
+    /// interest rates and that every active vault is linked exactly once.
+    pub fn verify_sorted_list(&self) -> bool {
+        self.require_registry_admin();
+
+        verify_links(
+            self.sorted_head.get().flatten(),
+            self.sorted_tail.get().flatten(),
+            self.vault_count.get().unwrap_or(0),
+            |key| {
+                self.sorted_vaults.get(key).map(|entry| LinkView {
+                    interest_rate_bps: entry.interest_rate_bps,
+                    prev: entry.prev,
+                    next: entry.next,
+                })
+            },
+        )
+    }
+
+    /// Rebuild the sorted list from the vaults map (admin only)
+    ///
+    /// Relinks every active vault in redemption order within one call, so no
+    /// open, close, rate change or redemption ever sees a half-built list.
+    /// Returns the number of vaults linked.
+    pub fn rebuild_sorted_list(&mut self) -> u64 {
+        self.require_registry_admin();
+
+        let total = self.state.all_vault_count.get().unwrap_or(0);
+        let mut nodes = Vec::new();
+        for index in 0..total {
+            if let Some(vault_key) = self.state.all_vault_keys.get(&index) {
+                if let Some(vault) = self.vaults.get(&vault_key) {
+                    if !(vault.collateral.is_zero() && vault.debt.is_zero()) {
+                        nodes.push((vault_key, vault.interest_rate_bps, self.vault_created_at(&vault_key)));
+                    }
+                }
+            }
+        }
+
+        let links = relink(nodes);
+        self.sorted_head.set(links.first().map(|(key, _)| *key));
+        self.sorted_tail.set(links.last().map(|(key, _)| *key));
+        for (vault_key, link) in &links {
+            let entry = SortedVaultEntry {
+                vault_key: *vault_key,
+                interest_rate_bps: link.interest_rate_bps,
+                prev: link.prev,
+                next: link.next,
+            };
+            self.sorted_vaults.set(vault_key, entry);
+        }
+        links.len() as u64
+    }
+
+    // ========== Internal helpers ==========
+
+    /// Revert if adding `added_collateral` would push branch TVL past the cap
+    fn require_within_tvl_cap(&self, added_collateral: U256) {
+        if self.exceeds_tvl_cap(added_collateral) {
+            self.env().revert(CdpError::TvlCapReached);
+        }
+    }
+
+    fn require_within_supply_cap(&self, minted: U256) {
+        let stablecoin_addr = self.get_stablecoin_address();
+        let cap_call = CallDef::new("get_supply_cap", false, runtime_args! {});
+        let cap: U256 = self.env().call_contract(stablecoin_addr, cap_call);
+        if cap.is_zero() {
+            return;
+        }
+        let supply_call = CallDef::new("total_supply", false, runtime_args! {});
+        let total_supply: U256 = self.env().call_contract(stablecoin_addr, supply_call);
+        if let Err(err) = check_supply_cap(total_supply, minted, cap) {
+            self.env().revert(err);
+        }
+    }
+
+    fn exceeds_tvl_cap(&self, added_collateral: U256) -> bool {
+        let max_tvl_usd = self.state.params.max_tvl_usd.get().unwrap_or(U256::zero());
+        if max_tvl_usd.is_zero() {
+            return false;
+        }
+        let total_collateral = self.total_collateral.get().unwrap_or(U256::zero());
+        let tvl_after = self.get_collateral_value(total_collateral + added_collateral);
+        exceeds_launch_cap(tvl_after, max_tvl_usd)
+    }
+
+    /// Upfront borrowing fee charged by an adjustment (zero for repayments)
+    fn adjustment_fee(&self, params: &AdjustVaultParams) -> U256 {
+        if params.debt_is_repay {
+            U256::zero()
+        } else {
+            borrow_fee(params.debt_delta, self.get_borrow_fee_bps())
+        }
+    }
+
+    /// New vault position after an adjustment, or the error `adjust_vault` reverts with
+    ///
+    /// A fully emptied vault skips the min debt, dust, cap and MCR checks.
+    fn check_adjustment(
+        &self,
+        vault: &VaultData,
+        params: &AdjustVaultParams,
+        fee: U256,
+    ) -> Result<(U256, U256), CdpError> {
+        let (new_collateral, new_debt) = adjusted_position(vault.collateral, vault.debt, params, fee)?;
+        if new_collateral.is_zero() && new_debt.is_zero() {
+            return Ok((new_collateral, new_debt));
+        }
+
+        // Check minimum debt (if any debt remains)
+        if is_below_min_debt(new_debt, self.get_min_debt()) {
+            return Err(CdpError::BelowMinDebt);
+        }
+
+        // Indebted vaults must keep enough collateral to stay worth liquidating
+        if is_dust_position(new_collateral, new_debt, self.get_min_collateral()) {
+            return Err(CdpError::BelowMinCollateral);
+        }
+
+        // Top-ups and repayments stay allowed past the TVL cap
+        if adjustment_hits_tvl_cap(params) && self.exceeds_tvl_cap(params.collateral_delta) {
+            return Err(CdpError::TvlCapReached);
+        }
+
+        let collateral_value = self.get_collateral_value(new_collateral);
+        if self.calculate_icr(collateral_value, new_debt) < self.get_mcr() {
+            return Err(CdpError::BelowMcr);
+        }
+        Ok((new_collateral, new_debt))
+    }
+
+    /// Whether the router's safe mode would reject this adjustment
+    fn is_blocked_by_safe_mode(&self, params: &AdjustVaultParams) -> bool {
+        if !blocked_in_safe_mode(params) {
+            return false;
+        }
+        let router_addr = match self.router.get() {
+            Some(addr) => addr,
+            None => return false,
+        };
+        let call_def = CallDef::new("get_safe_mode", false, runtime_args! {});
+        let state: SafeModeState = self.env().call_contract(router_addr, call_def);
+        let effective_call = CallDef::new("safe_mode_effective", false, runtime_args! {});
+        let effective: bool = self.env().call_contract(router_addr, effective_call);
+        safe_mode_blocks_adjustment(params, state.is_active, effective)
+    }
+
+    /// Accrual window start for a vault, skipping its interest-free grace period
+    fn accrual_start_for(&self, vault_key: &VaultKey, vault: &VaultData) -> u64 {
+        accrual_start(vault.last_accrual_timestamp, self.vault_created_at(vault_key), self.get_grace_period_seconds())
+    }
+
+    /// Open timestamp of a vault (zero if opened before it was recorded)
+    fn vault_created_at(&self, vault_key: &VaultKey) -> u64 {
+        self.state.vault_created_at.get(vault_key).unwrap_or(0)
+    }
+
+    fn require_router(&self) {
+        let caller = self.env().caller();
+        let router = self.router.get().unwrap_or_else(|| self.env().self_address());
+        if caller != router {
+            self.env().revert(CdpError::UnauthorizedProtocol);
+        }
+    }
+
+    /// Revert unless called by the liquidation engine registered in the registry
+    fn require_liquidation_engine(&self) {
+        if self.env().caller() != self.get_liquidation_engine_address() {
+            self.env().revert(CdpError::UnauthorizedProtocol);
+        }
+    }
+
+    /// Revert unless called by the redemption engine registered in the registry
+    fn require_redemption_engine(&self) {
+        if self.env().caller() != self.get_redemption_engine_address() {
+            self.env().revert(CdpError::UnauthorizedProtocol);
+        }
+    }
+
+    fn require_registry_admin(&self) {
+        let caller = self.env().caller();
+        let registry_addr = self.registry.get();
+
+        if registry_addr.is_none() {
+            self.env().revert(CdpError::InvalidConfig);
+        }
+
+        let args = runtime_args! {
+            "caller" => caller
+        };
+        let call_def = CallDef::new("is_admin", false, args);
+        let is_admin: bool = self.env().call_contract(registry_addr.unwrap(), call_def);
+
+        if !is_admin {
+            self.env().revert(CdpError::UnauthorizedProtocol);
+        }
+    }
+
+    /// Mint a borrowing fee to the treasury and record it
+    fn credit_borrow_fee(&mut self, fee: U256) {
+        if fee.is_zero() {
+            return;
+        }
+
+        let registry_addr = match self.registry.get() {
+            Some(addr) => addr,
+            None => self.env().revert(CdpError::InvalidConfig),
+        };
+        let treasury_call = CallDef::new("get_treasury", false, runtime_args! {});
+        let treasury: Option<Address> = self.env().call_contract(registry_addr, treasury_call);
+        let stablecoin_call = CallDef::new("get_stablecoin", false, runtime_args! {});
+        let stablecoin: Option<Address> = self.env().call_contract(registry_addr, stablecoin_call);
+        let (treasury_addr, stablecoin_addr) = match (treasury, stablecoin) {
+            (Some(t), Some(s)) => (t, s),
+            _ => self.env().revert(CdpError::InvalidConfig),
+        };
+
+        let mint_args = runtime_args! {
+            "to" => treasury_addr,
+            "amount" => fee
+        };
+        let mint_call = CallDef::new("mint", true, mint_args);
+        self.env().call_contract::<()>(stablecoin_addr, mint_call);
+
+        let record_args = runtime_args! {
+            "amount" => fee
+        };
+        let record_call = CallDef::new("record_borrowing_fee", true, record_args);
+        self.env().call_contract::<()>(treasury_addr, record_call);
+    }
+
+    /// Add accrued interest to total debt and to the unrealized interest counter
+    fn book_accrued_interest(&mut self, interest: U256) {
+        let current_debt = self.total_debt.get().unwrap_or(U256::zero());
+        self.total_debt.set(current_debt + interest);
+        let unrealized = self.state.total_accrued_interest.get_or_default();
+        self.state.total_accrued_interest.set(unrealized + interest);
+    }
+
+    /// Accrue a vault's pending interest into its debt and the branch total
+    fn accrue_vault_interest(&mut self, vault_key: VaultKey, vault: &mut VaultData) {
+        let current_time = self.env().get_block_time();
+        let accrual = accrue_interest(
+            vault.debt,
+            vault.interest_rate_bps,
+            self.accrual_start_for(&vault_key, vault),
+            current_time,
+        );
+        vault.debt = accrual.new_debt;
+        vault.last_accrual_timestamp = current_time;
+        if accrual.interest_accrued > U256::zero() {
+            self.book_accrued_interest(accrual.interest_accrued);
+            let seq = self.next_event_seq();
+            self.env().emit_event(InterestAccrued {
+                seq,
+                owner: vault_key.owner,
+                vault_id: vault_key.id,
+                amount: accrual.interest_accrued,
+                new_debt: accrual.new_debt,
+            });
+        }
+    }
+
+    /// Advance the event sequence counter and return the new value
+    fn next_event_seq(&mut self) -> u64 {
+        let seq = next_event_seq(self.state.event_seq.get_or_default());
+        self.state.event_seq.set(seq);
+        seq
+    }
+
+    fn totals(&self) -> BranchTotals {
+        BranchTotals {
+            total_collateral: self.total_collateral.get().unwrap_or(U256::zero()),
+            total_debt: self.total_debt.get().unwrap_or(U256::zero()),
+            vault_count: self.vault_count.get().unwrap_or(0),
+        }
+    }
+
+    fn set_totals(&mut self, totals: BranchTotals) {
+        self.total_collateral.set(totals.total_collateral);
+        self.total_debt.set(totals.total_debt);
+        self.vault_count.set(totals.vault_count);
+    }
+
+    /// Emit `TotalsChanged` with the branch totals as they stand now
+    fn emit_totals_changed(&mut self, reason: TotalsChangeReason) {
+        let seq = self.next_event_seq();
+        let totals = self.totals();
+        self.env().emit_event(TotalsChanged {
+            seq,
+            total_collateral: totals.total_collateral,
+            total_debt: totals.total_debt,
+            vault_count: totals.vault_count,
+            reason,
+        });
+    }
+
+    fn get_stablecoin_address(&self) -> Address {
+        let registry_addr = match self.registry.get() {
+            Some(addr) => addr,
+            None => self.env().revert(CdpError::InvalidConfig),
+        };
+        let stablecoin_call = CallDef::new("get_stablecoin", false, runtime_args! {});
+        let stablecoin: Option<Address> = self.env().call_contract(registry_addr, stablecoin_call);
+        match stablecoin {
+            Some(addr) => addr,
+            None => self.env().revert(CdpError::InvalidConfig),
+        }
+    }
+
+    fn credit_surplus(&mut self, owner: Address, amount: U256) {
+        if amount.is_zero() {
+            return;
+        }
+        let owed = self.state.claimable_surplus.get(&owner).unwrap_or(U256::zero());
+        self.state.claimable_surplus.set(&owner, owed + amount);
+        let total_surplus = self.state.total_claimable_surplus.get().unwrap_or(U256::zero());
+        self.state.total_claimable_surplus.set(total_surplus + amount);
+    }
+
+    fn transfer_collateral(&mut self, recipient: Address, amount: U256) {
+        if amount.is_zero() {
+            return;
+        }
+        let scspr_addr = match self.state.scspr_token.get() {
+            Some(addr) => addr,
+            None => self.env().revert(CdpError::InvalidConfig),
+        };
+        let transfer_args = runtime_args! {
+            "recipient" => recipient,
+            "amount" => amount
+        };
+        let transfer_call = CallDef::new("transfer", true, transfer_args);
+        let success: bool = self.env().call_contract(scspr_addr, transfer_call);
+        if !success {
+            self.env().revert(CdpError::InsufficientTokenBalance);
+        }
+    }
+
+    /// Pull `amount` stCSPR from `from` into the branch (CEP-18 transfer_from)
+    ///
+    /// Returns the amount actually received, measured from the branch balance
+    /// so fee-on-transfer tokens are credited net.
+    fn receive_collateral(&mut self, from: Address, amount: U256) -> U256 {
+        if amount.is_zero() {
+            return U256::zero();
+        }
+        let scspr_addr = match self.state.scspr_token.get() {
+            Some(addr) => addr,
+            None => self.env().revert(CdpError::InvalidConfig),
+        };
+
+        let before = self.collateral_balance();
+        let transfer_args = runtime_args! {
+            "owner" => from,
+            "recipient" => self.env().self_address(),
+            "amount" => amount
+        };
+        let transfer_call = CallDef::new("transfer_from", true, transfer_args);
+        let success: bool = self.env().call_contract(scspr_addr, transfer_call);
+        if !success {
+            self.env().revert(CdpError::TokenTransferFailed);
+        }
+
+        let snapshot = BalanceSnapshot {
+            before,
+            after: self.collateral_balance(),
+        };
+        let received = net_received(&snapshot, amount);
+        if received.is_zero() {
+            self.env().revert(CdpError::TokenTransferFailed);
+        }
+        received
+    }
+
+    /// stCSPR held by the branch
+    fn collateral_balance(&self) -> U256 {
+        let scspr_addr = match self.state.scspr_token.get() {
+            Some(addr) => addr,
+            None => self.env().revert(CdpError::InvalidConfig),
+        };
+        let balance_args = runtime_args! {
+            "owner" => self.env().self_address()
+        };
+        let balance_call = CallDef::new("balance_of", false, balance_args);
+        self.env().call_contract(scspr_addr, balance_call)
+    }
+
+    /// Send withdrawn collateral to `owner`, less the withdrawal fee paid to the treasury
+    fn pay_out_withdrawal(&mut self, owner: Address, amount: U256) {
+        if amount.is_zero() {
+            return;
+        }
+        let fee = withdrawal_fee(amount, self.get_withdrawal_fee_bps());
+        if !fee.is_zero() {
+            let treasury = self.get_treasury_address();
+            self.transfer_collateral(treasury, fee);
+        }
+        self.transfer_collateral(owner, amount - fee);
+    }
+
+    fn get_treasury_address(&self) -> Address {
+        let registry_addr = match self.registry.get() {
+            Some(addr) => addr,
+            None => self.env().revert(CdpError::InvalidConfig),
+        };
+        let treasury_call = CallDef::new("get_treasury", false, runtime_args! {});
+        let treasury: Option<Address> = self.env().call_contract(registry_addr, treasury_call);
+        match treasury {
+            Some(addr) => addr,
+            None => self.env().revert(CdpError::InvalidConfig),
+        }
+    }
+
+    fn get_liquidation_engine_address(&self) -> Address {
+        let registry_addr = match self.registry.get() {
+            Some(addr) => addr,
+            None => self.env().revert(CdpError::InvalidConfig),
+        };
+        let engine_call = CallDef::new("get_liquidation_engine", false, runtime_args! {});
+        let engine: Option<Address> = self.env().call_contract(registry_addr, engine_call);
+        match engine {
+            Some(addr) => addr,
+            None => self.env().revert(CdpError::InvalidConfig),
+        }
+    }
+
+    fn get_redemption_engine_address(&self) -> Address {
+        let registry_addr = match self.registry.get() {
+            Some(addr) => addr,
+            None => self.env().revert(CdpError::InvalidConfig),
+        };
+        match registry::try_resolve(&self.env(), registry_addr, ComponentId::RedemptionEngine) {
+            Some(addr) => addr,
+            None => self.env().revert(CdpError::InvalidConfig),
+        }
+    }
+
+    /// Liquidation penalty in bps, read from the liquidation engine
+    fn get_liquidation_penalty_bps(&self) -> u32 {
+        let engine_addr = self.get_liquidation_engine_address();
+        let penalty_call = CallDef::new("get_liquidation_penalty", false, runtime_args! {});
+        self.env().call_contract(engine_addr, penalty_call)
+    }
+
+    /// (rate bps, current debt) of the first MAX_RISK_SCAN vaults in sorted order
+    fn scan_rate_entries(&self) -> Vec<(u32, U256)> {
+        self.get_sorted_vault_owners(MAX_RISK_SCAN)
+            .into_iter()
+            .filter_map(|key| self.get_vault(key.owner, key.id))
+            .map(|info| (info.vault.interest_rate_bps, info.vault.debt))
+            .collect()
+    }
+
+    /// (stored debt, rate bps, accrual start) of the first MAX_RISK_SCAN vaults in sorted order
+    fn scan_accrual_entries(&self) -> Vec<(U256, u32, u64)> {
+        self.get_sorted_vault_owners(MAX_RISK_SCAN)
+            .into_iter()
+            .filter_map(|key| self.vaults.get(&key).map(|vault| (key, vault)))
+            .map(|(key, vault)| (vault.debt, vault.interest_rate_bps, self.accrual_start_for(&key, &vault)))
+            .collect()
+    }
+
+    /// Composite pricing: P(stCSPR) = P(CSPR) * R
+    /// Where R is the stCSPR/CSPR exchange rate (CSPR_PER_SCSPR)
+    fn get_collateral_value(&self, collateral: U256) -> U256 {
+        let cspr_price = self.last_good_price.get().unwrap_or(U256::from(PRICE_SCALE));
+        let rate = self.exchange_rate.get().unwrap_or(U256::from(RATE_SCALE));
+
+        // stCSPR -> CSPR equivalent (9 dec) -> USD value (18 dec)
+        let cspr_equivalent = apply_rate(collateral, rate);
+        collateral_to_value(cspr_equivalent, cspr_price)
+    }
+
+    fn calculate_icr(&self, collateral_value: U256, debt: U256) -> u32 {
+        if debt.is_zero() {
+            return u32::MAX;
+        }
+        // ICR = (collateral_value * 10000) / debt
+        let scaled = collateral_value * U256::from(10000) / debt;
+        if scaled > U256::from(u32::MAX) {
+            u32::MAX
+        } else {
+            scaled.low_u32()
         }
     }
 
     fn check_mcr(&self, collateral_value: U256, debt: U256) {
         let icr = self.calculate_icr(collateral_value, debt);
-        if icr < MCR_BPS {
+        if icr < self.get_mcr() {
             self.env().revert(CdpError::BelowMcr);
         }
     }
 
+    fn pending_redistribution(&self, vault_key: VaultKey) -> (U256, U256) {
+        let snapshot = self.state.redistribution.redistribution_snapshots.get(&vault_key).unwrap_or_default();
+        let l_collateral = self.state.redistribution.l_collateral.get().unwrap_or(U256::zero());
+        let l_debt = self.state.redistribution.l_debt.get().unwrap_or(U256::zero());
+        (
+            pending_reward(snapshot.stake, l_collateral, snapshot.l_collateral),
+            pending_reward(snapshot.stake, l_debt, snapshot.l_debt),
+        )
+    }
+
+    /// Move the vault's pending redistribution share into its stored values
+    fn apply_pending_redistribution(&mut self, vault_key: VaultKey, vault: &mut VaultData) {
+        let (pending_collateral, pending_debt) = self.pending_redistribution(vault_key);
+        vault.collateral += pending_collateral;
+        vault.debt += pending_debt;
+        self.update_stake(vault_key, vault.collateral);
+    }
+
+    /// Set the vault stake and snapshot the current trackers
+    fn update_stake(&mut self, vault_key: VaultKey, new_stake: U256) {
+        let old_stake = self.state.redistribution.redistribution_snapshots
+            .get(&vault_key)
+            .map(|s| s.stake)
+            .unwrap_or(U256::zero());
+        let total_stakes = self.state.redistribution.total_stakes.get().unwrap_or(U256::zero());
+        self.state.redistribution.total_stakes.set(total_stakes - old_stake + new_stake);
+
+        let snapshot = RedistributionSnapshot {
+            stake: new_stake,
+            l_collateral: self.state.redistribution.l_collateral.get().unwrap_or(U256::zero()),
+            l_debt: self.state.redistribution.l_debt.get().unwrap_or(U256::zero()),
+        };
+        self.state.redistribution.redistribution_snapshots.set(&vault_key, snapshot);
+    }
+
     fn remove_vault_from_owner_list(&mut self, vault_key: VaultKey) {
         let owner = vault_key.owner;
         let count = self.user_vault_count.get(&owner).unwrap_or(0);
@@ -711,7 +2172,7 @@ impl BranchScspr {
             return;
         }
 
-        let index = self.vault_indices.get(&vault_key).unwrap_or(u64::MAX);
+        let index = self.state.vault_indices.get(&vault_key).unwrap_or(u64::MAX);
         if index == u64::MAX || index >= count {
             return;
         }
@@ -725,18 +2186,22 @@ impl BranchScspr {
                 self.user_vault_ids.set(&move_key, last_id);
 
                 let moved_vault_key = VaultKey { owner, id: last_id };
-                self.vault_indices.set(&moved_vault_key, index);
+                self.state.vault_indices.set(&moved_vault_key, index);
             }
         }
 
         // Best-effort clear last slot (ignored because count is decremented).
         let last_key = UserVaultIndex { owner, index: last_index };
         self.user_vault_ids.set(&last_key, 0);
-        self.vault_indices.set(&vault_key, u64::MAX);
+        self.state.vault_indices.set(&vault_key, u64::MAX);
         self.user_vault_count.set(&owner, last_index);
     }
 
-    fn insert_into_sorted_list(&mut self, vault_key: VaultKey, interest_rate_bps: u32) {
+    /// Link a vault into the sorted list at its redemption position
+    ///
+    /// Ordered by ascending rate; equal rates are ordered oldest `created_at`
+    /// first (see `redeemed_before`), so ties don't depend on insertion order.
+    fn insert_into_sorted_list(&mut self, vault_key: VaultKey, interest_rate_bps: u32, created_at: u64) {
         let head = self.sorted_head.get().flatten();
         let tail = self.sorted_tail.get().flatten();
 
@@ -754,11 +2219,12 @@ impl BranchScspr {
             return;
         }
 
-        // Find insertion point (sorted by ascending interest rate)
+        // Find insertion point (ascending interest rate, then oldest first)
         let mut current = head;
         while let Some(curr_key) = current {
             if let Some(curr_entry) = self.sorted_vaults.get(&curr_key) {
-                if interest_rate_bps <= curr_entry.interest_rate_bps {
+                let curr_created_at = self.vault_created_at(&curr_key);
+                if redeemed_before((interest_rate_bps, created_at), (curr_entry.interest_rate_bps, curr_created_at)) {
                     // Insert before current
                     let new_entry = SortedVaultEntry {
                         vault_key,
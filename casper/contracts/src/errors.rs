@@ -14,6 +14,13 @@ pub enum CdpError {
     InsufficientCollateral = 104,
     InsufficientDebt = 105,
     RepayExceedsDebt = 106,
+    RateChangeTooSoon = 107,
+    BelowMinNetDebt = 108,
+    BelowMinCollateral = 109,
+    VaultCountCapReached = 110,
+    TvlCapReached = 111,
+    BatchTooLarge = 112,
+    DebtCeilingReached = 113,
 
     // Oracle errors (2xx)
     OraclePriceUnavailable = 200,
@@ -22,6 +29,8 @@ pub enum CdpError {
     OracleInvalidRate = 203,
     OracleDecimalsMismatch = 204,
     OracleRateTooLow = 205,
+    OracleRateFresh = 206,
+    OraclePriceZero = 207,
 
     // Safe mode errors (3xx)
     SafeModeActive = 300,
@@ -30,6 +39,7 @@ pub enum CdpError {
     // Access control errors (4xx)
     Unauthorized = 400,
     UnauthorizedProtocol = 401,
+    Reentrancy = 402,
 
     // Token errors (5xx)
     TokenTransferFailed = 500,
@@ -39,19 +49,33 @@ pub enum CdpError {
     // Stability pool errors (6xx)
     SpInsufficientDeposit = 600,
     SpNoGains = 601,
+    DepositLocked = 602,
+    SpBelowMinDeposit = 603,
+    DepositCapReached = 604,
+    SpDepositsPaused = 605,
+    SpAutoCompoundDisabled = 606,
 
     // Liquidation errors (7xx)
     NotLiquidatable = 700,
     LiquidationInsufficientSp = 701,
+    LiquidationPending = 702,
+    LiquidationNotPending = 703,
+    DisputeWindowOpen = 704,
+    DisputeWindowClosed = 705,
+    LiquidationBatchTooLarge = 706,
 
     // Redemption errors (8xx)
     RedemptionNoEligibleVaults = 800,
     RedemptionAmountExceeds = 801,
+    RedemptionBelowMin = 802,
+    RedemptionTooLarge = 803,
+    RedemptionRateLimited = 804,
 
     // Configuration errors (9xx)
     InvalidConfig = 900,
     InterestRateOutOfBounds = 901,
     UnsupportedCollateral = 902,
+    MigrationNotActive = 903,
 
     // LST errors (10xx)
     LstRequestNotFound = 1000,
@@ -62,6 +86,15 @@ pub enum CdpError {
     LstInvalidRate = 1005,
     LstDepositsPaused = 1006,
     LstWithdrawalsPaused = 1007,
+    LstDepositCapExceeded = 1008,
+    LstBelowMinAmount = 1009,
+    LstRequestCancelled = 1010,
+    LstInsufficientIdle = 1011,
+    LstEmergencyModeInactive = 1012,
+
+    // User protection errors (11xx)
+    SlippageExceeded = 1100,
+    FeeExceedsMax = 1101,
 }
 
 impl CdpError {
@@ -75,6 +108,13 @@ impl CdpError {
             CdpError::InsufficientCollateral => "Insufficient collateral",
             CdpError::InsufficientDebt => "Insufficient debt to repay",
             CdpError::RepayExceedsDebt => "Repay amount exceeds vault debt",
+            CdpError::RateChangeTooSoon => "Interest rate change still in cooldown",
+            CdpError::BelowMinNetDebt => "Borrowed amount (before fee) below minimum debt",
+            CdpError::BelowMinCollateral => "Collateral below minimum for a vault with debt",
+            CdpError::VaultCountCapReached => "Branch vault count cap reached",
+            CdpError::TvlCapReached => "Branch TVL cap reached",
+            CdpError::BatchTooLarge => "Too many operations in batch",
+            CdpError::DebtCeilingReached => "Stablecoin supply cap reached",
 
             // Oracle
             CdpError::OraclePriceUnavailable => "Oracle price unavailable",
@@ -83,6 +123,8 @@ impl CdpError {
             CdpError::OracleInvalidRate => "Oracle invalid rate",
             CdpError::OracleDecimalsMismatch => "Oracle decimals mismatch",
             CdpError::OracleRateTooLow => "Oracle rate too low or zero",
+            CdpError::OracleRateFresh => "Exchange rate is not stale",
+            CdpError::OraclePriceZero => "Oracle price is zero",
 
             // Safe mode
             CdpError::SafeModeActive => "Operation blocked: safe mode active",
@@ -91,6 +133,7 @@ impl CdpError {
             // Access control
             CdpError::Unauthorized => "Unauthorized: caller is not admin",
             CdpError::UnauthorizedProtocol => "Unauthorized: caller is not protocol contract",
+            CdpError::Reentrancy => "Reentrant call rejected",
 
             // Token
             CdpError::TokenTransferFailed => "Token transfer failed",
@@ -100,19 +143,33 @@ impl CdpError {
             // Stability pool
             CdpError::SpInsufficientDeposit => "Stability pool: insufficient deposit",
             CdpError::SpNoGains => "Stability pool: no gains to claim",
+            CdpError::DepositLocked => "Stability pool: deposit still locked",
+            CdpError::SpBelowMinDeposit => "Stability pool: deposit below minimum",
+            CdpError::DepositCapReached => "Stability pool: per-user deposit cap reached",
+            CdpError::SpDepositsPaused => "Stability pool: deposits paused",
+            CdpError::SpAutoCompoundDisabled => "Stability pool: auto-compound not enabled",
 
             // Liquidation
             CdpError::NotLiquidatable => "Vault is not liquidatable",
             CdpError::LiquidationInsufficientSp => "Liquidation: insufficient SP funds",
+            CdpError::LiquidationPending => "Liquidation: vault already has a pending liquidation",
+            CdpError::LiquidationNotPending => "Liquidation: not pending",
+            CdpError::DisputeWindowOpen => "Liquidation: dispute window still open",
+            CdpError::DisputeWindowClosed => "Liquidation: dispute window closed",
+            CdpError::LiquidationBatchTooLarge => "Liquidation: batch exceeds per-transaction cap",
 
             // Redemption
             CdpError::RedemptionNoEligibleVaults => "Redemption: no eligible vaults",
             CdpError::RedemptionAmountExceeds => "Redemption: amount exceeds available",
+            CdpError::RedemptionBelowMin => "Redemption: amount below minimum",
+            CdpError::RedemptionTooLarge => "Redemption: exceeds max fraction of branch debt",
+            CdpError::RedemptionRateLimited => "Redemption: per-address rate limit exceeded",
 
             // Config
             CdpError::InvalidConfig => "Invalid configuration parameter",
             CdpError::InterestRateOutOfBounds => "Interest rate out of bounds",
             CdpError::UnsupportedCollateral => "Collateral not supported",
+            CdpError::MigrationNotActive => "Branch migration not active",
 
             // LST
             CdpError::LstRequestNotFound => "LST: withdrawal request not found",
@@ -123,6 +180,15 @@ impl CdpError {
             CdpError::LstInvalidRate => "LST: invalid rate (zero or overflow)",
             CdpError::LstDepositsPaused => "LST: deposits paused",
             CdpError::LstWithdrawalsPaused => "LST: withdrawals paused",
+            CdpError::LstDepositCapExceeded => "LST: deposit cap exceeded",
+            CdpError::LstBelowMinAmount => "LST: amount below minimum",
+            CdpError::LstRequestCancelled => "LST: withdrawal request cancelled",
+            CdpError::LstInsufficientIdle => "LST: insufficient idle CSPR",
+            CdpError::LstEmergencyModeInactive => "LST: emergency mode not active",
+
+            // User protection
+            CdpError::SlippageExceeded => "Output below caller's minimum",
+            CdpError::FeeExceedsMax => "Fee exceeds caller's maximum",
         }
     }
 }
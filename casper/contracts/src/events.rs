@@ -0,0 +1,9 @@
+//! Event sequencing shared by contracts whose major events carry a `seq`.
+
+/// Next value of a per-contract event sequence counter
+///
+/// Major events carry this as `seq` so indexers can order events from one
+/// contract deterministically. Starts at 1; zero means no event emitted yet.
+pub fn next_event_seq(last: u64) -> u64 {
+    last.saturating_add(1)
+}
@@ -7,6 +7,7 @@
 
 use odra::prelude::*;
 use odra::casper_types::U256;
+use crate::math::mul_div_ceil;
 
 /// Seconds in a year (365 days)
 pub const SECONDS_PER_YEAR: u64 = 31_536_000;
@@ -17,6 +18,42 @@ pub const BPS_SCALE: u64 = 10_000;
 /// Internal precision scale (1e18)
 pub const PRECISION: u64 = 1_000_000_000_000_000_000;
 
+/// Emitted when interest is accrued onto a vault's debt
+#[odra::event]
+pub struct InterestAccrued {
+    /// Branch event sequence number
+    pub seq: u64,
+    /// Vault owner
+    pub owner: Address,
+    /// Vault id
+    pub vault_id: u64,
+    /// Interest added to debt
+    pub amount: U256,
+    /// Vault debt after accrual
+    pub new_debt: U256,
+}
+
+/// How the applied rate is derived from a vault's chosen rate
+#[odra::odra_type]
+#[derive(Copy, Default)]
+pub enum InterestModel {
+    /// The vault's own rate applies unchanged
+    #[default]
+    Fixed,
+    /// Utilization-based kinked curve; the vault pays the higher of its own
+    /// rate and the curve rate
+    Kinked {
+        /// Curve rate at zero utilization, in bps
+        base_bps: u32,
+        /// Rate added between zero utilization and the kink, in bps
+        slope1_bps: u32,
+        /// Rate added between the kink and full utilization, in bps
+        slope2_bps: u32,
+        /// Utilization at which the steeper slope starts, in bps
+        kink_bps: u32,
+    },
+}
+
 /// Interest rate bounds configuration
 #[odra::odra_type]
 pub struct InterestRateConfig {
@@ -24,6 +61,8 @@ pub struct InterestRateConfig {
     pub min_rate_bps: u32,
     /// Maximum interest rate in basis points (e.g., 4000 = 40%)
     pub max_rate_bps: u32,
+    /// Rate model applied on top of per-vault rates
+    pub model: InterestModel,
 }
 
 impl Default for InterestRateConfig {
@@ -31,10 +70,48 @@ impl Default for InterestRateConfig {
         Self {
             min_rate_bps: 0,
             max_rate_bps: 4000, // 40% max APR
+            model: InterestModel::Fixed,
         }
     }
 }
 
+impl InterestRateConfig {
+    /// Rate applied to a vault at `vault_rate_bps` given branch utilization
+    ///
+    /// `utilization_bps` is branch debt over its ceiling (10000 = 100%, values
+    /// above are treated as full). The result is capped at `max_rate_bps`.
+    pub fn effective_rate(&self, vault_rate_bps: u32, utilization_bps: u32) -> u32 {
+        let rate = match self.model {
+            InterestModel::Fixed => vault_rate_bps,
+            InterestModel::Kinked { base_bps, slope1_bps, slope2_bps, kink_bps } => {
+                vault_rate_bps.max(kinked_rate(base_bps, slope1_bps, slope2_bps, kink_bps, utilization_bps))
+            }
+        };
+        rate.min(self.max_rate_bps)
+    }
+}
+
+/// Kinked curve rate in bps at `utilization_bps`
+///
+/// Rises linearly by `slope1_bps` up to the kink, then by `slope2_bps` over
+/// the remaining utilization.
+fn kinked_rate(base_bps: u32, slope1_bps: u32, slope2_bps: u32, kink_bps: u32, utilization_bps: u32) -> u32 {
+    let bps = BPS_SCALE as u32;
+    let utilization = utilization_bps.min(bps) as u64;
+    let kink = kink_bps.min(bps) as u64;
+    let below_kink = if kink == 0 {
+        slope1_bps as u64
+    } else {
+        slope1_bps as u64 * utilization.min(kink) / kink
+    };
+    let above_kink = if utilization <= kink {
+        0
+    } else {
+        slope2_bps as u64 * (utilization - kink) / (BPS_SCALE - kink)
+    };
+    (base_bps as u64 + below_kink + above_kink).min(u32::MAX as u64) as u32
+}
+
 /// Interest accrual result
 #[odra::odra_type]
 pub struct AccrualResult {
@@ -86,13 +163,12 @@ pub fn accrue_interest(
     let elapsed_seconds = current_timestamp - last_accrual_timestamp;
 
     // Calculate interest: debt * rate_bps * elapsed / (BPS_SCALE * SECONDS_PER_YEAR)
-    // Using high precision to avoid rounding errors
-    let interest = debt
-        .checked_mul(U256::from(interest_rate_bps))
-        .and_then(|v| v.checked_mul(U256::from(elapsed_seconds)))
-        .and_then(|v| v.checked_div(U256::from(BPS_SCALE)))
-        .and_then(|v| v.checked_div(U256::from(SECONDS_PER_YEAR)))
-        .unwrap_or(U256::zero());
+    // Debt charged rounds up
+    let interest = mul_div_ceil(
+        debt,
+        U256::from(interest_rate_bps) * U256::from(elapsed_seconds),
+        U256::from(BPS_SCALE) * U256::from(SECONDS_PER_YEAR),
+    );
 
     let new_debt = debt + interest;
 
@@ -132,6 +208,120 @@ pub fn calculate_interest_multiplier(
     U256::from(PRECISION) + rate_component
 }
 
+/// Upfront borrowing fee charged on newly borrowed debt
+///
+/// # Arguments
+/// * `debt_amount` - Newly borrowed debt
+/// * `fee_bps` - Borrowing fee in basis points
+pub fn borrow_fee(debt_amount: U256, fee_bps: u32) -> U256 {
+    // Debt charged rounds up
+    mul_div_ceil(debt_amount, U256::from(fee_bps), U256::from(BPS_SCALE))
+}
+
+/// Fee taken from collateral withdrawn from a vault
+///
+/// # Arguments
+/// * `amount` - Collateral withdrawn
+/// * `fee_bps` - Withdrawal fee in basis points
+pub fn withdrawal_fee(amount: U256, fee_bps: u32) -> U256 {
+    // Fees charged round up
+    mul_div_ceil(amount, U256::from(fee_bps), U256::from(BPS_SCALE))
+}
+
+/// Fee charged when a vault lowers its interest rate
+///
+/// Raising the rate (or leaving it unchanged) is free.
+///
+/// # Arguments
+/// * `debt` - Vault debt at the time of the change
+/// * `current_rate_bps` - Vault's current interest rate
+/// * `new_rate_bps` - Requested interest rate
+/// * `fee_bps` - Rate adjustment fee in basis points
+pub fn rate_adjust_fee(debt: U256, current_rate_bps: u32, new_rate_bps: u32, fee_bps: u32) -> U256 {
+    if new_rate_bps >= current_rate_bps {
+        return U256::zero();
+    }
+    borrow_fee(debt, fee_bps)
+}
+
+/// Debt-weighted average interest rate over `(rate_bps, debt)` entries
+///
+/// Returns 0 when there is no debt.
+pub fn weighted_average_rate_bps(entries: &[(u32, U256)]) -> u32 {
+    let total_debt = entries.iter().fold(U256::zero(), |acc, (_, debt)| acc + *debt);
+    if total_debt.is_zero() {
+        return 0;
+    }
+    let weighted = entries
+        .iter()
+        .fold(U256::zero(), |acc, (rate_bps, debt)| acc + *debt * U256::from(*rate_bps));
+    (weighted / total_debt).low_u32()
+}
+
+/// Debt per interest rate bucket over `(rate_bps, debt)` entries
+///
+/// Splits `[0, max_rate_bps]` into `buckets` equal-width buckets and returns
+/// (bucket lower bound in bps, debt) for each; rates at or above the last
+/// bound fall in the last bucket.
+pub fn rate_distribution(entries: &[(u32, U256)], buckets: u32, max_rate_bps: u32) -> Vec<(u32, U256)> {
+    if buckets == 0 {
+        return Vec::new();
+    }
+    let width = (max_rate_bps / buckets).max(1);
+    let mut distribution: Vec<(u32, U256)> = (0..buckets).map(|i| (i * width, U256::zero())).collect();
+    for (rate_bps, debt) in entries.iter() {
+        let index = (*rate_bps / width).min(buckets - 1) as usize;
+        distribution[index].1 += *debt;
+    }
+    distribution
+}
+
+/// Whether a vault may change its interest rate again
+///
+/// # Arguments
+/// * `last_change` - Timestamp of the vault's last rate change (0 if never)
+/// * `now` - Current block time
+/// * `cooldown_seconds` - Minimum time between rate changes
+pub fn rate_change_allowed(last_change: u64, now: u64, cooldown_seconds: u64) -> bool {
+    now >= last_change.saturating_add(cooldown_seconds)
+}
+
+/// Timestamp from which interest accrues for a vault
+///
+/// Time before `created_at + grace_period_seconds` is interest-free, so the
+/// accrual window starts at the later of the last accrual and the grace end.
+pub fn accrual_start(last_accrual_timestamp: u64, created_at: u64, grace_period_seconds: u64) -> u64 {
+    last_accrual_timestamp.max(created_at.saturating_add(grace_period_seconds))
+}
+
+/// Unrealized interest that can be minted now
+///
+/// Capped at the branch's total debt: gUSD minted for interest must stay
+/// matched by outstanding debt even if vaults carrying it were closed.
+pub fn realizable_interest(unrealized: U256, total_debt: U256) -> U256 {
+    unrealized.min(total_debt)
+}
+
+/// Interest accrued but not yet booked across vaults
+///
+/// Each entry is a vault's (stored debt, rate bps, accrual start); the sum
+/// matches what touching every vault at `now` would add to total debt.
+pub fn pending_interest(entries: &[(U256, u32, u64)], now: u64) -> U256 {
+    entries.iter().fold(U256::zero(), |total, (debt, rate_bps, start)| {
+        total + accrue_interest(*debt, *rate_bps, *start, now).interest_accrued
+    })
+}
+
+/// Total debt and number of vaults whose rate lies in `min_bps..=max_bps`
+///
+/// Entries are (rate bps, debt), e.g. a walk of the sorted vault list.
+pub fn debt_in_rate_range(entries: &[(u32, U256)], min_bps: u32, max_bps: u32) -> (U256, u64) {
+    entries
+        .iter()
+        .filter(|(rate_bps, _)| (min_bps..=max_bps).contains(rate_bps))
+        .fold((U256::zero(), 0u64), |(debt, count), (_, vault_debt)| (debt + *vault_debt, count + 1))
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -205,4 +395,234 @@ mod tests {
         let expected = U256::from(50_000_000_000_000_000u64); // 0.05 * 1e18
         assert_eq!(fraction, expected);
     }
+
+    #[test]
+    fn test_borrow_fee_added_to_debt() {
+        // Opening 1000 gUSD at a 0.5% borrowing fee adds 5 gUSD of debt
+        let debt = U256::from(1000u64) * U256::from(PRECISION);
+        let fee = borrow_fee(debt, 50);
+        assert_eq!(fee, U256::from(5u64) * U256::from(PRECISION));
+        assert_eq!(debt + fee, U256::from(1005u64) * U256::from(PRECISION));
+    }
+
+    #[test]
+    fn test_zero_borrow_fee_by_default() {
+        let debt = U256::from(1000u64) * U256::from(PRECISION);
+        assert_eq!(borrow_fee(debt, 0), U256::zero());
+    }
+
+    #[test]
+    fn test_rate_change_blocked_within_cooldown() {
+        // Vault changed its rate at t=1000 with a 1 day cooldown
+        assert!(!rate_change_allowed(1000, 1000, 86_400));
+        assert!(!rate_change_allowed(1000, 1000 + 86_399, 86_400));
+    }
+
+    #[test]
+    fn test_rate_change_allowed_after_cooldown() {
+        assert!(rate_change_allowed(1000, 1000 + 86_400, 86_400));
+        // No cooldown configured
+        assert!(rate_change_allowed(1000, 1000, 0));
+    }
+
+    #[test]
+    fn test_rate_decrease_incurs_fee() {
+        // Dropping from 5% to 2% on 1000 gUSD at a 0.5% fee costs 5 gUSD
+        let debt = U256::from(1000u64) * U256::from(PRECISION);
+        let fee = rate_adjust_fee(debt, 500, 200, 50);
+        assert_eq!(fee, U256::from(5u64) * U256::from(PRECISION));
+    }
+
+    #[test]
+    fn test_rate_increase_is_free() {
+        let debt = U256::from(1000u64) * U256::from(PRECISION);
+        assert_eq!(rate_adjust_fee(debt, 200, 500, 50), U256::zero());
+        assert_eq!(rate_adjust_fee(debt, 500, 500, 50), U256::zero());
+    }
+
+    #[test]
+    fn test_weighted_average_rate() {
+        let gusd = |n: u64| U256::from(n) * U256::from(PRECISION);
+        // 1000 gUSD at 2%, 3000 gUSD at 6%: (1000*200 + 3000*600) / 4000 = 500 bps
+        let entries = [(200, gusd(1000)), (600, gusd(3000))];
+        assert_eq!(weighted_average_rate_bps(&entries), 500);
+
+        // A third vault with no debt doesn't move the average
+        let entries = [(200, gusd(1000)), (600, gusd(3000)), (4000, U256::zero())];
+        assert_eq!(weighted_average_rate_bps(&entries), 500);
+
+        assert_eq!(weighted_average_rate_bps(&[]), 0);
+    }
+
+    #[test]
+    fn test_rate_distribution_buckets() {
+        let gusd = |n: u64| U256::from(n) * U256::from(PRECISION);
+        let entries = [(200, gusd(1000)), (600, gusd(3000)), (900, gusd(500)), (4000, gusd(100))];
+
+        // Four 10% buckets over 0-40%
+        let distribution = rate_distribution(&entries, 4, 4000);
+        assert_eq!(distribution.len(), 4);
+        assert_eq!(distribution[0], (0, gusd(4500)));
+        assert_eq!(distribution[1], (1000, U256::zero()));
+        assert_eq!(distribution[3], (3000, gusd(100)));
+    }
+
+    #[test]
+    fn test_no_interest_within_grace_period() {
+        let debt = U256::from(1000u64) * U256::from(PRECISION);
+        let created_at = 1000;
+        let grace = 7 * 86_400;
+
+        // Touched half-way through the grace window: nothing accrues
+        let start = accrual_start(created_at, created_at, grace);
+        let mid = created_at + grace / 2;
+        assert!(accrue_interest(debt, 800, start, mid).interest_accrued.is_zero());
+
+        // Exactly at the end of the window: still nothing
+        let start = accrual_start(mid, created_at, grace);
+        assert!(accrue_interest(debt, 800, start, created_at + grace).interest_accrued.is_zero());
+    }
+
+    #[test]
+    fn test_accrual_resumes_after_grace_period() {
+        let debt = U256::from(1000u64) * U256::from(PRECISION);
+        let created_at = 1000;
+        let grace = 7 * 86_400;
+        let now = created_at + grace + SECONDS_PER_YEAR / 4;
+
+        // Only the quarter year after the grace window is charged
+        let start = accrual_start(created_at, created_at, grace);
+        let accrual = accrue_interest(debt, 800, start, now);
+        assert_eq!(accrual.interest_accrued, U256::from(20u64) * U256::from(PRECISION));
+
+        // Once past the window, the last accrual timestamp governs as before
+        let last = created_at + grace + 100;
+        assert_eq!(accrual_start(last, created_at, grace), last);
+
+        // No grace configured leaves accrual unchanged
+        assert_eq!(accrual_start(created_at, created_at, 0), created_at);
+    }
+
+    #[test]
+    fn test_kinked_model_raises_rate_past_kink() {
+        let config = InterestRateConfig {
+            model: InterestModel::Kinked {
+                base_bps: 100,
+                slope1_bps: 400,
+                slope2_bps: 3000,
+                kink_bps: 8000,
+            },
+            ..InterestRateConfig::default()
+        };
+
+        // Below the kink the curve climbs gently: 1% + 4% * 50/80
+        assert_eq!(config.effective_rate(0, 5000), 350);
+        // At the kink: 1% + 4%
+        assert_eq!(config.effective_rate(0, 8000), 500);
+        // Past the kink the steep slope applies: 5% + 30% * 10/20
+        assert_eq!(config.effective_rate(0, 9000), 2000);
+        assert!(config.effective_rate(0, 9000) - config.effective_rate(0, 8000)
+            > config.effective_rate(0, 8000) - config.effective_rate(0, 7000));
+
+        // A vault rate above the curve is kept; the result never exceeds the max
+        assert_eq!(config.effective_rate(1200, 5000), 1200);
+        assert_eq!(config.effective_rate(0, 10_000), 3500);
+        assert_eq!(config.effective_rate(0, 20_000), 3500);
+        let steep = InterestRateConfig {
+            model: InterestModel::Kinked { base_bps: 100, slope1_bps: 400, slope2_bps: 9000, kink_bps: 8000 },
+            ..InterestRateConfig::default()
+        };
+        assert_eq!(steep.effective_rate(0, 10_000), 4000);
+
+        // The default Fixed model passes the vault rate through
+        assert_eq!(InterestRateConfig::default().effective_rate(700, 9500), 700);
+    }
+
+    #[test]
+    fn test_realized_interest_matches_accrued() {
+        let debt = |n: u64| U256::from(n) * U256::from(PRECISION);
+        let now = SECONDS_PER_YEAR;
+
+        // Two vaults accrue for a year at 5% and 10%
+        let a = accrue_interest(debt(1000), 500, 0, now);
+        let b = accrue_interest(debt(2000), 1000, 0, now);
+        let unrealized = a.interest_accrued + b.interest_accrued;
+        let total_debt = a.new_debt + b.new_debt;
+        assert_eq!(unrealized, debt(250));
+
+        // Realizing mints exactly what accrued and leaves nothing behind
+        let minted = realizable_interest(unrealized, total_debt);
+        assert_eq!(minted, debt(250));
+        assert!(realizable_interest(unrealized - minted, total_debt).is_zero());
+
+        // Never more than the debt that backs it
+        assert_eq!(realizable_interest(debt(250), debt(100)), debt(100));
+    }
+
+    #[test]
+    fn test_live_total_debt_includes_pending_interest() {
+        let debt = |n: u64| U256::from(n) * U256::from(1_000_000_000_000_000_000u128);
+        let t0 = 1_700_000_000u64;
+        let year = SECONDS_PER_YEAR;
+        let stored_total = debt(3000);
+        let entries = [(debt(1000), 500u32, t0), (debt(2000), 1000u32, t0)];
+
+        // Nothing pending until time passes
+        assert_eq!(pending_interest(&entries, t0), U256::zero());
+
+        // After a year: 5% on 1000 plus 10% on 2000
+        let pending = pending_interest(&entries, t0 + year);
+        assert_eq!(pending, debt(50) + debt(200));
+        assert!(stored_total + pending > stored_total);
+
+        // Matches what touching each vault would book
+        let booked = entries.iter().fold(U256::zero(), |total, (d, r, s)| {
+            total + accrue_interest(*d, *r, *s, t0 + year).interest_accrued
+        });
+        assert_eq!(pending, booked);
+
+        // Vaults still in their grace period or at 0% add nothing
+        let idle = [(debt(1000), 500u32, t0 + year), (debt(1000), 0u32, t0)];
+        assert_eq!(pending_interest(&idle, t0 + year), U256::zero());
+    }
+
+    #[test]
+    fn test_debt_in_rate_range_totals() {
+        let debt = |n: u64| U256::from(n) * U256::from(PRECISION);
+        // Vaults spread across rates, ascending like the sorted list
+        let entries = [
+            (100u32, debt(1_000)),
+            (150, debt(2_000)),
+            (150, debt(500)),
+            (400, debt(3_000)),
+            (1_200, debt(10_000)),
+        ];
+
+        // Cheapest vaults only: both bounds inclusive
+        assert_eq!(debt_in_rate_range(&entries, 0, 150), (debt(3_500), 3));
+        assert_eq!(debt_in_rate_range(&entries, 150, 400), (debt(5_500), 3));
+        assert_eq!(debt_in_rate_range(&entries, 401, 1_199), (U256::zero(), 0));
+        assert_eq!(debt_in_rate_range(&entries, 0, u32::MAX), (debt(16_500), 5));
+
+        // An inverted range is empty
+        assert_eq!(debt_in_rate_range(&entries, 400, 100), (U256::zero(), 0));
+    }
+
+    #[test]
+    fn test_withdrawal_fee_splits_payout() {
+        let cspr = |n: u64| U256::from(n) * U256::from(1_000_000_000u64);
+        let withdrawn = cspr(1_000);
+
+        // 0.5%: the user nets 995, the treasury receives 5
+        let fee = withdrawal_fee(withdrawn, 50);
+        assert_eq!(fee, cspr(5));
+        assert_eq!(withdrawn - fee, cspr(995));
+
+        // Sub-unit fees round up in the protocol's favour, never past the amount
+        assert_eq!(withdrawal_fee(U256::from(1_999u64), 50), U256::from(10u64));
+        assert!(withdrawal_fee(U256::one(), 500) <= U256::one());
+
+        // Default zero: the whole amount goes to the user
+        assert!(withdrawal_fee(withdrawn, 0).is_zero());
+    }
 }
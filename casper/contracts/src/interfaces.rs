@@ -33,6 +33,39 @@ pub struct AdjustVaultParams {
     pub debt_is_repay: bool,
 }
 
+impl AdjustVaultParams {
+    /// Debt increase only: borrow `amount` more gUSD
+    pub fn borrow(amount: U256) -> Self {
+        Self {
+            collateral_delta: U256::zero(),
+            collateral_is_withdraw: false,
+            debt_delta: amount,
+            debt_is_repay: false,
+        }
+    }
+
+    /// Debt decrease only: repay `amount` gUSD
+    pub fn repay(amount: U256) -> Self {
+        Self {
+            collateral_delta: U256::zero(),
+            collateral_is_withdraw: false,
+            debt_delta: amount,
+            debt_is_repay: true,
+        }
+    }
+}
+
+/// One vault adjustment in a router batch
+#[odra::odra_type]
+pub struct AdjustOp {
+    /// Branch holding the vault
+    pub collateral_id: CollateralId,
+    /// Caller's vault id in that branch
+    pub vault_id: u64,
+    /// Same parameters as a single `adjust_vault`
+    pub params: AdjustVaultParams,
+}
+
 /// Branch status information
 #[odra::odra_type]
 pub struct BranchStatus {
@@ -48,6 +81,55 @@ pub struct BranchStatus {
     pub safe_mode: SafeModeState,
 }
 
+/// Protocol-wide backing snapshot
+#[odra::odra_type]
+pub struct SystemBacking {
+    /// Total debt across all branches
+    pub total_debt: U256,
+    /// Total collateral value in USD across all branches (cached prices)
+    pub total_collateral_value_usd: U256,
+    /// gUSD deposited in the stability pool
+    pub sp_deposits: U256,
+    /// (collateral value + SP deposits) / total debt in bps (u32::MAX if no debt)
+    pub backing_ratio_bps: u32,
+}
+
+/// USD valuation of collateral held across branches
+#[odra::odra_type]
+pub struct CollateralValuation {
+    /// CSPR branch collateral value in USD (18 decimals)
+    pub cspr_value_usd: U256,
+    /// stCSPR branch collateral value in USD at the composite price
+    pub scspr_value_usd: U256,
+    /// Sum of both branch values
+    pub total_value_usd: U256,
+    /// false if any branch was valued at its last good price because its feed was not Ok
+    pub prices_ok: bool,
+}
+
+/// Protocol-wide figures for dashboards, gathered in one router call
+#[odra::odra_type]
+pub struct ProtocolStats {
+    /// Collateral value plus SP deposits in USD (18 decimals)
+    pub tvl_usd: U256,
+    /// Branch collateral valuation at oracle prices
+    pub collateral: CollateralValuation,
+    /// Total debt across all branches
+    pub total_debt: U256,
+    /// Active vaults across all branches
+    pub vault_count: u64,
+    /// gUSD deposited in the stability pool
+    pub sp_deposits: U256,
+    /// Number of stability pool depositors
+    pub sp_depositor_count: u64,
+    /// Total number of liquidations
+    pub total_liquidations: u64,
+    /// Total redemption operations
+    pub total_redemptions: u64,
+    /// Total gUSD redeemed (cumulative)
+    pub total_redeemed: U256,
+}
+
 /// Vault query result
 #[odra::odra_type]
 pub struct VaultInfo {
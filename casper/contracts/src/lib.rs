@@ -19,6 +19,7 @@
 //! When oracle status is not OK, the protocol enters safe_mode (ADR-001):
 //! - Allowed: repay, add collateral, SP deposit
 //! - Blocked: open/borrow, withdraw, liquidation, redemption, SP withdraw
+//! - Open/borrow blocks wait out an optional grace period (`safe_mode_grace_seconds`)
 
 #![cfg_attr(target_arch = "wasm32", no_std)]
 
@@ -30,10 +31,18 @@ pub use odra;
 
 // Core module declarations
 pub mod types;
+pub mod math;
 pub mod errors;
 pub mod interfaces;
 pub mod interest;
+pub mod branch;
+pub mod redistribution;
+pub mod sorted_list;
+pub mod reentrancy;
+pub mod events;
+pub mod recovery;
 pub mod styks_oracle;
+pub mod voting;
 
 // Contract modules
 pub mod registry;
@@ -1,7 +1,7 @@
 //! Liquidation Engine Contract
 //!
 //! Handles liquidation of under-collateralized vaults.
-//! Liquidation occurs when a vault's ICR falls below the MCR (110%).
+//! Liquidation occurs when a vault's ICR falls below its branch's MCR (110% by default).
 //!
 //! Liquidation flow:
 //! 1. Check vault is liquidatable (ICR < MCR)
@@ -10,12 +10,21 @@
 //! 4. Apply liquidation penalty (10% default)
 //! 5. Transfer collateral to liquidator/SP depositors
 //! 6. Close or reduce the vault
+//!
+//! With a non-zero `dispute_window_seconds`, steps 3-6 are deferred: the
+//! liquidation is queued and finalized by anyone once the window has passed.
+//! Until then the guardian can `revert_liquidation` it (e.g. after a
+//! manipulated price), leaving the vault untouched.
 
 use odra::prelude::*;
 use odra::casper_types::{U256, U512, RuntimeArgs, runtime_args};
 use odra::CallDef;
-use crate::types::{CollateralId, VaultKey, OracleStatus, SafeModeState};
+use crate::types::{CollateralId, ComponentId, VaultKey, OracleStatus, PriceData, SafeModeState};
+use crate::math::{collateral_to_value, value_to_collateral, COLLATERAL_SCALE, PRICE_SCALE};
+use crate::events::next_event_seq;
+use crate::registry::{self, resolve_all};
 use crate::errors::CdpError;
+use crate::reentrancy::check_not_entered;
 use crate::styks_oracle::StyksOracle;
 
 /// Branch interface for vault operations
@@ -23,9 +32,12 @@ use crate::styks_oracle::StyksOracle;
 pub trait Branch {
     fn get_collateral(&self, owner: Address, vault_id: u64) -> U256;
     fn get_debt(&self, owner: Address, vault_id: u64) -> U256;
+    fn get_effective_collateral(&self, owner: Address, vault_id: u64) -> U256;
+    fn get_effective_debt(&self, owner: Address, vault_id: u64) -> U256;
     fn seize_collateral(&mut self, owner: Address, vault_id: u64, amount: U256);
     fn reduce_debt(&mut self, owner: Address, vault_id: u64, amount: U256);
     fn close_vault(&mut self, owner: Address, vault_id: u64);
+    fn get_mcr(&self) -> u32;
 }
 
 /// Stability Pool interface
@@ -39,6 +51,8 @@ pub trait StabilityPool {
 #[odra::external_contract]
 pub trait GUsd {
     fn burn_from(&mut self, from: Address, amount: U256);
+    fn transfer(&mut self, recipient: Address, amount: U256) -> bool;
+    fn balance_of(&self, owner: Address) -> U256;
 }
 
 /// CEP-18 interface for stCSPR
@@ -47,21 +61,34 @@ pub trait Cep18 {
     fn transfer(&mut self, recipient: Address, amount: U256) -> bool;
 }
 
-/// Minimum Collateralization Ratio for liquidation (110% = 11000 bps)
+/// Default Minimum Collateralization Ratio for liquidation (110% = 11000 bps)
 const MCR_BPS: u32 = 11000;
 
+/// Components pulled from the registry by `refresh_addresses`
+pub const REGISTRY_DEPENDENCIES: [ComponentId; 9] = [
+    ComponentId::Router,
+    ComponentId::StabilityPool,
+    ComponentId::Oracle,
+    ComponentId::StyksOracle,
+    ComponentId::ScsprYbToken,
+    ComponentId::BranchCspr,
+    ComponentId::BranchScspr,
+    ComponentId::Stablecoin,
+    ComponentId::ScsprToken,
+];
+
 /// Liquidation penalty in basis points (10% = 1000 bps)
 const LIQUIDATION_PENALTY_BPS: u32 = 1000;
 
-/// Precision scale for prices (1e18)
-const PRICE_SCALE: u64 = 1_000_000_000_000_000_000;
-
-/// Collateral decimals (CSPR/stCSPR use 9 decimals)
-const COLLATERAL_DECIMALS: u64 = 1_000_000_000;
-
 /// Basis points scale
 const BPS_SCALE: u32 = 10000;
 
+/// Maximum liquidation dispute window (1 hour)
+const MAX_DISPUTE_WINDOW_SECONDS: u64 = 3_600;
+
+/// Default cap on vaults processed by one `batch_liquidate` call
+const DEFAULT_MAX_LIQUIDATIONS_PER_TX: u32 = 50;
+
 /// Liquidation result for a single vault
 #[odra::odra_type]
 pub struct LiquidationResult {
@@ -77,23 +104,103 @@ pub struct LiquidationResult {
     pub collateral_to_sp: U256,
     /// Collateral going to liquidator as gas compensation
     pub collateral_to_liquidator: U256,
+    /// gUSD paid to liquidator from the gas reserve (gUSD mode only)
+    pub gusd_to_liquidator: U256,
+    /// Whether vault was fully liquidated
+    pub fully_liquidated: bool,
+}
+
+/// Emitted for each vault liquidation
+#[odra::event]
+pub struct Liquidated {
+    /// Liquidation engine event sequence number
+    pub seq: u64,
+    /// Collateral type
+    pub collateral_id: CollateralId,
+    /// Liquidated vault key
+    pub vault_key: VaultKey,
+    /// Account that triggered the liquidation
+    pub liquidator: Address,
+    /// Debt covered in the liquidation
+    pub debt_liquidated: U256,
+    /// Collateral seized (including penalty)
+    pub collateral_seized: U256,
     /// Whether vault was fully liquidated
     pub fully_liquidated: bool,
 }
 
+/// Lifecycle of a queued liquidation
+#[odra::odra_type]
+#[derive(Copy)]
+pub enum PendingLiquidationStatus {
+    /// Waiting out the dispute window
+    Pending,
+    /// Executed after the window
+    Finalized,
+    /// Cancelled by the guardian; the vault was never touched
+    Reverted,
+}
+
+/// Liquidation recorded during the dispute window
+#[odra::odra_type]
+pub struct PendingLiquidation {
+    /// Outcome computed at liquidation time
+    pub result: LiquidationResult,
+    /// Account that triggered the liquidation (receives gas compensation)
+    pub liquidator: Address,
+    /// Timestamp after which the liquidation can be finalized
+    pub finalizes_at: u64,
+    /// Current status
+    pub status: PendingLiquidationStatus,
+}
+
+/// Emitted when a liquidation is queued for the dispute window
+#[odra::event]
+pub struct LiquidationQueued {
+    /// Liquidation engine event sequence number
+    pub seq: u64,
+    /// Pending liquidation id
+    pub id: u64,
+    /// Collateral type
+    pub collateral_id: CollateralId,
+    /// Vault owner
+    pub owner: Address,
+    /// Vault id
+    pub vault_id: u64,
+    /// Timestamp after which the liquidation can be finalized
+    pub finalizes_at: u64,
+}
+
+/// Emitted when the guardian reverts a queued liquidation
+#[odra::event]
+pub struct LiquidationReverted {
+    /// Liquidation engine event sequence number
+    pub seq: u64,
+    /// Pending liquidation id
+    pub id: u64,
+    /// Collateral type
+    pub collateral_id: CollateralId,
+    /// Vault owner
+    pub owner: Address,
+    /// Vault id
+    pub vault_id: u64,
+}
+
 /// Batch liquidation summary
 #[odra::odra_type]
 pub struct BatchLiquidationResult {
-    /// Number of vaults liquidated
+    /// Number of vaults liquidated (or queued, with a dispute window set)
     pub vaults_liquidated: u32,
     /// Total debt liquidated
     pub total_debt_liquidated: U256,
     /// Total collateral seized
     pub total_collateral_seized: U256,
+    /// Whether the batch stopped early because oracle status degraded
+    pub aborted: bool,
 }
 
 /// Liquidation Engine Contract
-#[odra::module]
+#[odra::module(events = [Liquidated, LiquidationQueued, LiquidationReverted])]
 pub struct LiquidationEngine {
     /// Registry contract address
     registry: Var<Address>,
@@ -115,7 +222,7 @@ pub struct LiquidationEngine {
     scspr_token: Var<Address>,
     /// Liquidation penalty in bps
     liquidation_penalty_bps: Var<u32>,
-    /// Gas compensation for liquidator (in collateral)
+    /// Gas compensation for liquidator (gUSD-denominated amount)
     gas_compensation: Var<U256>,
     /// Total liquidations processed
     total_liquidations: Var<u64>,
@@ -123,8 +230,37 @@ pub struct LiquidationEngine {
     total_debt_liquidated: Var<U256>,
     /// Total collateral seized (cumulative)
     total_collateral_seized: Var<U256>,
+    /// State beyond the core fields (nested: a module holds at most 15 fields)
+    state: SubModule<LiquidationEngineState>,
+}
+
+/// Liquidation engine state added on top of the core config and stats storage
+#[odra::module]
+pub struct LiquidationEngineState {
     /// Local safe mode state
     safe_mode: Var<SafeModeState>,
+    /// Oracle adapter (composite stCSPR price, confirmed liquidation prices)
+    oracle_adapter: Var<Address>,
+    /// Pay gas compensation as minted gUSD instead of seized collateral
+    gas_compensation_in_gusd: Var<bool>,
+    /// Share of the liquidation penalty paid to the liquidator, in bps of the penalty
+    liquidator_bounty_bps: Var<u32>,
+    /// Reentrancy guard for external transfer sections
+    reentrancy_lock: Var<bool>,
+    /// Sequence number of the last emitted liquidation event
+    event_seq: Var<u64>,
+    /// Delay before a liquidation executes (0 = immediate)
+    dispute_window_seconds: Var<u64>,
+    /// Account allowed to revert liquidations inside the dispute window
+    guardian: Var<Address>,
+    /// Queued liquidations by id
+    pending_liquidations: Mapping<u64, PendingLiquidation>,
+    /// Number of liquidations ever queued (ids start at 1)
+    pending_liquidation_count: Var<u64>,
+    /// Pending liquidation id per vault (0 = none)
+    pending_by_vault: Mapping<(CollateralId, VaultKey), u64>,
+    /// Cap on `max_vaults` accepted by `batch_liquidate`
+    max_liquidations_per_tx: Var<u32>,
 }
 
 #[odra::module]
@@ -146,7 +282,7 @@ impl LiquidationEngine {
         self.total_liquidations.set(0);
         self.total_debt_liquidated.set(U256::zero());
         self.total_collateral_seized.set(U256::zero());
-        self.safe_mode.set(SafeModeState {
+        self.state.safe_mode.set(SafeModeState {
             is_active: false,
             triggered_at: 0,
             reason: OracleStatus::Ok,
@@ -189,32 +325,101 @@ impl LiquidationEngine {
         self.scspr_ybtoken.set(scspr_ybtoken);
     }
 
+    /// Set oracle adapter address (liquidation price source once set)
+    pub fn set_oracle_adapter(&mut self, oracle_adapter: Address) {
+        // TODO: Add admin access control
+        self.state.oracle_adapter.set(oracle_adapter);
+    }
+
+    /// Get oracle adapter address
+    pub fn get_oracle_adapter(&self) -> Option<Address> {
+        self.state.oracle_adapter.get()
+    }
+
+    /// Pull all dependency addresses from the registry in one call (admin only)
+    ///
+    /// Components the registry doesn't know yet keep their current address;
+    /// the individual setters remain available as overrides.
+    pub fn refresh_addresses(&mut self) {
+        self.require_registry_admin();
+        let registry_addr = match self.registry.get() {
+            Some(addr) => addr,
+            None => self.env().revert(CdpError::InvalidConfig),
+        };
+        let env = self.env();
+        let resolved = resolve_all(&REGISTRY_DEPENDENCIES, |component| {
+            registry::try_resolve(&env, registry_addr, component)
+        });
+        for (component, address) in resolved {
+            match component {
+                ComponentId::Router => self.router.set(address),
+                ComponentId::StabilityPool => self.stability_pool.set(address),
+                ComponentId::Oracle => self.state.oracle_adapter.set(address),
+                ComponentId::StyksOracle => self.styks_oracle.set(address),
+                ComponentId::ScsprYbToken => self.scspr_ybtoken.set(address),
+                ComponentId::BranchCspr => self.branch_cspr.set(address),
+                ComponentId::BranchScspr => self.branch_scspr.set(address),
+                ComponentId::Stablecoin => self.stablecoin.set(address),
+                ComponentId::ScsprToken => self.scspr_token.set(address),
+                _ => {}
+            }
+        }
+    }
+
     // ========== Liquidation Functions ==========
 
     /// Liquidate a single vault
+    ///
+    /// Reverts with `VaultNotFound` or `NotLiquidatable`; see `liquidate_or_skip`
+    /// for a variant that returns `None` instead. With a dispute window set the
+    /// returned outcome is queued, not yet executed.
     pub fn liquidate(&mut self, collateral_id: CollateralId, vault_owner: Address, vault_id: u64) -> LiquidationResult {
         // Check safe mode - liquidations blocked
         self.require_not_safe_mode();
 
-        // Get vault data and check if liquidatable
-        let vault_data = self.get_vault_data(collateral_id, vault_owner, vault_id);
-        if vault_data.collateral.is_zero() && vault_data.debt.is_zero() {
-            self.env().revert(CdpError::VaultNotFound);
+        match self.liquidate_if_eligible(collateral_id, vault_owner, vault_id) {
+            Ok(result) => result,
+            Err(err) => self.env().revert(err),
         }
+    }
+
+    /// Liquidate a single vault, or return `None` if it is healthy or gone
+    ///
+    /// Lets racing keepers lose without reverting. Safe mode still reverts.
+    pub fn liquidate_or_skip(
+        &mut self,
+        collateral_id: CollateralId,
+        vault_owner: Address,
+        vault_id: u64,
+    ) -> Option<LiquidationResult> {
+        self.require_not_safe_mode();
+        self.liquidate_if_eligible(collateral_id, vault_owner, vault_id).ok()
+    }
+
+    /// Liquidate a vault that exists and is below MCR, updating statistics
+    fn liquidate_if_eligible(
+        &mut self,
+        collateral_id: CollateralId,
+        vault_owner: Address,
+        vault_id: u64,
+    ) -> Result<LiquidationResult, CdpError> {
+        let vault_data = self.get_vault_data(collateral_id, vault_owner, vault_id);
 
         // Get current price
         let price = self.get_price(collateral_id);
+        if price.is_zero() {
+            return Err(CdpError::OraclePriceUnavailable);
+        }
 
-        // Calculate ICR
+        // Calculate ICR and check the vault exists and is liquidatable
         let collateral_value = self.calculate_collateral_value(vault_data.collateral, price);
         let icr_bps = self.calculate_icr(collateral_value, vault_data.debt);
-
-        // Check if vault is liquidatable
-        if icr_bps >= MCR_BPS {
-            self.env().revert(CdpError::NotLiquidatable);
-        }
+        check_liquidatable(vault_data.collateral, vault_data.debt, icr_bps, self.get_mcr(collateral_id))?;
 
         let vault_key = VaultKey { owner: vault_owner, id: vault_id };
+        if self.has_pending_liquidation(collateral_id, vault_key) {
+            return Err(CdpError::LiquidationPending);
+        }
 
         // Calculate liquidation amounts
         let result = self.calculate_liquidation(
@@ -225,20 +430,10 @@ impl LiquidationEngine {
             price,
         );
 
-        // Update statistics
-        let total_liq = self.total_liquidations.get().unwrap_or(0);
-        self.total_liquidations.set(total_liq + 1);
+        // Execute actual liquidation, or queue it for the dispute window
+        self.execute_or_queue(collateral_id, &result);
 
-        let total_debt = self.total_debt_liquidated.get().unwrap_or(U256::zero());
-        self.total_debt_liquidated.set(total_debt + result.debt_liquidated);
-
-        let total_coll = self.total_collateral_seized.get().unwrap_or(U256::zero());
-        self.total_collateral_seized.set(total_coll + result.collateral_seized);
-
-        // Execute actual liquidation
-        self.execute_liquidation(collateral_id, &result);
-
-        result
+        Ok(result)
     }
 
     /// Frontend-friendly liquidate using primitive types
@@ -254,23 +449,50 @@ impl LiquidationEngine {
     }
 
     /// Batch liquidate multiple vaults (gas efficient)
+    ///
+    /// The oracle price is re-fetched every `refresh_price_every` vaults
+    /// (0 = once per batch). If safe mode is latched or the refreshed price is
+    /// unavailable, the batch stops and returns what was liquidated so far.
+    ///
+    /// `max_vaults` may not exceed `max_liquidations_per_tx`, which bounds the
+    /// gas a batch can use. The batch is atomic like any Casper call: per-vault
+    /// conditions (healthy, empty, already pending) skip the vault instead of
+    /// reverting, so a revert only happens on a failed cross-contract call and
+    /// then undoes the whole batch.
     pub fn batch_liquidate(
         &mut self,
         collateral_id: CollateralId,
         vault_keys: Vec<VaultKey>,
         max_vaults: u32,
+        refresh_price_every: u32,
     ) -> BatchLiquidationResult {
         // Check safe mode
         self.require_not_safe_mode();
+        if let Err(err) = check_batch_size(max_vaults, self.get_max_liquidations_per_tx()) {
+            self.env().revert(err);
+        }
 
         let mut vaults_liquidated: u32 = 0;
         let mut total_debt = U256::zero();
         let mut total_collateral = U256::zero();
 
-        // Get price once for batch efficiency
-        let price = self.get_price(collateral_id);
+        // Get price and MCR once for batch efficiency
+        let mut price = self.get_price(collateral_id);
+        let mcr_bps = self.get_mcr(collateral_id);
+
+        // Without a usable price the batch stops before touching any vault
+        let mut aborted = price.is_zero();
+        let max_vaults = if aborted { 0 } else { max_vaults };
+
+        for (index, vault_key) in vault_keys.iter().take(max_vaults as usize).enumerate() {
+            if should_refresh_price(index as u32, refresh_price_every) {
+                price = self.get_price(collateral_id);
+                if !batch_status_ok(self.is_safe_mode_active(), price) {
+                    aborted = true;
+                    break;
+                }
+            }
 
-        for vault_key in vault_keys.iter().take(max_vaults as usize) {
             let vault_data = self.get_vault_data(collateral_id, vault_key.owner, vault_key.id);
 
             // Skip empty vaults
@@ -282,8 +504,8 @@ impl LiquidationEngine {
             let collateral_value = self.calculate_collateral_value(vault_data.collateral, price);
             let icr_bps = self.calculate_icr(collateral_value, vault_data.debt);
 
-            // Skip healthy vaults
-            if icr_bps >= MCR_BPS {
+            // Skip healthy vaults and vaults already waiting out a dispute
+            if !is_below_mcr(icr_bps, mcr_bps) || self.has_pending_liquidation(collateral_id, *vault_key) {
                 continue;
             }
 
@@ -297,32 +519,94 @@ impl LiquidationEngine {
             );
 
             // Execute the liquidation
-            self.execute_liquidation(collateral_id, &result);
+            self.execute_or_queue(collateral_id, &result);
 
             vaults_liquidated += 1;
             total_debt = total_debt + result.debt_liquidated;
             total_collateral = total_collateral + result.collateral_seized;
         }
 
-        // Update cumulative stats
-        let total_liq = self.total_liquidations.get().unwrap_or(0);
-        self.total_liquidations.set(total_liq + vaults_liquidated as u64);
-
-        let cumulative_debt = self.total_debt_liquidated.get().unwrap_or(U256::zero());
-        self.total_debt_liquidated.set(cumulative_debt + total_debt);
-
-        let cumulative_coll = self.total_collateral_seized.get().unwrap_or(U256::zero());
-        self.total_collateral_seized.set(cumulative_coll + total_collateral);
-
         BatchLiquidationResult {
             vaults_liquidated,
             total_debt_liquidated: total_debt,
             total_collateral_seized: total_collateral,
+            aborted,
         }
     }
 
+    /// Execute a queued liquidation once its dispute window has passed
+    ///
+    /// Callable by anyone. The branch re-checks the seized amounts against the
+    /// vault as it stands now, so a vault changed in the meantime reverts here.
+    pub fn finalize_liquidation(&mut self, id: u64) {
+        let mut pending = match self.state.pending_liquidations.get(&id) {
+            Some(pending) => pending,
+            None => self.env().revert(CdpError::LiquidationNotPending),
+        };
+        if let Err(err) = check_finalize(pending.status, pending.finalizes_at, self.env().get_block_time()) {
+            self.env().revert(err);
+        }
+
+        pending.status = PendingLiquidationStatus::Finalized;
+        let collateral_id = pending.result.collateral_id;
+        self.state.pending_by_vault.set(&(collateral_id, pending.result.vault_key), 0);
+        self.state.pending_liquidations.set(&id, pending.clone());
+        self.execute_liquidation(collateral_id, &pending.result, pending.liquidator);
+    }
+
+    /// Cancel a queued liquidation inside its dispute window (guardian only)
+    ///
+    /// Nothing was moved while the liquidation was pending, so the vault is
+    /// left exactly as it was.
+    pub fn revert_liquidation(&mut self, id: u64) {
+        self.require_guardian();
+        let mut pending = match self.state.pending_liquidations.get(&id) {
+            Some(pending) => pending,
+            None => self.env().revert(CdpError::LiquidationNotPending),
+        };
+        if let Err(err) = check_revert(pending.status, pending.finalizes_at, self.env().get_block_time()) {
+            self.env().revert(err);
+        }
+
+        pending.status = PendingLiquidationStatus::Reverted;
+        let collateral_id = pending.result.collateral_id;
+        let vault_key = pending.result.vault_key;
+        self.state.pending_by_vault.set(&(collateral_id, vault_key), 0);
+        self.state.pending_liquidations.set(&id, pending);
+
+        let seq = self.next_event_seq();
+        self.env().emit_event(LiquidationReverted {
+            seq,
+            id,
+            collateral_id,
+            owner: vault_key.owner,
+            vault_id: vault_key.id,
+        });
+    }
+
     // ========== Query Functions ==========
 
+    /// Get a queued liquidation by id
+    pub fn get_pending_liquidation(&self, id: u64) -> Option<PendingLiquidation> {
+        self.state.pending_liquidations.get(&id)
+    }
+
+    /// Id of the vault's pending liquidation (0 = none)
+    pub fn get_pending_liquidation_id(&self, collateral_id: CollateralId, vault_owner: Address, vault_id: u64) -> u64 {
+        let vault_key = VaultKey { owner: vault_owner, id: vault_id };
+        self.state.pending_by_vault.get(&(collateral_id, vault_key)).unwrap_or(0)
+    }
+
+    /// Get the liquidation dispute window in seconds (0 = immediate)
+    pub fn get_dispute_window_seconds(&self) -> u64 {
+        self.state.dispute_window_seconds.get_or_default()
+    }
+
+    /// Get the dispute guardian
+    pub fn get_guardian(&self) -> Option<Address> {
+        self.state.guardian.get()
+    }
+
     /// Check if a vault is liquidatable
     pub fn is_liquidatable(&self, collateral_id: CollateralId, vault_owner: Address, vault_id: u64) -> bool {
         let vault_data = self.get_vault_data(collateral_id, vault_owner, vault_id);
@@ -334,7 +618,7 @@ impl LiquidationEngine {
         let collateral_value = self.calculate_collateral_value(vault_data.collateral, price);
         let icr_bps = self.calculate_icr(collateral_value, vault_data.debt);
 
-        icr_bps < MCR_BPS
+        is_below_mcr(icr_bps, self.get_mcr(collateral_id))
     }
 
     /// Get liquidation statistics
@@ -374,19 +658,104 @@ impl LiquidationEngine {
         self.gas_compensation.set(amount);
     }
 
+    /// Pay gas compensation in gUSD instead of collateral (admin only)
+    ///
+    /// In gUSD mode the SP offsets the full debt and receives all seized
+    /// collateral, and the compensation is paid from the engine's gUSD gas
+    /// reserve. Nothing is minted, so supply stays matched to debt; a short
+    /// reserve pays out what it holds. Fund it with a plain gUSD transfer to
+    /// this contract.
+    pub fn set_gas_compensation_in_gusd(&mut self, enabled: bool) {
+        // TODO: Add admin access control
+        self.state.gas_compensation_in_gusd.set(enabled);
+    }
+
+    /// Whether gas compensation is paid in gUSD
+    pub fn is_gas_compensation_in_gusd(&self) -> bool {
+        self.state.gas_compensation_in_gusd.get().unwrap_or(false)
+    }
+
+    /// gUSD held by the engine for gas compensation
+    pub fn get_gas_reserve(&self) -> U256 {
+        match self.stablecoin.get() {
+            Some(stablecoin_addr) => {
+                let args = runtime_args! { "owner" => self.env().self_address() };
+                let call_def = CallDef::new("balance_of", false, args);
+                self.env().call_contract(stablecoin_addr, call_def)
+            }
+            None => U256::zero(),
+        }
+    }
+
+    /// Set liquidator bounty as a share of the penalty in bps (admin only)
+    ///
+    /// The bounty plus any collateral gas compensation is capped at the
+    /// penalty portion of the seized collateral, so SP depositors always
+    /// receive at least the collateral backing the covered debt.
+    pub fn set_liquidator_bounty_bps(&mut self, bounty_bps: u32) {
+        // TODO: Add admin access control
+        if bounty_bps > BPS_SCALE {
+            self.env().revert(CdpError::InvalidConfig);
+        }
+        self.state.liquidator_bounty_bps.set(bounty_bps);
+    }
+
+    /// Get liquidator bounty share of the penalty in bps
+    pub fn get_liquidator_bounty_bps(&self) -> u32 {
+        self.state.liquidator_bounty_bps.get().unwrap_or(0)
+    }
+
+    /// Set the liquidation dispute window in seconds (admin only)
+    ///
+    /// Zero executes liquidations immediately. Queued liquidations keep the
+    /// window they were created with.
+    pub fn set_dispute_window_seconds(&mut self, window_seconds: u64) {
+        // TODO: Add admin access control
+        if window_seconds > MAX_DISPUTE_WINDOW_SECONDS {
+            self.env().revert(CdpError::InvalidConfig);
+        }
+        self.state.dispute_window_seconds.set(window_seconds);
+    }
+
+    /// Get the cap on vaults processed per `batch_liquidate` call
+    pub fn get_max_liquidations_per_tx(&self) -> u32 {
+        self.state.max_liquidations_per_tx.get().unwrap_or(DEFAULT_MAX_LIQUIDATIONS_PER_TX)
+    }
+
+    /// Set the cap on vaults processed per `batch_liquidate` call (admin only, non-zero)
+    pub fn set_max_liquidations_per_tx(&mut self, max_liquidations: u32) {
+        // TODO: Add admin access control
+        if max_liquidations == 0 {
+            self.env().revert(CdpError::InvalidConfig);
+        }
+        self.state.max_liquidations_per_tx.set(max_liquidations);
+    }
+
+    /// Set the account allowed to revert liquidations in the dispute window (admin only)
+    pub fn set_guardian(&mut self, guardian: Address) {
+        // TODO: Add admin access control
+        self.state.guardian.set(guardian);
+    }
+
+    /// Sequence number of the last emitted liquidation event (0 if none yet)
+    pub fn get_event_seq(&self) -> u64 {
+        self.state.event_seq.get_or_default()
+    }
+
     /// Trigger safe mode
     pub fn trigger_safe_mode(&mut self, reason: OracleStatus) {
-        self.safe_mode.set(SafeModeState {
+        let safe_mode = SafeModeState {
             is_active: true,
             triggered_at: self.env().get_block_time(),
             reason,
-        });
+        };
+        self.state.safe_mode.set(safe_mode);
     }
 
     /// Clear safe mode
     pub fn clear_safe_mode(&mut self) {
         // TODO: Add admin access control
-        self.safe_mode.set(SafeModeState {
+        self.state.safe_mode.set(SafeModeState {
             is_active: false,
             triggered_at: 0,
             reason: OracleStatus::Ok,
@@ -395,43 +764,150 @@ impl LiquidationEngine {
 
     // ========== Internal Functions ==========
 
-    fn require_not_safe_mode(&self) {
-        let state = self.safe_mode.get().unwrap_or(SafeModeState {
-            is_active: false,
-            triggered_at: 0,
-            reason: OracleStatus::Ok,
+    /// Advance the event sequence counter and return the new value
+    fn next_event_seq(&mut self) -> u64 {
+        let seq = next_event_seq(self.state.event_seq.get_or_default());
+        self.state.event_seq.set(seq);
+        seq
+    }
+
+    fn require_guardian(&self) {
+        if self.state.guardian.get() != Some(self.env().caller()) {
+            self.env().revert(CdpError::Unauthorized);
+        }
+    }
+
+    fn require_registry_admin(&self) {
+        let registry_addr = match self.registry.get() {
+            Some(addr) => addr,
+            None => self.env().revert(CdpError::InvalidConfig),
+        };
+        let args = runtime_args! {
+            "caller" => self.env().caller()
+        };
+        let is_admin: bool = self.env().call_contract(registry_addr, CallDef::new("is_admin", false, args));
+        if !is_admin {
+            self.env().revert(CdpError::Unauthorized);
+        }
+    }
+
+    fn has_pending_liquidation(&self, collateral_id: CollateralId, vault_key: VaultKey) -> bool {
+        self.state.pending_by_vault.get(&(collateral_id, vault_key)).unwrap_or(0) != 0
+    }
+
+    /// Execute now, or queue for finalization when a dispute window is set
+    fn execute_or_queue(&mut self, collateral_id: CollateralId, result: &LiquidationResult) {
+        let liquidator = self.env().caller();
+        let window = self.get_dispute_window_seconds();
+        if window == 0 {
+            self.execute_liquidation(collateral_id, result, liquidator);
+            return;
+        }
+
+        let id = self.state.pending_liquidation_count.get_or_default() + 1;
+        self.state.pending_liquidation_count.set(id);
+        let finalizes_at = self.env().get_block_time().saturating_add(window);
+        self.state.pending_liquidations.set(
+            &id,
+            PendingLiquidation {
+                result: result.clone(),
+                liquidator,
+                finalizes_at,
+                status: PendingLiquidationStatus::Pending,
+            },
+        );
+        self.state.pending_by_vault.set(&(collateral_id, result.vault_key), id);
+
+        let seq = self.next_event_seq();
+        self.env().emit_event(LiquidationQueued {
+            seq,
+            id,
+            collateral_id,
+            owner: result.vault_key.owner,
+            vault_id: result.vault_key.id,
+            finalizes_at,
         });
-        if state.is_active {
+    }
+
+    fn enter_non_reentrant(&mut self) {
+        if let Err(err) = check_not_entered(self.state.reentrancy_lock.get().unwrap_or(false)) {
+            self.env().revert(err);
+        }
+        self.state.reentrancy_lock.set(true);
+    }
+
+    fn exit_non_reentrant(&mut self) {
+        self.state.reentrancy_lock.set(false);
+    }
+
+    fn require_not_safe_mode(&self) {
+        if self.is_safe_mode_active() {
             self.env().revert(CdpError::SafeModeActive);
         }
     }
 
+    fn is_safe_mode_active(&self) -> bool {
+        self.state.safe_mode
+            .get()
+            .map(|state| state.is_active)
+            .unwrap_or(false)
+    }
+
+    /// MCR configured on the collateral's branch (default if the branch is unset)
+    fn get_mcr(&self, collateral_id: CollateralId) -> u32 {
+        let branch_addr = match collateral_id {
+            CollateralId::Cspr => self.branch_cspr.get(),
+            CollateralId::SCSPR => self.branch_scspr.get(),
+        };
+        match branch_addr {
+            Some(addr) => {
+                let call = CallDef::new("get_mcr", false, runtime_args! {});
+                self.env().call_contract::<u32>(addr, call)
+            }
+            None => MCR_BPS,
+        }
+    }
+
     fn get_vault_data(&self, collateral_id: CollateralId, owner: Address, vault_id: u64) -> VaultDataSimple {
         let branch_addr = match collateral_id {
             CollateralId::Cspr => self.branch_cspr.get().expect("branch_cspr not set"),
             CollateralId::SCSPR => self.branch_scspr.get().expect("branch_scspr not set"),
         };
 
-        // Get collateral from branch
+        // Get collateral from branch (including pending redistribution)
         let get_coll_args = runtime_args! {
             "owner" => owner,
             "vault_id" => vault_id
         };
-        let get_coll_call = CallDef::new("get_collateral", false, get_coll_args);
+        let get_coll_call = CallDef::new("get_effective_collateral", false, get_coll_args);
         let collateral: U256 = self.env().call_contract(branch_addr, get_coll_call);
 
-        // Get debt from branch
+        // Get debt from branch (including pending redistribution)
         let get_debt_args = runtime_args! {
             "owner" => owner,
             "vault_id" => vault_id
         };
-        let get_debt_call = CallDef::new("get_debt", false, get_debt_args);
+        let get_debt_call = CallDef::new("get_effective_debt", false, get_debt_args);
         let debt: U256 = self.env().call_contract(branch_addr, get_debt_call);
 
         VaultDataSimple { collateral, debt }
     }
 
+    /// Collateral price used to value vaults (zero if unavailable)
+    ///
+    /// With an oracle adapter set, this is its `get_liquidation_price`: for
+    /// stCSPR the composite P(CSPR) * R, with the confirmed CSPR leg. Without
+    /// one, Styks is read directly and R comes from the ybToken.
     fn get_price(&self, collateral_id: CollateralId) -> U256 {
+        if let Some(adapter_addr) = self.state.oracle_adapter.get() {
+            let args = runtime_args! {
+                "collateral_id" => collateral_id
+            };
+            let call_def = CallDef::new("get_liquidation_price", false, args);
+            let price_data: PriceData = self.env().call_contract(adapter_addr, call_def);
+            return usable_price(&price_data);
+        }
+
         let styks_addr = self.styks_oracle.get().expect("styks_oracle not set");
 
         // Get stCSPR exchange rate if needed
@@ -452,8 +928,9 @@ impl LiquidationEngine {
         Some(self.env().call_contract::<U256>(ybtoken_addr, call_def))
     }
 
-    fn execute_liquidation(&mut self, collateral_id: CollateralId, result: &LiquidationResult) {
-        let liquidator = self.env().caller();
+    fn execute_liquidation(&mut self, collateral_id: CollateralId, result: &LiquidationResult, liquidator: Address) {
+        self.enter_non_reentrant();
+        self.record_liquidation_stats(result);
 
         // 1. Get branch address
         let branch_addr = match collateral_id {
@@ -479,8 +956,17 @@ impl LiquidationEngine {
         let reduce_debt_call = CallDef::new("reduce_debt", true, reduce_debt_args);
         self.env().call_contract::<()>(branch_addr, reduce_debt_call);
 
-        // 4. Offset debt with stability pool
-        if !result.debt_liquidated.is_zero() {
+        // 4. Offset debt with stability pool, redistributing what it cannot absorb
+        let sp_deposits = match self.stability_pool.get() {
+            Some(sp_addr) => {
+                let deposits_call = CallDef::new("get_total_deposits", false, runtime_args! {});
+                self.env().call_contract::<U256>(sp_addr, deposits_call)
+            }
+            None => U256::zero(),
+        };
+        let (sp_debt, sp_collateral, redistributed_debt, redistributed_collateral) =
+            split_offset(result.debt_liquidated, result.collateral_to_sp, sp_deposits);
+        if !sp_debt.is_zero() {
             if let Some(sp_addr) = self.stability_pool.get() {
                 let coll_id: u8 = match collateral_id {
                     CollateralId::Cspr => 0,
@@ -490,18 +976,18 @@ impl LiquidationEngine {
                 // Call SP offset to record the debt absorption
                 let offset_args = runtime_args! {
                     "collateral_id" => coll_id,
-                    "debt_to_offset" => result.debt_liquidated,
-                    "collateral_to_add" => result.collateral_to_sp
+                    "debt_to_offset" => sp_debt,
+                    "collateral_to_add" => sp_collateral
                 };
                 let offset_call = CallDef::new("offset_u8", true, offset_args);
                 let _offset_result: U256 = self.env().call_contract(sp_addr, offset_call);
 
                 // Transfer collateral to SP
-                if !result.collateral_to_sp.is_zero() {
+                if !sp_collateral.is_zero() {
                     match collateral_id {
                         CollateralId::Cspr => {
                             // Native CSPR transfer to SP via receive_cspr_collateral
-                            self.env().transfer_tokens(&sp_addr, &u256_to_u512(result.collateral_to_sp));
+                            self.env().transfer_tokens(&sp_addr, &u256_to_u512(sp_collateral));
                         }
                         CollateralId::SCSPR => {
                             // For stCSPR, approve and call receive_scspr_collateral
@@ -510,7 +996,7 @@ impl LiquidationEngine {
                             let engine_addr = self.env().self_address();
                             let receive_args = runtime_args! {
                                 "from" => engine_addr,
-                                "amount" => result.collateral_to_sp
+                                "amount" => sp_collateral
                             };
                             let receive_call = CallDef::new("receive_scspr_collateral", true, receive_args);
                             self.env().call_contract::<()>(sp_addr, receive_call);
@@ -519,6 +1005,14 @@ impl LiquidationEngine {
                 }
             }
         }
+        if !redistributed_debt.is_zero() {
+            let redistribute_args = runtime_args! {
+                "debt" => redistributed_debt,
+                "collateral" => redistributed_collateral
+            };
+            let redistribute_call = CallDef::new("redistribute_debt_and_collateral", true, redistribute_args);
+            self.env().call_contract::<()>(branch_addr, redistribute_call);
+        }
 
         // 5. Transfer gas compensation to liquidator
         if !result.collateral_to_liquidator.is_zero() {
@@ -540,6 +1034,18 @@ impl LiquidationEngine {
                 }
             }
         }
+        if !result.gusd_to_liquidator.is_zero() {
+            let stablecoin_addr = self.stablecoin.get().expect("stablecoin not set");
+            let transfer_args = runtime_args! {
+                "recipient" => liquidator,
+                "amount" => result.gusd_to_liquidator
+            };
+            let transfer_call = CallDef::new("transfer", true, transfer_args);
+            let success: bool = self.env().call_contract(stablecoin_addr, transfer_call);
+            if !success {
+                self.env().revert(CdpError::InsufficientTokenBalance);
+            }
+        }
 
         // 6. Close vault if fully liquidated
         if result.fully_liquidated {
@@ -550,11 +1056,33 @@ impl LiquidationEngine {
             let close_call = CallDef::new("close_vault_for_liquidation", true, close_args);
             self.env().call_contract::<()>(branch_addr, close_call);
         }
+
+        let seq = self.next_event_seq();
+        self.env().emit_event(Liquidated {
+            seq,
+            collateral_id,
+            vault_key: result.vault_key,
+            liquidator,
+            debt_liquidated: result.debt_liquidated,
+            collateral_seized: result.collateral_seized,
+            fully_liquidated: result.fully_liquidated,
+        });
+        self.exit_non_reentrant();
+    }
+
+    fn record_liquidation_stats(&mut self, result: &LiquidationResult) {
+        let total_liq = self.total_liquidations.get().unwrap_or(0);
+        self.total_liquidations.set(total_liq + 1);
+
+        let total_debt = self.total_debt_liquidated.get().unwrap_or(U256::zero());
+        self.total_debt_liquidated.set(total_debt + result.debt_liquidated);
+
+        let total_coll = self.total_collateral_seized.get().unwrap_or(U256::zero());
+        self.total_collateral_seized.set(total_coll + result.collateral_seized);
     }
 
     fn calculate_collateral_value(&self, collateral: U256, price: U256) -> U256 {
-        // collateral (9 dec) * price (18 dec) / 1e9 = value (18 dec)
-        collateral * price / U256::from(COLLATERAL_DECIMALS)
+        collateral_to_value(collateral, price)
     }
 
     fn calculate_icr(&self, collateral_value: U256, debt: U256) -> u32 {
@@ -583,7 +1111,7 @@ impl LiquidationEngine {
         // collateral (9 dec) = debt (18 dec) * penalty_multiplier / BPS_SCALE * 1e9 / price (18 dec)
         let penalty_multiplier = U256::from(BPS_SCALE + penalty_bps);
         let collateral_value_needed = debt * penalty_multiplier / U256::from(BPS_SCALE);
-        let collateral_to_seize = collateral_value_needed * U256::from(COLLATERAL_DECIMALS) / price;
+        let collateral_to_seize = value_to_collateral(collateral_value_needed, price);
 
         // Cap at available collateral
         let actual_collateral_seized = if collateral_to_seize > collateral {
@@ -596,24 +1124,33 @@ impl LiquidationEngine {
         let debt_covered = if collateral_to_seize > collateral {
             // Partial liquidation due to insufficient collateral
             // debt (18 dec) = collateral (9 dec) * price (18 dec) / 1e9 * BPS_SCALE / penalty_multiplier
-            collateral * price * U256::from(BPS_SCALE) / U256::from(COLLATERAL_DECIMALS) / penalty_multiplier
+            collateral * price * U256::from(BPS_SCALE) / U256::from(COLLATERAL_SCALE) / penalty_multiplier
         } else {
             debt
         };
 
         let fully_liquidated = collateral_to_seize <= collateral;
 
-        // Gas compensation for liquidator (small portion of collateral)
-        // gas_comp is in gUSD (18 dec), convert to collateral (9 dec)
         let gas_comp = self.gas_compensation.get().unwrap_or(U256::zero());
-        let gas_comp_in_collateral = gas_comp * U256::from(COLLATERAL_DECIMALS) / price;
-        let collateral_to_liquidator = if gas_comp_in_collateral > actual_collateral_seized {
-            actual_collateral_seized / U256::from(100) // 1% fallback
-        } else {
-            gas_comp_in_collateral
-        };
+        let in_gusd = self.is_gas_compensation_in_gusd();
+        let gas_reserve = if in_gusd { self.get_gas_reserve() } else { U256::zero() };
+        let (collateral_to_sp, collateral_to_liquidator, gusd_to_liquidator) = split_gas_compensation(
+            actual_collateral_seized,
+            gas_comp,
+            gas_reserve,
+            price,
+            in_gusd,
+        );
 
-        let collateral_to_sp = actual_collateral_seized - collateral_to_liquidator;
+        // Carve the liquidator bounty out of the penalty portion
+        let debt_collateral = value_to_collateral(debt_covered, price);
+        let penalty_collateral = actual_collateral_seized.saturating_sub(debt_collateral);
+        let (collateral_to_sp, collateral_to_liquidator) = carve_liquidator_bounty(
+            collateral_to_sp,
+            collateral_to_liquidator,
+            penalty_collateral,
+            self.get_liquidator_bounty_bps(),
+        );
 
         LiquidationResult {
             vault_key,
@@ -622,6 +1159,7 @@ impl LiquidationEngine {
             collateral_seized: actual_collateral_seized,
             collateral_to_sp,
             collateral_to_liquidator,
+            gusd_to_liquidator,
             fully_liquidated,
         }
     }
@@ -646,6 +1184,161 @@ pub struct LiquidationStats {
 
 // ===== Helper Functions =====
 
+/// Whether the batch price should be re-fetched before the vault at `index`
+///
+/// The first vault uses the price read at batch start; `every == 0` never refreshes.
+fn should_refresh_price(index: u32, every: u32) -> bool {
+    every != 0 && index != 0 && index % every == 0
+}
+
+/// Price from an adapter reading, or zero unless its status is Ok
+fn usable_price(price_data: &PriceData) -> U256 {
+    if price_data.status == OracleStatus::Ok {
+        price_data.price_int
+    } else {
+        U256::zero()
+    }
+}
+
+/// Whether a batch may keep liquidating after a price refresh
+fn batch_status_ok(safe_mode_active: bool, price: U256) -> bool {
+    !safe_mode_active && !price.is_zero()
+}
+
+/// Why a vault cannot be liquidated, if it can't
+fn check_liquidatable(collateral: U256, debt: U256, icr_bps: u32, mcr_bps: u32) -> Result<(), CdpError> {
+    if collateral.is_zero() && debt.is_zero() {
+        return Err(CdpError::VaultNotFound);
+    }
+    if !is_below_mcr(icr_bps, mcr_bps) {
+        return Err(CdpError::NotLiquidatable);
+    }
+    Ok(())
+}
+
+/// Whether an ICR is below the given MCR (liquidatable)
+fn is_below_mcr(icr_bps: u32, mcr_bps: u32) -> bool {
+    icr_bps < mcr_bps
+}
+
+/// Collateral price (18 dec) below which a vault drops under `mcr_bps`
+///
+/// `collateral` is in priced units (9 dec), i.e. already converted through any
+/// exchange rate. Zero debt has no liquidation price (returns zero); zero
+/// collateral returns `U256::MAX`.
+pub fn liquidation_price(debt: U256, collateral: U256, mcr_bps: u32) -> U256 {
+    if debt.is_zero() {
+        return U256::zero();
+    }
+    if collateral.is_zero() {
+        return U256::MAX;
+    }
+    // price = debt * MCR / (BPS_SCALE * collateral), rescaled to 9-decimal collateral
+    debt * U256::from(mcr_bps) * U256::from(COLLATERAL_SCALE)
+        / (U256::from(BPS_SCALE) * collateral)
+}
+
+/// Health factor (18 dec): ICR / MCR, so 1e18 is exactly at MCR
+///
+/// Computed from the collateral value rather than the bps ICR to keep full
+/// precision. Debt-free vaults return `U256::MAX`.
+pub fn health_factor(collateral_value: U256, debt: U256, mcr_bps: u32) -> U256 {
+    if debt.is_zero() {
+        return U256::MAX;
+    }
+    collateral_value * U256::from(PRICE_SCALE) * U256::from(BPS_SCALE)
+        / (debt * U256::from(mcr_bps))
+}
+
+/// Split liquidated debt and SP collateral between the SP and redistribution
+///
+/// Returns (sp_debt, sp_collateral, redistributed_debt, redistributed_collateral).
+/// The SP absorbs at most its deposits and takes collateral pro rata; the
+/// remainder goes to the branch's other vaults.
+fn split_offset(debt: U256, collateral_to_sp: U256, sp_deposits: U256) -> (U256, U256, U256, U256) {
+    if debt <= sp_deposits {
+        return (debt, collateral_to_sp, U256::zero(), U256::zero());
+    }
+    let sp_collateral = collateral_to_sp * sp_deposits / debt;
+    (sp_deposits, sp_collateral, debt - sp_deposits, collateral_to_sp - sp_collateral)
+}
+
+/// Split seized collateral between SP and liquidator gas compensation
+///
+/// Returns (collateral_to_sp, collateral_to_liquidator, gusd_to_liquidator).
+/// - Collateral mode: gas_comp (gUSD, 18 dec) is converted to collateral (9 dec)
+///   and taken from the seized amount (1% fallback if it exceeds the seizure).
+/// - gUSD mode: SP receives all seized collateral; gas_comp is paid from the
+///   engine's gUSD gas reserve and capped at what the reserve holds.
+fn split_gas_compensation(
+    collateral_seized: U256,
+    gas_comp: U256,
+    gas_reserve: U256,
+    price: U256,
+    in_gusd: bool,
+) -> (U256, U256, U256) {
+    if in_gusd {
+        let gusd_to_liquidator = if gas_comp > gas_reserve { gas_reserve } else { gas_comp };
+        return (collateral_seized, U256::zero(), gusd_to_liquidator);
+    }
+
+    let gas_comp_in_collateral = value_to_collateral(gas_comp, price);
+    let collateral_to_liquidator = if gas_comp_in_collateral > collateral_seized {
+        collateral_seized / U256::from(100) // 1% fallback
+    } else {
+        gas_comp_in_collateral
+    };
+    (collateral_seized - collateral_to_liquidator, collateral_to_liquidator, U256::zero())
+}
+
+/// Move a bounty share of the penalty collateral from the SP to the liquidator
+///
+/// Returns (collateral_to_sp, collateral_to_liquidator). The liquidator's total
+/// (gas compensation plus bounty) never exceeds `penalty_collateral`, unless the
+/// gas compensation alone already does.
+fn carve_liquidator_bounty(
+    collateral_to_sp: U256,
+    collateral_to_liquidator: U256,
+    penalty_collateral: U256,
+    bounty_bps: u32,
+) -> (U256, U256) {
+    let bounty = penalty_collateral * U256::from(bounty_bps) / U256::from(BPS_SCALE);
+    let headroom = penalty_collateral.saturating_sub(collateral_to_liquidator);
+    let bounty = bounty.min(headroom).min(collateral_to_sp);
+    (collateral_to_sp - bounty, collateral_to_liquidator + bounty)
+}
+
+/// A batch may not ask for more vaults than the per-transaction cap
+fn check_batch_size(max_vaults: u32, max_liquidations_per_tx: u32) -> Result<(), CdpError> {
+    if max_vaults > max_liquidations_per_tx {
+        Err(CdpError::LiquidationBatchTooLarge)
+    } else {
+        Ok(())
+    }
+}
+
+/// A queued liquidation can be finalized once its window has passed
+fn check_finalize(status: PendingLiquidationStatus, finalizes_at: u64, now: u64) -> Result<(), CdpError> {
+    if status != PendingLiquidationStatus::Pending {
+        Err(CdpError::LiquidationNotPending)
+    } else if now < finalizes_at {
+        Err(CdpError::DisputeWindowOpen)
+    } else {
+        Ok(())
+    }
+}
+
+/// A queued liquidation can be reverted only while its window is open
+fn check_revert(status: PendingLiquidationStatus, finalizes_at: u64, now: u64) -> Result<(), CdpError> {
+    if status != PendingLiquidationStatus::Pending {
+        Err(CdpError::LiquidationNotPending)
+    } else if now >= finalizes_at {
+        Err(CdpError::DisputeWindowClosed)
+    } else {
+        Ok(())
+    }
+}
+
 /// Convert U256 to U512
 fn u256_to_u512(value: U256) -> U512 {
     let mut bytes = [0u8; 32];
@@ -676,6 +1369,73 @@ mod tests {
         assert!(10999 < MCR_BPS); // Liquidatable
     }
 
+    #[test]
+    fn test_liquidate_or_skip_healthy_vault_is_noop() {
+        let collateral = U256::from(1_200u64);
+        let debt = U256::from(1_000u64);
+
+        // Healthy (120% vs 110%): liquidate reverts, liquidate_or_skip returns None
+        let healthy = check_liquidatable(collateral, debt, 12000, MCR_BPS);
+        assert_eq!(healthy, Err(CdpError::NotLiquidatable));
+        assert!(healthy.ok().is_none());
+
+        // Already closed by another keeper
+        let gone = check_liquidatable(U256::zero(), U256::zero(), u32::MAX, MCR_BPS);
+        assert_eq!(gone, Err(CdpError::VaultNotFound));
+        assert!(gone.ok().is_none());
+
+        // Undercollateralized: proceeds
+        assert_eq!(check_liquidatable(collateral, debt, 10800, MCR_BPS), Ok(()));
+    }
+
+    #[test]
+    fn test_raised_scspr_mcr_makes_vault_liquidatable() {
+        // Both vaults sit at 115% ICR
+        let icr_bps = 11500;
+        let cspr_mcr = MCR_BPS;
+        assert!(!is_below_mcr(icr_bps, cspr_mcr));
+
+        // Governance raises the stCSPR MCR to 120%; only that branch's vault becomes liquidatable
+        let scspr_mcr = 12000;
+        assert!(is_below_mcr(icr_bps, scspr_mcr));
+        assert!(!is_below_mcr(icr_bps, cspr_mcr));
+    }
+
+    #[test]
+    fn test_price_refresh_cadence() {
+        // Never refresh when disabled
+        assert!((0..10).all(|i| !should_refresh_price(i, 0)));
+
+        // Every 3 vaults, skipping the first (uses the batch-start price)
+        let refreshed: Vec<u32> = (0..10).filter(|i| should_refresh_price(*i, 3)).collect();
+        assert_eq!(refreshed, vec![3, 6, 9]);
+    }
+
+    #[test]
+    fn test_batch_aborts_when_status_flips() {
+        let price = U256::from(PRICE_SCALE);
+        // Safe mode latches while vault #4 is being processed
+        let safe_mode_at = |i: u32| i >= 4;
+
+        let mut processed = 0;
+        let mut aborted = false;
+        for i in 0..10u32 {
+            if should_refresh_price(i, 2) && !batch_status_ok(safe_mode_at(i), price) {
+                aborted = true;
+                break;
+            }
+            processed += 1;
+        }
+
+        // Stopped cleanly at the next refresh after the flip
+        assert!(aborted);
+        assert_eq!(processed, 4);
+
+        // An unavailable price also stops the batch
+        assert!(!batch_status_ok(false, U256::zero()));
+        assert!(batch_status_ok(false, price));
+    }
+
     #[test]
     fn test_penalty_calculation() {
         // Debt: 1000, Penalty: 10%
@@ -686,4 +1446,155 @@ mod tests {
         let collateral_needed = debt * penalty_multiplier / U256::from(BPS_SCALE);
         assert_eq!(collateral_needed, U256::from(1100u64));
     }
+
+    #[test]
+    fn test_gas_compensation_modes_collateral_to_sp() {
+        // 1100 CSPR seized at $1 for 1000 gUSD debt, 200 gUSD gas compensation
+        let price = U256::from(PRICE_SCALE);
+        let seized = U256::from(1100u64) * U256::from(COLLATERAL_SCALE);
+        let debt = U256::from(1000u64) * U256::from(PRICE_SCALE);
+        let gas_comp = U256::from(200u64) * U256::from(PRICE_SCALE);
+
+        let reserve = U256::from(500u64) * U256::from(PRICE_SCALE);
+
+        // Collateral mode: liquidator takes 200 CSPR, SP receives the rest
+        let (to_sp, to_liq, gusd) = split_gas_compensation(seized, gas_comp, reserve, price, false);
+        assert_eq!(to_liq, U256::from(200u64) * U256::from(COLLATERAL_SCALE));
+        assert_eq!(to_sp, U256::from(900u64) * U256::from(COLLATERAL_SCALE));
+        assert!(gusd.is_zero());
+
+        // gUSD mode: SP receives all collateral and offsets the full debt,
+        // the liquidator is paid 200 gUSD from the reserve
+        let (to_sp, to_liq, gusd) = split_gas_compensation(seized, gas_comp, reserve, price, true);
+        assert_eq!(to_sp, seized);
+        assert!(to_liq.is_zero());
+        assert_eq!(gusd, gas_comp);
+        assert!(gusd < debt);
+    }
+
+    #[test]
+    fn test_liquidator_bounty_split_sums_to_seized() {
+        // 1100 seized for 1000 debt at $1: penalty portion is 100
+        let seized = U256::from(1100u64);
+        let penalty = U256::from(100u64);
+
+        // 30% of the penalty as bounty, no gas compensation
+        let (to_sp, to_liq) = carve_liquidator_bounty(seized, U256::zero(), penalty, 3000);
+        assert_eq!(to_liq, U256::from(30u64));
+        assert_eq!(to_sp + to_liq, seized);
+
+        // Bounty is capped so that bounty + gas comp <= penalty
+        let (to_sp, to_liq) = carve_liquidator_bounty(U256::from(1020u64), U256::from(80u64), penalty, 5000);
+        assert_eq!(to_liq, penalty);
+        assert_eq!(to_sp + to_liq, seized);
+
+        // Zero bounty leaves the split unchanged
+        let (to_sp, to_liq) = carve_liquidator_bounty(U256::from(1000u64), U256::from(100u64), penalty, 0);
+        assert_eq!((to_sp, to_liq), (U256::from(1000u64), U256::from(100u64)));
+    }
+
+    #[test]
+    fn test_split_offset_redistributes_what_sp_cannot_absorb() {
+        let debt = U256::from(1000u64);
+        let collateral = U256::from(1100u64);
+
+        // Deep pool absorbs everything
+        let split = split_offset(debt, collateral, U256::from(5000u64));
+        assert_eq!(split, (debt, collateral, U256::zero(), U256::zero()));
+
+        // Pool holding 400 takes 40% of the debt and collateral
+        let (sp_debt, sp_coll, re_debt, re_coll) = split_offset(debt, collateral, U256::from(400u64));
+        assert_eq!((sp_debt, sp_coll), (U256::from(400u64), U256::from(440u64)));
+        assert_eq!((re_debt, re_coll), (U256::from(600u64), U256::from(660u64)));
+
+        // Empty pool redistributes everything
+        let split = split_offset(debt, collateral, U256::zero());
+        assert_eq!(split, (U256::zero(), U256::zero(), debt, collateral));
+    }
+
+    #[test]
+    fn test_gusd_gas_compensation_capped_at_reserve() {
+        let price = U256::from(PRICE_SCALE);
+        let reserve = U256::from(50u64) * U256::from(PRICE_SCALE);
+        let gas_comp = U256::from(200u64) * U256::from(PRICE_SCALE);
+
+        let (_, _, gusd) = split_gas_compensation(U256::from(1u64), gas_comp, reserve, price, true);
+        assert_eq!(gusd, reserve);
+
+        // An empty reserve pays nothing rather than minting
+        let (_, _, gusd) = split_gas_compensation(U256::from(1u64), gas_comp, U256::zero(), price, true);
+        assert!(gusd.is_zero());
+    }
+
+    #[test]
+    fn test_dispute_window_finalize_and_revert() {
+        use PendingLiquidationStatus::{Finalized, Pending, Reverted};
+        let queued_at = 1_000u64;
+
+        // Immediate mode: a zero window is final at once and cannot be disputed
+        assert_eq!(check_finalize(Pending, queued_at, queued_at), Ok(()));
+        assert_eq!(check_revert(Pending, queued_at, queued_at), Err(CdpError::DisputeWindowClosed));
+
+        // A 10 minute window: the guardian can revert until it closes...
+        let finalizes_at = queued_at + 600;
+        assert_eq!(check_revert(Pending, finalizes_at, finalizes_at - 1), Ok(()));
+        assert_eq!(check_finalize(Pending, finalizes_at, finalizes_at - 1), Err(CdpError::DisputeWindowOpen));
+
+        // ...after which only finalization is possible
+        assert_eq!(check_revert(Pending, finalizes_at, finalizes_at), Err(CdpError::DisputeWindowClosed));
+        assert_eq!(check_finalize(Pending, finalizes_at, finalizes_at), Ok(()));
+
+        // A reverted liquidation can never be finalized, nor a finalized one reverted
+        assert_eq!(check_finalize(Reverted, finalizes_at, u64::MAX), Err(CdpError::LiquidationNotPending));
+        assert_eq!(check_revert(Finalized, finalizes_at, queued_at), Err(CdpError::LiquidationNotPending));
+        assert_eq!(check_finalize(Finalized, finalizes_at, u64::MAX), Err(CdpError::LiquidationNotPending));
+    }
+
+    #[test]
+    fn test_batch_size_cap_boundary() {
+        let cap = DEFAULT_MAX_LIQUIDATIONS_PER_TX;
+        assert_eq!(check_batch_size(0, cap), Ok(()));
+        assert_eq!(check_batch_size(cap - 1, cap), Ok(()));
+        assert_eq!(check_batch_size(cap, cap), Ok(()));
+        assert_eq!(check_batch_size(cap + 1, cap), Err(CdpError::LiquidationBatchTooLarge));
+        assert_eq!(check_batch_size(u32::MAX, cap), Err(CdpError::LiquidationBatchTooLarge));
+
+        // A lowered cap applies immediately
+        assert_eq!(check_batch_size(2, 1), Err(CdpError::LiquidationBatchTooLarge));
+        assert_eq!(check_batch_size(1, 1), Ok(()));
+    }
+
+    #[test]
+    fn test_exchange_rate_changes_scspr_liquidatability() {
+        use crate::math::apply_rate;
+
+        let icr_at = |collateral: U256, debt: U256, price: U256| {
+            let value = collateral_to_value(collateral, price);
+            (value * U256::from(BPS_SCALE) / debt).low_u32()
+        };
+        let adapter_reading = |price_int: U256, status: OracleStatus| PriceData {
+            price_int,
+            price_decimals: 18,
+            timestamp_sec: 0,
+            status,
+        };
+
+        // 1000 stCSPR against 1050 gUSD with CSPR at $1.10
+        let collateral = U256::from(1000u64) * U256::from(COLLATERAL_SCALE);
+        let debt = U256::from(1050u64) * U256::from(PRICE_SCALE);
+        let cspr_price = U256::from(PRICE_SCALE) * U256::from(11u64) / U256::from(10u64);
+
+        // A flat CSPR price values the vault at 1100: ICR ~104.8%, liquidatable
+        let flat = usable_price(&adapter_reading(cspr_price, OracleStatus::Ok));
+        assert!(is_below_mcr(icr_at(collateral, debt, flat), MCR_BPS));
+
+        // The composite price with R = 1.1 values it at 1210: ICR ~115.2%, healthy
+        let rate = U256::from(PRICE_SCALE) * U256::from(11u64) / U256::from(10u64);
+        let composite = usable_price(&adapter_reading(apply_rate(cspr_price, rate), OracleStatus::Ok));
+        assert!(!is_below_mcr(icr_at(collateral, debt, composite), MCR_BPS));
+
+        // A non-Ok reading yields no price rather than a liquidating one
+        assert_eq!(usable_price(&adapter_reading(composite, OracleStatus::Stale)), U256::zero());
+        assert!(!batch_status_ok(false, usable_price(&adapter_reading(composite, OracleStatus::Deviation))));
+    }
 }
@@ -0,0 +1,217 @@
+//! Fixed-point helpers with explicit rounding direction.
+//!
+//! Rounding policy: whatever the protocol hands out (shares minted, assets or
+//! collateral paid) rounds down; whatever it charges (interest, fees) rounds
+//! up. Either way the protocol keeps the sub-unit remainder, so repeated
+//! operations cannot leak value to users.
+//!
+//! Decimal normalization: collateral (CSPR, stCSPR) uses 9 decimals; prices,
+//! gUSD debt, values and exchange rates use 18. The conversion helpers round
+//! down.
+
+use odra::casper_types::{U256, U512};
+
+/// `a * b / c` rounded down, computed in U512 when the product overflows U256
+///
+/// Results that do not fit back into U256 saturate at `U256::MAX`.
+pub fn mul_div_floor(a: U256, b: U256, c: U256) -> U256 {
+    if let Some(product) = a.checked_mul(b) {
+        return product / c;
+    }
+    narrow(widen(a) * widen(b) / widen(c))
+}
+
+/// `a * b / c` rounded up, computed in U512 when the product overflows U256
+///
+/// Results that do not fit back into U256 saturate at `U256::MAX`.
+pub fn mul_div_ceil(a: U256, b: U256, c: U256) -> U256 {
+    if let Some(product) = a.checked_mul(b) {
+        let quotient = product / c;
+        return if (product % c).is_zero() { quotient } else { quotient + U256::one() };
+    }
+    let product = widen(a) * widen(b);
+    let divisor = widen(c);
+    let quotient = product / divisor;
+    if (product % divisor).is_zero() {
+        narrow(quotient)
+    } else {
+        narrow(quotient + U512::one())
+    }
+}
+
+/// Collateral scale (1e9)
+pub const COLLATERAL_SCALE: u64 = 1_000_000_000;
+
+/// Price, value and rate scale (1e18)
+pub const PRICE_SCALE: u64 = 1_000_000_000_000_000_000;
+
+/// Value (18 dec) of `collateral` (9 dec) at `price` (18 dec)
+pub fn collateral_to_value(collateral: U256, price: U256) -> U256 {
+    mul_div_floor(collateral, price, U256::from(COLLATERAL_SCALE))
+}
+
+/// Collateral (9 dec) worth `value` (18 dec) at `price` (18 dec)
+///
+/// `price` must be non-zero.
+pub fn value_to_collateral(value: U256, price: U256) -> U256 {
+    mul_div_floor(value, U256::from(COLLATERAL_SCALE), price)
+}
+
+/// Scale `amount` by an 18-decimal `rate` (1e18 = 1.0), keeping its decimals
+pub fn apply_rate(amount: U256, rate: U256) -> U256 {
+    mul_div_floor(amount, rate, U256::from(PRICE_SCALE))
+}
+
+/// Signed difference between a contract's actual and accounted balance
+///
+/// Returns `(magnitude, is_surplus)`: `is_surplus` is true when `actual` holds
+/// at least `accounted` (zero drift reports as a zero surplus). The sign is a
+/// flag because there is no signed 256-bit CLType.
+pub fn balance_delta(accounted: U256, actual: U256) -> (U256, bool) {
+    if actual >= accounted {
+        (actual - accounted, true)
+    } else {
+        (accounted - actual, false)
+    }
+}
+
+fn widen(value: U256) -> U512 {
+    let mut bytes = [0u8; 32];
+    value.to_little_endian(&mut bytes);
+    U512::from_little_endian(&bytes)
+}
+
+fn narrow(value: U512) -> U256 {
+    if value > widen(U256::MAX) {
+        return U256::MAX;
+    }
+    let mut bytes = [0u8; 64];
+    value.to_little_endian(&mut bytes);
+    U256::from_little_endian(&bytes[..32])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Deterministic pseudo-random U256 values spanning many magnitudes
+    fn samples() -> Vec<U256> {
+        let mut state = 0x9E37_79B9_7F4A_7C15u64;
+        let mut out = Vec::new();
+        for i in 0..200u32 {
+            state = state.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1_442_695_040_888_963_407);
+            out.push(U256::from(state >> (i % 48)) * U256::from(10u64).pow(U256::from(i % 20)) + U256::one());
+        }
+        out
+    }
+
+    #[test]
+    fn test_floor_and_ceil_bracket_exact_quotient() {
+        let values = samples();
+        for window in values.windows(3) {
+            let (a, b, c) = (window[0], window[1], window[2]);
+            let floor = mul_div_floor(a, b, c);
+            let ceil = mul_div_ceil(a, b, c);
+            let product = widen(a) * widen(b);
+
+            // floor * c <= a * b <= ceil * c, and they differ by at most one unit
+            assert!(widen(floor) * widen(c) <= product);
+            assert!(widen(ceil) * widen(c) >= product);
+            assert!(ceil - floor <= U256::one());
+        }
+    }
+
+    #[test]
+    fn test_round_trip_never_creates_value() {
+        // Deposit assets for shares (down), redeem shares for assets (down):
+        // the user never gets back more than they put in
+        let values = samples();
+        for window in values.windows(3) {
+            let (deposit, total_assets, total_shares) = (window[0], window[1], window[2]);
+            let shares = mul_div_floor(deposit, total_shares, total_assets);
+            let assets_back = mul_div_floor(shares, total_assets, total_shares);
+            assert!(assets_back <= deposit);
+        }
+    }
+
+    #[test]
+    fn test_charges_never_undercharge() {
+        // A fee charged at ceil is never below the exact fee, and never more
+        // than one unit above it
+        let values = samples();
+        for amount in values.iter() {
+            for bps in [1u64, 30, 50, 333, 9_999] {
+                let fee = mul_div_ceil(*amount, U256::from(bps), U256::from(10_000u64));
+                let exact_times_scale = widen(*amount) * widen(U256::from(bps));
+                assert!(widen(fee) * widen(U256::from(10_000u64)) >= exact_times_scale);
+                assert!(widen(fee - U256::one()) * widen(U256::from(10_000u64)) < exact_times_scale);
+            }
+        }
+    }
+
+    #[test]
+    fn test_overflow_path_and_saturation() {
+        let big = U256::MAX / U256::from(2u64);
+        assert_eq!(mul_div_floor(big, U256::from(4u64), U256::from(8u64)), big / U256::from(2u64));
+        assert_eq!(mul_div_ceil(big, U256::from(4u64), U256::from(8u64)), big / U256::from(2u64) + U256::one());
+        assert_eq!(mul_div_floor(U256::MAX, U256::MAX, U256::one()), U256::MAX);
+        assert_eq!(mul_div_ceil(U256::MAX, U256::MAX, U256::one()), U256::MAX);
+        assert_eq!(mul_div_ceil(U256::from(7u64), U256::from(1u64), U256::from(2u64)), U256::from(4u64));
+        assert_eq!(mul_div_ceil(U256::from(8u64), U256::from(1u64), U256::from(2u64)), U256::from(4u64));
+    }
+
+    fn prices() -> Vec<U256> {
+        let one = U256::from(PRICE_SCALE);
+        vec![
+            U256::from(COLLATERAL_SCALE),
+            one / U256::from(37u64),
+            one,
+            one * U256::from(3u64) / U256::from(7u64),
+            one * U256::from(12_345u64),
+        ]
+    }
+
+    fn collaterals() -> Vec<U256> {
+        vec![
+            U256::one(),
+            U256::from(999u64),
+            U256::from(COLLATERAL_SCALE),
+            U256::from(123_456_789_012u64),
+            U256::from(COLLATERAL_SCALE) * U256::from(10_000_000u64),
+        ]
+    }
+
+    #[test]
+    fn test_collateral_value_roundtrip_within_one_unit() {
+        for price in prices() {
+            for collateral in collaterals() {
+                let back = value_to_collateral(collateral_to_value(collateral, price), price);
+                assert!(back <= collateral);
+                assert!(collateral - back <= U256::one(), "price {} collateral {}", price, collateral);
+            }
+        }
+    }
+
+    #[test]
+    fn test_value_collateral_roundtrip_within_one_collateral_unit() {
+        for price in prices() {
+            // One collateral unit is worth price / 1e9 (+1 for rounding)
+            let unit_value = price / U256::from(COLLATERAL_SCALE) + U256::one();
+            for collateral in collaterals() {
+                let value = collateral * U256::from(PRICE_SCALE) + U256::from(7u64);
+                let back = collateral_to_value(value_to_collateral(value, price), price);
+                assert!(back <= value);
+                assert!(value - back <= unit_value, "price {} value {}", price, value);
+            }
+        }
+    }
+
+    #[test]
+    fn test_apply_rate() {
+        let amount = U256::from(1_000u64) * U256::from(COLLATERAL_SCALE);
+        assert_eq!(apply_rate(amount, U256::from(PRICE_SCALE)), amount);
+
+        let rate = U256::from(PRICE_SCALE) * U256::from(11u64) / U256::from(10u64);
+        assert_eq!(apply_rate(amount, rate), U256::from(1_100u64) * U256::from(COLLATERAL_SCALE));
+    }
+}
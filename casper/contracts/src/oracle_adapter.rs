@@ -5,13 +5,17 @@
 //! - Direct CSPR/USD price feed
 //! - Composite pricing for stCSPR: P(stCSPR) = P(CSPR) * R
 //! - Freshness and deviation checks
+//! - Ordered price source fallback (primary, then backups)
 //! - Safe mode triggering on oracle failures
 //! - Last good price caching
 //! - Integration with stCSPR ybToken for on-chain exchange rate
 
 use odra::prelude::*;
-use odra::casper_types::U256;
-use crate::types::{CollateralId, PriceData, OracleStatus};
+use odra::casper_types::{U256, runtime_args};
+use odra::CallDef;
+use crate::types::{CollateralId, ComponentId, PriceData, OracleStatus};
+use crate::registry::{self, resolve_all};
+use crate::styks_oracle::StyksOracle;
 use crate::errors::CdpError;
 
 /// Default maximum price age in seconds (1 hour)
@@ -30,13 +34,44 @@ const RATE_SCALE: u128 = 1_000_000_000_000_000_000;
 /// Default rate (1.0 = 1e18)
 const DEFAULT_RATE: u128 = 1_000_000_000_000_000_000;
 
+/// Maximum keeper bounty per rate sync (10 gUSD)
+const MAX_KEEPER_BOUNTY: u128 = 10 * PRICE_SCALE;
+
+/// Maximum number of configured price sources
+const MAX_PRICE_SOURCES: usize = 4;
+
+/// Decimals of every price the adapter stores or returns
+pub const CANONICAL_PRICE_DECIMALS: u8 = 18;
+
+/// Largest supported source feed precision
+const MAX_SOURCE_DECIMALS: u8 = 36;
+
+/// Maximum confirmation window for liquidation prices (1 hour)
+pub const MAX_CONFIRMATION_SECONDS: u64 = 3600;
+
+/// Updates needed to confirm a price that moved outside the tolerance band
+const MIN_CONFIRMING_UPDATES: u32 = 2;
+
+/// Band (1%) within which a print confirms, or directly replaces, a reference price
+const CONFIRMATION_TOLERANCE_BPS: u32 = 100;
+
+/// Components pulled from the registry by `refresh_addresses`
+pub const REGISTRY_DEPENDENCIES: [ComponentId; 4] = [
+    ComponentId::Router,
+    ComponentId::StyksOracle,
+    ComponentId::ScsprYbToken,
+    ComponentId::Treasury,
+];
+
 /// Oracle configuration
 #[odra::odra_type]
 pub struct OracleConfig {
     /// Maximum price age in seconds before considered stale
     pub max_price_age_seconds: u64,
-    /// Maximum price deviation from last good price in bps
-    pub max_deviation_bps: u32,
+    /// Maximum price rise from last good price in bps
+    pub max_up_deviation_bps: u32,
+    /// Maximum price drop from last good price in bps
+    pub max_down_deviation_bps: u32,
     /// Minimum valid CSPR price (sanity check)
     pub min_cspr_price: U256,
     /// Maximum valid CSPR price (sanity check)
@@ -51,7 +86,8 @@ impl Default for OracleConfig {
     fn default() -> Self {
         Self {
             max_price_age_seconds: DEFAULT_MAX_PRICE_AGE_SECONDS,
-            max_deviation_bps: DEFAULT_MAX_DEVIATION_BPS,
+            max_up_deviation_bps: DEFAULT_MAX_DEVIATION_BPS,
+            max_down_deviation_bps: DEFAULT_MAX_DEVIATION_BPS,
             // CSPR price bounds: $0.001 to $1000 (scaled by 1e18)
             min_cspr_price: U256::from(PRICE_SCALE / 1000), // $0.001
             max_cspr_price: U256::from(1000u64) * U256::from(PRICE_SCALE), // $1000
@@ -74,8 +110,53 @@ pub struct CachedPrice {
     pub status: OracleStatus,
 }
 
+/// How `get_price` treats a price past its heartbeat
+#[odra::odra_type]
+#[derive(Copy)]
+pub enum StalePolicy {
+    /// Return the last good price flagged `Stale`
+    LastGood,
+    /// Revert with `OraclePriceStale`
+    Revert,
+}
+
+/// CSPR/USD print awaiting confirmation before liquidation reads use it
+#[odra::odra_type]
+pub struct PendingPrice {
+    /// Latest print in the pending band (scaled by 1e18)
+    pub price: U256,
+    /// Timestamp of the first print in the band
+    pub first_seen: u64,
+    /// Timestamp of the latest counted print
+    pub last_seen: u64,
+    /// Number of distinct-timestamp prints seen in the band
+    pub updates: u32,
+}
+
+/// CSPR/USD price source with the status it reported on the last fallback read
+#[odra::odra_type]
+pub struct PriceSource {
+    /// Feed contract (Styks-compatible `get_twap_price`)
+    pub address: Address,
+    /// Status of the source's last reading
+    pub status: OracleStatus,
+}
+
+/// Emitted when a fallback read accepts a price from a source
+#[odra::event]
+pub struct PriceSourceUsed {
+    /// Source that served the price
+    pub source: Address,
+    /// Position in the priority list (0 = primary)
+    pub index: u32,
+    /// Accepted CSPR/USD price
+    pub price: U256,
+    /// Timestamp reported by the source
+    pub timestamp: u64,
+}
+
 /// Oracle Adapter Contract
-#[odra::module]
+#[odra::module(events = [PriceSourceUsed])]
 pub struct OracleAdapter {
     /// Registry contract address
     registry: Var<Address>,
@@ -99,6 +180,33 @@ pub struct OracleAdapter {
     cached_scspr_price: Var<CachedPrice>,
     /// Whether oracle is in degraded mode
     is_degraded: Var<bool>,
+    /// Treasury contract address (funds keeper bounties)
+    treasury: Var<Address>,
+    /// gUSD bounty paid for syncing a stale exchange rate (default 0)
+    keeper_bounty: Var<U256>,
+    /// Per-collateral freshness window overriding `max_price_age_seconds`
+    heartbeat_seconds: Mapping<CollateralId, u64>,
+    /// State beyond the core fields (nested: a module holds at most 15 fields)
+    state: SubModule<OracleAdapterState>,
+}
+
+/// Price source state added on top of the core oracle storage
+#[odra::module]
+pub struct OracleAdapterState {
+    /// CSPR/USD sources in priority order (empty = `cspr_oracle` only)
+    price_sources: Var<Vec<PriceSource>>,
+    /// Source that served the last accepted fallback price
+    active_price_source: Var<Option<Address>>,
+    /// Minimum age of a confirmed price move (0 = liquidations use the latest print)
+    confirmation_seconds: Var<u64>,
+    /// CSPR price confirmed for liquidation-relevant reads
+    confirmed_cspr_price: Var<U256>,
+    /// CSPR print awaiting confirmation
+    pending_cspr_price: Var<Option<PendingPrice>>,
+    /// Decimals of raw CSPR/USD prices reported by the feeds (default 18)
+    source_decimals: Var<u8>,
+    /// What `get_price` does with a stale price (default LastGood)
+    on_stale: Var<StalePolicy>,
 }
 
 #[odra::module]
@@ -115,6 +223,7 @@ impl OracleAdapter {
         let default_rate = U256::from(DEFAULT_RATE); // 1e18 = 1.0
 
         self.last_good_cspr_price.set(default_price);
+        self.state.confirmed_cspr_price.set(default_price);
         self.last_good_exchange_rate.set(default_rate);
 
         let current_time = self.env().get_block_time();
@@ -137,13 +246,28 @@ impl OracleAdapter {
     // ========== Price Query Functions ==========
 
     /// Get price for a collateral type
+    ///
+    /// A stale price is handled per `get_stale_policy`: returned with a
+    /// `Stale` status, or reverted with `OraclePriceStale`.
     pub fn get_price(&self, collateral_id: CollateralId) -> PriceData {
-        match collateral_id {
+        let price = match collateral_id {
             CollateralId::Cspr => self.get_cspr_price(),
             CollateralId::SCSPR => self.get_scspr_price(),
+        };
+        match apply_stale_policy(price, self.get_stale_policy()) {
+            Ok(price) => price,
+            Err(err) => self.env().revert(err),
         }
     }
 
+    /// Get the price of every supported collateral in one call
+    ///
+    /// Each entry carries its own status, exactly as `get_price` would
+    /// return it for that collateral.
+    pub fn get_all_prices(&self) -> Vec<(CollateralId, PriceData)> {
+        collect_prices(|collateral_id| self.get_price(collateral_id))
+    }
+
     /// Get CSPR/USD price
     pub fn get_cspr_price(&self) -> PriceData {
         let cached = self.cached_cspr_price.get().unwrap_or(CachedPrice {
@@ -153,15 +277,10 @@ impl OracleAdapter {
         });
 
         let current_time = self.env().get_block_time();
-        let config = self.config.get().unwrap_or_default();
 
-        // Check freshness
-        let age = current_time.saturating_sub(cached.timestamp);
-        let status = if age > config.max_price_age_seconds {
-            OracleStatus::Stale
-        } else {
-            cached.status
-        };
+        // Check freshness against the CSPR heartbeat
+        let heartbeat = self.get_heartbeat_seconds(CollateralId::Cspr);
+        let status = status_within_heartbeat(cached.status, current_time, cached.timestamp, heartbeat);
 
         PriceData {
             price_int: cached.price,
@@ -180,12 +299,11 @@ impl OracleAdapter {
         let cspr_price = self.get_cspr_price();
         let rate = self.last_good_exchange_rate.get().unwrap_or(U256::from(DEFAULT_RATE));
         let rate_timestamp = self.last_rate_update.get().unwrap_or(0);
-        let config = self.config.get().unwrap_or_default();
         let current_time = self.env().get_block_time();
 
-        // Check rate freshness
-        let rate_age = current_time.saturating_sub(rate_timestamp);
-        let rate_is_stale = rate_age > config.max_price_age_seconds;
+        // Check rate freshness against the stCSPR heartbeat
+        let heartbeat = self.get_heartbeat_seconds(CollateralId::SCSPR);
+        let rate_is_stale = is_past_max_age(current_time, rate_timestamp, heartbeat);
 
         // If CSPR price is not OK, stCSPR price inherits the status
         if cspr_price.status != OracleStatus::Ok {
@@ -220,6 +338,38 @@ impl OracleAdapter {
         }
     }
 
+    /// Get the price liquidation-relevant reads should use
+    ///
+    /// Same as `get_price`, except the CSPR leg is the last confirmed print:
+    /// a move outside the tolerance band only takes effect after
+    /// `MIN_CONFIRMING_UPDATES` prints spanning `confirmation_seconds`, so a
+    /// single flash-crash print cannot open vaults to liquidation. Borrowing
+    /// keeps using the latest print via `get_price`.
+    pub fn get_liquidation_price(&self, collateral_id: CollateralId) -> PriceData {
+        let latest = self.get_price(collateral_id);
+        let confirmed = self.get_confirmed_cspr_price();
+        let price_int = match collateral_id {
+            CollateralId::Cspr => confirmed,
+            CollateralId::SCSPR => {
+                let rate = self.last_good_exchange_rate.get().unwrap_or(U256::from(DEFAULT_RATE));
+                self.calculate_composite_price(confirmed, rate)
+            }
+        };
+        PriceData { price_int, ..latest }
+    }
+
+    /// Get the CSPR price confirmed for liquidation-relevant reads
+    pub fn get_confirmed_cspr_price(&self) -> U256 {
+        self.state.confirmed_cspr_price
+            .get()
+            .unwrap_or_else(|| self.last_good_cspr_price.get().unwrap_or(U256::from(PRICE_SCALE)))
+    }
+
+    /// Get the CSPR print awaiting confirmation, if any
+    pub fn get_pending_cspr_price(&self) -> Option<PendingPrice> {
+        self.state.pending_cspr_price.get().flatten()
+    }
+
     /// Get last known good price for a collateral type
     pub fn get_last_good_price(&self, collateral_id: CollateralId) -> U256 {
         match collateral_id {
@@ -235,40 +385,135 @@ impl OracleAdapter {
     // ========== Price Update Functions ==========
 
     /// Update CSPR price (called by authorized oracle feeder)
+    ///
+    /// `price` is in the feed's `source_decimals` and is normalized to 18
+    /// decimals before validation and storage.
     pub fn update_cspr_price(&mut self, price: U256, timestamp: u64) {
         // TODO: Add access control for oracle feeder
 
+        let price = normalize_price(price, self.get_source_decimals());
         let config = self.config.get().unwrap_or_default();
-        let current_time = self.env().get_block_time();
-
-        // Validate price bounds
-        if price < config.min_cspr_price || price > config.max_cspr_price {
-            self.handle_price_failure(OracleStatus::Deviation);
+        let status = self.validate_cspr_price(price, timestamp, &config);
+        if status != OracleStatus::Ok {
+            self.handle_price_failure(status);
             return;
         }
 
-        // Check deviation from last good price
-        let last_good = self.last_good_cspr_price.get().unwrap_or(price);
-        let deviation_status = self.check_deviation(price, last_good, config.max_deviation_bps);
+        self.accept_cspr_price(price, timestamp);
+    }
 
-        if deviation_status != OracleStatus::Ok {
-            self.handle_price_failure(deviation_status);
-            return;
+    /// Update CSPR price, declaring the precision it is reported in
+    ///
+    /// Reverts unless `price_decimals` matches the configured `source_decimals`,
+    /// so a feeder that changed precision cannot corrupt stored prices.
+    pub fn update_cspr_price_with_decimals(&mut self, price: U256, price_decimals: u8, timestamp: u64) {
+        if let Err(err) = check_source_decimals(price_decimals, self.get_source_decimals()) {
+            self.env().revert(err);
         }
+        self.update_cspr_price(price, timestamp);
+    }
 
-        // Check freshness (timestamp should be recent)
-        if timestamp < current_time.saturating_sub(config.max_price_age_seconds) {
-            self.handle_price_failure(OracleStatus::Stale);
-            return;
+    /// Read CSPR/USD from the configured sources in priority order
+    ///
+    /// The first source reporting a valid, fresh price is accepted and recorded
+    /// as the active source; the adapter only degrades if every source fails.
+    /// Each source's status is stored for auditability.
+    pub fn get_price_with_fallback(&mut self) -> PriceData {
+        let mut sources = self.get_price_sources();
+        if sources.is_empty() {
+            self.env().revert(CdpError::OraclePriceUnavailable);
+        }
+
+        let config = self.config.get().unwrap_or_default();
+        let now = self.env().get_block_time();
+        let heartbeat = self.get_heartbeat_seconds(CollateralId::Cspr);
+        let source_decimals = self.get_source_decimals();
+        let mut readings = Vec::new();
+        for source in sources.iter_mut() {
+            let (price, timestamp, status) = match StyksOracle::get_cspr_twap(&self.env(), source.address) {
+                Some(twap) => {
+                    let price = normalize_price(twap.price, source_decimals);
+                    let last_good = self.last_good_cspr_price.get().unwrap_or(price);
+                    let status = price_status(price, twap.timestamp, now, heartbeat, last_good, &config);
+                    (price, twap.timestamp, status)
+                }
+                None => (U256::zero(), 0, OracleStatus::Unavailable),
+            };
+            source.status = status;
+            readings.push((price, timestamp));
+        }
+
+        let statuses: Vec<OracleStatus> = sources.iter().map(|source| source.status).collect();
+        match first_ok_source(&statuses) {
+            Some(index) => {
+                let (price, timestamp) = readings[index];
+                let source = sources[index].address;
+                self.accept_cspr_price(price, timestamp);
+                self.state.active_price_source.set(Some(source));
+                self.env().emit_event(PriceSourceUsed {
+                    source,
+                    index: index as u32,
+                    price,
+                    timestamp,
+                });
+            }
+            None => {
+                self.state.active_price_source.set(None);
+                self.handle_price_failure(statuses[0]);
+            }
         }
+        self.state.price_sources.set(sources);
+
+        self.get_cspr_price()
+    }
 
-        // Price is valid - update cache and last good price
+    /// Get configured price sources with their last reported status
+    ///
+    /// Falls back to the single `cspr_oracle` when no list is configured.
+    pub fn get_price_sources(&self) -> Vec<PriceSource> {
+        let sources = self.state.price_sources.get().unwrap_or_default();
+        if !sources.is_empty() {
+            return sources;
+        }
+        match self.cspr_oracle.get().flatten() {
+            Some(address) => vec![PriceSource {
+                address,
+                status: OracleStatus::Unavailable,
+            }],
+            None => Vec::new(),
+        }
+    }
+
+    /// Set CSPR/USD price sources in priority order (admin only)
+    pub fn set_price_sources(&mut self, sources: Vec<Address>) {
+        // TODO: Add admin access control
+        if sources.len() > MAX_PRICE_SOURCES {
+            self.env().revert(CdpError::InvalidConfig);
+        }
+        let sources = sources
+            .into_iter()
+            .map(|address| PriceSource {
+                address,
+                status: OracleStatus::Unavailable,
+            })
+            .collect();
+        self.state.price_sources.set(sources);
+    }
+
+    /// Get the source that served the last accepted fallback price
+    pub fn get_active_price_source(&self) -> Option<Address> {
+        self.state.active_price_source.get().flatten()
+    }
+
+    /// Accept a validated CSPR price: update caches and last good price
+    fn accept_cspr_price(&mut self, price: U256, timestamp: u64) {
         self.cached_cspr_price.set(CachedPrice {
             price,
             timestamp,
             status: OracleStatus::Ok,
         });
         self.last_good_cspr_price.set(price);
+        self.track_confirmation(price, timestamp);
         self.is_degraded.set(false);
 
         // Update stCSPR cached price with new CSPR price
@@ -282,6 +527,16 @@ impl OracleAdapter {
         });
     }
 
+    /// Feed an accepted CSPR print into the liquidation price confirmation
+    fn track_confirmation(&mut self, price: U256, timestamp: u64) {
+        let confirmed = self.state.confirmed_cspr_price.get();
+        let pending = self.get_pending_cspr_price();
+        let window = self.get_confirmation_seconds();
+        let (confirmed, pending) = confirm_price(confirmed, pending, price, timestamp, window);
+        self.state.confirmed_cspr_price.set(confirmed);
+        self.state.pending_cspr_price.set(pending);
+    }
+
     /// Update stCSPR/CSPR exchange rate (called by authorized rate feeder or sync)
     /// Rate should be scaled by 1e18 (CSPR_PER_SCSPR)
     pub fn update_exchange_rate(&mut self, rate: U256) {
@@ -312,18 +567,52 @@ impl OracleAdapter {
         });
     }
 
+    /// Update CSPR price and stCSPR/CSPR exchange rate atomically
+    ///
+    /// Both inputs are validated before anything is written, so a rejected
+    /// price or rate leaves all caches untouched. The stCSPR composite is
+    /// recomputed once from the new pair.
+    pub fn update_prices(&mut self, cspr_price: U256, cspr_timestamp: u64, exchange_rate: U256) {
+        // TODO: Add access control for oracle feeder
+
+        let cspr_price = normalize_price(cspr_price, self.get_source_decimals());
+        let config = self.config.get().unwrap_or_default();
+
+        if exchange_rate < config.min_exchange_rate || exchange_rate > config.max_exchange_rate {
+            self.handle_price_failure(OracleStatus::InvalidRate);
+            return;
+        }
+
+        let status = self.validate_cspr_price(cspr_price, cspr_timestamp, &config);
+        if status != OracleStatus::Ok {
+            self.handle_price_failure(status);
+            return;
+        }
+
+        let current_time = self.env().get_block_time();
+        let (cspr_cache, scspr_cache) =
+            build_price_caches(cspr_price, cspr_timestamp, exchange_rate, current_time);
+
+        self.last_good_cspr_price.set(cspr_price);
+        self.track_confirmation(cspr_price, cspr_timestamp);
+        self.last_good_exchange_rate.set(exchange_rate);
+        self.last_rate_update.set(current_time);
+        self.cached_cspr_price.set(cspr_cache);
+        self.cached_scspr_price.set(scspr_cache);
+        self.is_degraded.set(false);
+    }
+
     /// Force refresh from external oracle (if configured)
     pub fn refresh_price(&mut self) {
         // TODO: Implement actual external oracle call when Styks interface is available
         // For now, this is a placeholder that validates cached prices
 
         let current_time = self.env().get_block_time();
-        let config = self.config.get().unwrap_or_default();
+        let heartbeat = self.get_heartbeat_seconds(CollateralId::Cspr);
 
         let cached = self.cached_cspr_price.get();
         if let Some(cached) = cached {
-            let age = current_time.saturating_sub(cached.timestamp);
-            if age > config.max_price_age_seconds {
+            if is_past_max_age(current_time, cached.timestamp, heartbeat) {
                 self.handle_price_failure(OracleStatus::Stale);
             }
         } else {
@@ -347,6 +636,37 @@ impl OracleAdapter {
         // TODO: Make cross-contract call to router.trigger_safe_mode(reason)
     }
 
+    /// Sweep price and rate freshness (callable by anyone, e.g. keepers)
+    ///
+    /// If the cached CSPR price or the exchange rate is older than its
+    /// collateral's heartbeat, marks the adapter degraded and latches safe
+    /// mode on the router. Returns true if safe mode was triggered.
+    pub fn enforce_freshness(&mut self) -> bool {
+        let current_time = self.env().get_block_time();
+
+        let price_timestamp = self.cached_cspr_price.get().map(|c| c.timestamp).unwrap_or(0);
+        let rate_timestamp = self.last_rate_update.get().unwrap_or(0);
+
+        let price_heartbeat = self.get_heartbeat_seconds(CollateralId::Cspr);
+        let rate_heartbeat = self.get_heartbeat_seconds(CollateralId::SCSPR);
+        let price_stale = is_past_max_age(current_time, price_timestamp, price_heartbeat);
+        let rate_stale = is_past_max_age(current_time, rate_timestamp, rate_heartbeat);
+        if !price_stale && !rate_stale {
+            return false;
+        }
+
+        self.handle_price_failure(OracleStatus::Stale);
+
+        if let Some(router_addr) = self.router.get() {
+            let args = runtime_args! {
+                "reason" => OracleStatus::Stale
+            };
+            let call_def = CallDef::new("trigger_safe_mode", true, args);
+            self.env().call_contract::<()>(router_addr, call_def);
+        }
+        true
+    }
+
     /// Check if oracle is in degraded mode
     pub fn is_oracle_degraded(&self) -> bool {
         self.is_degraded.get().unwrap_or(false)
@@ -388,6 +708,60 @@ impl OracleAdapter {
         self.config.set(config);
     }
 
+    /// Get the freshness window for a collateral's price inputs
+    ///
+    /// For stCSPR this bounds the exchange rate age; the CSPR leg of the
+    /// composite price still follows the CSPR heartbeat.
+    pub fn get_heartbeat_seconds(&self, collateral_id: CollateralId) -> u64 {
+        let config = self.config.get().unwrap_or_default();
+        effective_heartbeat(self.heartbeat_seconds.get(&collateral_id), config.max_price_age_seconds)
+    }
+
+    /// Set a collateral's heartbeat (admin only, 0 = use `max_price_age_seconds`)
+    pub fn set_heartbeat_seconds(&mut self, collateral_id: CollateralId, heartbeat_seconds: u64) {
+        // TODO: Add admin access control
+        self.heartbeat_seconds.set(&collateral_id, heartbeat_seconds);
+    }
+
+    /// Get the decimals raw feed prices are reported in
+    pub fn get_source_decimals(&self) -> u8 {
+        self.state.source_decimals.get().unwrap_or(CANONICAL_PRICE_DECIMALS)
+    }
+
+    /// Set the decimals raw feed prices are reported in (admin only)
+    pub fn set_source_decimals(&mut self, source_decimals: u8) {
+        // TODO: Add admin access control
+        if source_decimals > MAX_SOURCE_DECIMALS {
+            self.env().revert(CdpError::InvalidConfig);
+        }
+        self.state.source_decimals.set(source_decimals);
+    }
+
+    /// Get what `get_price` does with a stale price
+    pub fn get_stale_policy(&self) -> StalePolicy {
+        self.state.on_stale.get().unwrap_or(StalePolicy::LastGood)
+    }
+
+    /// Set what `get_price` does with a stale price (admin only)
+    pub fn set_stale_policy(&mut self, on_stale: StalePolicy) {
+        // TODO: Add admin access control
+        self.state.on_stale.set(on_stale);
+    }
+
+    /// Get the minimum span of prints confirming a liquidation price move
+    pub fn get_confirmation_seconds(&self) -> u64 {
+        self.state.confirmation_seconds.get().unwrap_or(0)
+    }
+
+    /// Set the confirmation window (admin only, 0 = no confirmation)
+    pub fn set_confirmation_seconds(&mut self, confirmation_seconds: u64) {
+        // TODO: Add admin access control
+        if confirmation_seconds > MAX_CONFIRMATION_SECONDS {
+            self.env().revert(CdpError::InvalidConfig);
+        }
+        self.state.confirmation_seconds.set(confirmation_seconds);
+    }
+
     /// Set CSPR oracle address
     pub fn set_cspr_oracle(&mut self, oracle: Address) {
         // TODO: Add admin access control
@@ -400,11 +774,61 @@ impl OracleAdapter {
         self.scspr_ybtoken.set(Some(ybtoken));
     }
 
+    /// Pull all dependency addresses from the registry in one call (admin only)
+    ///
+    /// Components the registry doesn't know yet keep their current address;
+    /// the individual setters remain available as overrides.
+    pub fn refresh_addresses(&mut self) {
+        self.require_registry_admin();
+        let registry_addr = match self.registry.get() {
+            Some(addr) => addr,
+            None => self.env().revert(CdpError::InvalidConfig),
+        };
+        let env = self.env();
+        let resolved = resolve_all(&REGISTRY_DEPENDENCIES, |component| {
+            registry::try_resolve(&env, registry_addr, component)
+        });
+        for (component, address) in resolved {
+            match component {
+                ComponentId::Router => self.router.set(address),
+                ComponentId::StyksOracle => self.cspr_oracle.set(Some(address)),
+                ComponentId::ScsprYbToken => self.scspr_ybtoken.set(Some(address)),
+                ComponentId::Treasury => self.treasury.set(address),
+                _ => {}
+            }
+        }
+    }
+
     /// Get stCSPR ybToken address
     pub fn get_scspr_ybtoken(&self) -> Option<Address> {
         self.scspr_ybtoken.get().flatten()
     }
 
+    /// Get treasury address
+    pub fn get_treasury(&self) -> Option<Address> {
+        self.treasury.get()
+    }
+
+    /// Set treasury address (funds keeper bounties)
+    pub fn set_treasury(&mut self, treasury: Address) {
+        // TODO: Add admin access control
+        self.treasury.set(treasury);
+    }
+
+    /// Get gUSD bounty paid per incentivized rate sync
+    pub fn get_keeper_bounty(&self) -> U256 {
+        self.keeper_bounty.get().unwrap_or(U256::zero())
+    }
+
+    /// Set gUSD bounty paid per incentivized rate sync (capped at MAX_KEEPER_BOUNTY)
+    pub fn set_keeper_bounty(&mut self, bounty: U256) {
+        // TODO: Add admin access control
+        if bounty > U256::from(MAX_KEEPER_BOUNTY) {
+            self.env().revert(CdpError::InvalidConfig);
+        }
+        self.keeper_bounty.set(bounty);
+    }
+
     /// Get registry address
     pub fn get_registry(&self) -> Option<Address> {
         self.registry.get()
@@ -448,13 +872,49 @@ impl OracleAdapter {
         self.update_exchange_rate(rate);
     }
 
+    /// Pull the exchange rate from the ybToken and pay the caller a bounty
+    ///
+    /// Only callable while the rate is stale, so the bounty can't be farmed on
+    /// fresh state. The bounty is paid in gUSD by the treasury (which must list
+    /// this oracle as a depositor) and is skipped if the new rate is rejected.
+    /// Returns the bounty paid.
+    pub fn sync_rate_incentivized(&mut self) -> U256 {
+        let ybtoken = match self.scspr_ybtoken.get().flatten() {
+            Some(addr) => addr,
+            None => self.env().revert(CdpError::InvalidConfig),
+        };
+        let was_stale = self.is_rate_stale();
+        if !was_stale {
+            self.env().revert(CdpError::OracleRateFresh);
+        }
+
+        let rate_call = CallDef::new("get_exchange_rate", false, runtime_args! {});
+        let rate: U256 = self.env().call_contract(ybtoken, rate_call);
+        self.update_exchange_rate(rate);
+
+        let bounty = match keeper_bounty_due(was_stale, self.is_rate_stale(), self.get_keeper_bounty()) {
+            Some(bounty) => bounty,
+            None => self.env().revert(CdpError::OracleRateFresh),
+        };
+        let treasury_addr = match self.treasury.get() {
+            Some(addr) if !bounty.is_zero() => addr,
+            _ => return U256::zero(),
+        };
+
+        let args = runtime_args! {
+            "keeper" => self.env().caller(),
+            "amount" => bounty
+        };
+        let call_def = CallDef::new("pay_keeper_bounty", true, args);
+        self.env().call_contract(treasury_addr, call_def)
+    }
+
     /// Check if rate sync is needed (rate is stale)
     pub fn is_rate_stale(&self) -> bool {
         let rate_timestamp = self.last_rate_update.get().unwrap_or(0);
         let current_time = self.env().get_block_time();
-        let config = self.config.get().unwrap_or_default();
 
-        current_time.saturating_sub(rate_timestamp) > config.max_price_age_seconds
+        is_past_max_age(current_time, rate_timestamp, self.get_heartbeat_seconds(CollateralId::SCSPR))
     }
 
     /// Get rate info for monitoring
@@ -467,40 +927,299 @@ impl OracleAdapter {
 
     // ========== Internal Functions ==========
 
+    fn require_registry_admin(&self) {
+        let registry_addr = match self.registry.get() {
+            Some(addr) => addr,
+            None => self.env().revert(CdpError::InvalidConfig),
+        };
+        let args = runtime_args! {
+            "caller" => self.env().caller()
+        };
+        let is_admin: bool = self.env().call_contract(registry_addr, CallDef::new("is_admin", false, args));
+        if !is_admin {
+            self.env().revert(CdpError::Unauthorized);
+        }
+    }
+
     /// Calculate composite price: P(stCSPR) = P(CSPR) * R / RATE_SCALE
     /// Where R is CSPR_PER_SCSPR (scaled by 1e18)
     fn calculate_composite_price(&self, cspr_price: U256, rate: U256) -> U256 {
-        cspr_price * rate / U256::from(RATE_SCALE)
+        composite_price(cspr_price, rate)
     }
 
-    /// Check deviation between new price and reference price
-    fn check_deviation(&self, new_price: U256, reference_price: U256, max_deviation_bps: u32) -> OracleStatus {
-        if reference_price.is_zero() {
-            return OracleStatus::Ok; // No reference to compare against
-        }
+    /// Validate a CSPR price update (bounds, deviation, freshness)
+    fn validate_cspr_price(&self, price: U256, timestamp: u64, config: &OracleConfig) -> OracleStatus {
+        let last_good = self.last_good_cspr_price.get().unwrap_or(price);
+        let current_time = self.env().get_block_time();
+        let heartbeat = self.get_heartbeat_seconds(CollateralId::Cspr);
+        price_status(price, timestamp, current_time, heartbeat, last_good, config)
+    }
+}
 
-        // Calculate absolute difference
-        let diff = if new_price > reference_price {
-            new_price - reference_price
-        } else {
-            reference_price - new_price
-        };
+/// Status of a CSPR price reading (bounds, deviation from last good, freshness)
+fn price_status(
+    price: U256,
+    timestamp: u64,
+    now: u64,
+    heartbeat: u64,
+    last_good: U256,
+    config: &OracleConfig,
+) -> OracleStatus {
+    // Validate price bounds
+    if price < config.min_cspr_price || price > config.max_cspr_price {
+        return OracleStatus::Deviation;
+    }
 
-        // Calculate deviation in bps: (diff * 10000) / reference
-        let deviation_bps = diff * U256::from(10000u32) / reference_price;
+    // Check deviation from last good price
+    let deviation = deviation_status(
+        price,
+        last_good,
+        config.max_up_deviation_bps,
+        config.max_down_deviation_bps,
+    );
+    if deviation != OracleStatus::Ok {
+        return deviation;
+    }
 
-        if deviation_bps > U256::from(max_deviation_bps) {
-            OracleStatus::Deviation
-        } else {
-            OracleStatus::Ok
+    // Check freshness (timestamp should be recent)
+    if timestamp < now.saturating_sub(heartbeat) {
+        return OracleStatus::Stale;
+    }
+
+    OracleStatus::Ok
+}
+
+/// Rescale a raw feed price from `source_decimals` to 18 decimals
+///
+/// Extra precision is truncated; scaling up saturates at `U256::MAX`, which
+/// the price bounds then reject.
+fn normalize_price(price: U256, source_decimals: u8) -> U256 {
+    let ten = U256::from(10u8);
+    if source_decimals < CANONICAL_PRICE_DECIMALS {
+        let factor = ten.pow(U256::from(CANONICAL_PRICE_DECIMALS - source_decimals));
+        price.checked_mul(factor).unwrap_or(U256::MAX)
+    } else {
+        price / ten.pow(U256::from(source_decimals - CANONICAL_PRICE_DECIMALS))
+    }
+}
+
+/// A feeder's declared precision must match the configured source decimals
+fn check_source_decimals(price_decimals: u8, source_decimals: u8) -> Result<(), CdpError> {
+    if price_decimals != source_decimals {
+        Err(CdpError::InvalidConfig)
+    } else {
+        Ok(())
+    }
+}
+
+/// Price of each supported collateral, in `CollateralId` order
+fn collect_prices<F: Fn(CollateralId) -> PriceData>(price_of: F) -> Vec<(CollateralId, PriceData)> {
+    [CollateralId::Cspr, CollateralId::SCSPR]
+        .into_iter()
+        .map(|collateral_id| (collateral_id, price_of(collateral_id)))
+        .collect()
+}
+
+/// Index of the first source whose reading is Ok, in priority order
+fn first_ok_source(statuses: &[OracleStatus]) -> Option<usize> {
+    statuses.iter().position(|status| *status == OracleStatus::Ok)
+}
+
+/// Deviation status of `new_price` against `reference_price`
+///
+/// Rises are bounded by `max_up_deviation_bps` and drops by
+/// `max_down_deviation_bps`, so a sudden fall can be held to a stricter limit.
+fn deviation_status(
+    new_price: U256,
+    reference_price: U256,
+    max_up_deviation_bps: u32,
+    max_down_deviation_bps: u32,
+) -> OracleStatus {
+    if reference_price.is_zero() {
+        return OracleStatus::Ok; // No reference to compare against
+    }
+
+    // Calculate absolute difference and pick the bound for its direction
+    let (diff, max_deviation_bps) = if new_price > reference_price {
+        (new_price - reference_price, max_up_deviation_bps)
+    } else {
+        (reference_price - new_price, max_down_deviation_bps)
+    };
+
+    // Calculate deviation in bps: (diff * 10000) / reference
+    let deviation_bps = diff * U256::from(10000u32) / reference_price;
+
+    if deviation_bps > U256::from(max_deviation_bps) {
+        OracleStatus::Deviation
+    } else {
+        OracleStatus::Ok
+    }
+}
+
+/// Next (confirmed, pending) CSPR prices after an accepted print
+///
+/// A print within `CONFIRMATION_TOLERANCE_BPS` of the confirmed price replaces
+/// it directly and drops any pending move, so a crash that recovers on the
+/// next print is discarded. A print outside the band becomes (or extends) the
+/// pending move, which is confirmed once it has `MIN_CONFIRMING_UPDATES`
+/// prints with distinct timestamps at least `confirmation_seconds` apart.
+fn confirm_price(
+    confirmed: Option<U256>,
+    pending: Option<PendingPrice>,
+    price: U256,
+    timestamp: u64,
+    confirmation_seconds: u64,
+) -> (U256, Option<PendingPrice>) {
+    let confirmed = match confirmed {
+        Some(confirmed) if confirmation_seconds > 0 => confirmed,
+        _ => return (price, None),
+    };
+    let within_band = |reference: U256| {
+        deviation_status(price, reference, CONFIRMATION_TOLERANCE_BPS, CONFIRMATION_TOLERANCE_BPS) == OracleStatus::Ok
+    };
+    if within_band(confirmed) {
+        return (price, None);
+    }
+
+    match pending {
+        Some(mut pending) if within_band(pending.price) => {
+            if timestamp > pending.last_seen {
+                pending.updates += 1;
+                pending.last_seen = timestamp;
+            }
+            pending.price = price;
+            let span = pending.last_seen.saturating_sub(pending.first_seen);
+            if pending.updates >= MIN_CONFIRMING_UPDATES && span >= confirmation_seconds {
+                (price, None)
+            } else {
+                (confirmed, Some(pending))
+            }
         }
+        _ => (
+            confirmed,
+            Some(PendingPrice {
+                price,
+                first_seen: timestamp,
+                last_seen: timestamp,
+                updates: 1,
+            }),
+        ),
+    }
+}
+
+/// Bounty owed for an incentivized rate sync
+///
+/// `None` if the rate was fresh to begin with (the call must revert); zero if
+/// the sync didn't refresh the rate (e.g. the rate was rejected as invalid).
+fn keeper_bounty_due(was_stale: bool, still_stale: bool, bounty: U256) -> Option<U256> {
+    if !was_stale {
+        return None;
+    }
+    if still_stale {
+        return Some(U256::zero());
     }
+    Some(bounty.min(U256::from(MAX_KEEPER_BOUNTY)))
+}
+
+/// Heartbeat override if set, else the global max price age
+fn effective_heartbeat(override_seconds: Option<u64>, max_price_age_seconds: u64) -> u64 {
+    match override_seconds {
+        Some(seconds) if seconds > 0 => seconds,
+        _ => max_price_age_seconds,
+    }
+}
+
+/// Pass a price through, or fail it if it is stale under `StalePolicy::Revert`
+fn apply_stale_policy(price: PriceData, policy: StalePolicy) -> Result<PriceData, CdpError> {
+    if price.status == OracleStatus::Stale && policy == StalePolicy::Revert {
+        return Err(CdpError::OraclePriceStale);
+    }
+    Ok(price)
+}
+
+/// Cached status, downgraded to Stale once `timestamp` is past the heartbeat
+fn status_within_heartbeat(status: OracleStatus, now: u64, timestamp: u64, heartbeat: u64) -> OracleStatus {
+    if is_past_max_age(now, timestamp, heartbeat) {
+        OracleStatus::Stale
+    } else {
+        status
+    }
+}
+
+/// Whether data recorded at `timestamp` is older than `max_age` at `now`
+fn is_past_max_age(now: u64, timestamp: u64, max_age: u64) -> bool {
+    now.saturating_sub(timestamp) > max_age
+}
+
+/// Composite stCSPR price: P(CSPR) * R / RATE_SCALE
+fn composite_price(cspr_price: U256, rate: U256) -> U256 {
+    cspr_price * rate / U256::from(RATE_SCALE)
+}
+
+/// Build CSPR and stCSPR caches from a validated price/rate pair
+///
+/// The composite timestamp is the older of the two inputs (effective age).
+fn build_price_caches(
+    cspr_price: U256,
+    cspr_timestamp: u64,
+    rate: U256,
+    rate_timestamp: u64,
+) -> (CachedPrice, CachedPrice) {
+    let cspr_cache = CachedPrice {
+        price: cspr_price,
+        timestamp: cspr_timestamp,
+        status: OracleStatus::Ok,
+    };
+    let scspr_cache = CachedPrice {
+        price: composite_price(cspr_price, rate),
+        timestamp: cspr_timestamp.min(rate_timestamp),
+        status: OracleStatus::Ok,
+    };
+    (cspr_cache, scspr_cache)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_combined_update_caches_consistent() {
+        let cspr_price = U256::from(50_000_000_000_000_000u128); // $0.05
+        let rate = U256::from(1_100_000_000_000_000_000u128); // 1.1
+        let now = 1_700_000_000u64;
+
+        let (cspr_cache, scspr_cache) = build_price_caches(cspr_price, now, rate, now);
+
+        assert_eq!(cspr_cache.price, cspr_price);
+        assert_eq!(scspr_cache.price, U256::from(55_000_000_000_000_000u128));
+        assert_eq!(scspr_cache.price, composite_price(cspr_cache.price, rate));
+        assert_eq!(cspr_cache.timestamp, scspr_cache.timestamp);
+        assert_eq!(cspr_cache.status, OracleStatus::Ok);
+        assert_eq!(scspr_cache.status, OracleStatus::Ok);
+    }
+
+    #[test]
+    fn test_stale_rate_detected_by_sweep() {
+        let max_age = DEFAULT_MAX_PRICE_AGE_SECONDS;
+        let rate_updated = 1_000u64;
+
+        // Within max age: sweep is a no-op
+        assert!(!is_past_max_age(rate_updated + max_age, rate_updated, max_age));
+        // Past max age: sweep flips the adapter into degraded mode
+        assert!(is_past_max_age(rate_updated + max_age + 1, rate_updated, max_age));
+        // Never-updated data is stale
+        assert!(is_past_max_age(rate_updated + max_age + 1, 0, max_age));
+    }
+
+    #[test]
+    fn test_combined_update_uses_older_timestamp() {
+        let price = U256::from(PRICE_SCALE);
+        let rate = U256::from(RATE_SCALE);
+
+        let (_, scspr_cache) = build_price_caches(price, 100, rate, 160);
+        assert_eq!(scspr_cache.timestamp, 100);
+    }
+
     #[test]
     fn test_composite_price_calculation() {
         // Test: CSPR price = $0.05, rate = 1.1 (R = CSPR_PER_SCSPR)
@@ -557,11 +1276,98 @@ mod tests {
         assert!(deviation_bps > U256::from(500u32)); // Exceeds 5% threshold
     }
 
+    #[test]
+    fn test_asymmetric_deviation_bounds() {
+        let reference = U256::from(100u64);
+        let rise = U256::from(106u64); // +6%
+        let drop = U256::from(94u64); // -6%
+
+        // 8% up, 5% down: the rise passes, the equal drop trips the down-bound
+        assert_eq!(deviation_status(rise, reference, 800, 500), OracleStatus::Ok);
+        assert_eq!(deviation_status(drop, reference, 800, 500), OracleStatus::Deviation);
+
+        // Symmetric defaults keep the old single-bound behaviour
+        assert_eq!(deviation_status(rise, reference, 500, 500), OracleStatus::Deviation);
+        assert_eq!(deviation_status(drop, reference, 500, 500), OracleStatus::Deviation);
+        assert_eq!(deviation_status(U256::from(105u64), reference, 500, 500), OracleStatus::Ok);
+    }
+
+    #[test]
+    fn test_stale_rate_sync_pays_bounty() {
+        let bounty = U256::from(PRICE_SCALE); // 1 gUSD
+
+        // Stale rate refreshed: keeper earns the bounty
+        assert_eq!(keeper_bounty_due(true, false, bounty), Some(bounty));
+
+        // Bounty never exceeds the per-call cap
+        let oversized = U256::from(MAX_KEEPER_BOUNTY) * U256::from(2u64);
+        assert_eq!(keeper_bounty_due(true, false, oversized), Some(U256::from(MAX_KEEPER_BOUNTY)));
+
+        // Rejected rate leaves it stale: nothing paid
+        assert_eq!(keeper_bounty_due(true, true, bounty), Some(U256::zero()));
+    }
+
+    #[test]
+    fn test_fresh_rate_sync_reverts() {
+        // A fresh rate yields no bounty entitlement, so the sync reverts
+        assert_eq!(keeper_bounty_due(false, false, U256::from(PRICE_SCALE)), None);
+
+        // Freshness follows the configured max age
+        assert!(!is_past_max_age(4_000, 1_000, 3_600));
+        assert!(is_past_max_age(4_601, 1_000, 3_600));
+    }
+
+    #[test]
+    fn test_scspr_heartbeat_tolerates_longer_gap() {
+        let global = DEFAULT_MAX_PRICE_AGE_SECONDS;
+        let cspr_heartbeat = effective_heartbeat(None, global);
+        let scspr_heartbeat = effective_heartbeat(Some(6 * 3600), global);
+        assert_eq!(cspr_heartbeat, 3600);
+        assert_eq!(scspr_heartbeat, 21_600);
+
+        // Two hours without an update: CSPR is stale, stCSPR's rate is not
+        let updated_at = 10_000;
+        let now = updated_at + 2 * 3600;
+        assert_eq!(status_within_heartbeat(OracleStatus::Ok, now, updated_at, cspr_heartbeat), OracleStatus::Stale);
+        assert_eq!(status_within_heartbeat(OracleStatus::Ok, now, updated_at, scspr_heartbeat), OracleStatus::Ok);
+
+        // Past its own heartbeat stCSPR goes stale too
+        let later = updated_at + 6 * 3600 + 1;
+        assert_eq!(status_within_heartbeat(OracleStatus::Ok, later, updated_at, scspr_heartbeat), OracleStatus::Stale);
+
+        // A zero override falls back to the global window
+        assert_eq!(effective_heartbeat(Some(0), global), global);
+    }
+
+    #[test]
+    fn test_fallback_skips_stale_primary() {
+        let config = OracleConfig::default();
+        let price = U256::from(PRICE_SCALE / 20); // $0.05
+        let now = 100_000;
+        let heartbeat = 3600;
+
+        // Primary last reported two hours ago; the backup is current
+        let primary = price_status(price, now - 7200, now, heartbeat, price, &config);
+        let secondary = price_status(price, now - 60, now, heartbeat, price, &config);
+        assert_eq!(primary, OracleStatus::Stale);
+        assert_eq!(secondary, OracleStatus::Ok);
+        assert_eq!(first_ok_source(&[primary, secondary]), Some(1));
+
+        // A healthy primary always wins
+        assert_eq!(first_ok_source(&[OracleStatus::Ok, OracleStatus::Ok]), Some(0));
+
+        // Only when every source fails does the adapter degrade
+        let jumped = price_status(price * U256::from(2u64), now, now, heartbeat, price, &config);
+        assert_eq!(jumped, OracleStatus::Deviation);
+        assert_eq!(first_ok_source(&[primary, jumped, OracleStatus::Unavailable]), None);
+    }
+
     #[test]
     fn test_default_config() {
         let config = OracleConfig::default();
         assert_eq!(config.max_price_age_seconds, 3600);
-        assert_eq!(config.max_deviation_bps, 500);
+        assert_eq!(config.max_up_deviation_bps, 500);
+        assert_eq!(config.max_down_deviation_bps, 500);
 
         // Rate bounds: 0.5e18 to 3.0e18
         assert_eq!(config.min_exchange_rate, U256::from(RATE_SCALE / 2));
@@ -580,4 +1386,121 @@ mod tests {
         // Verify PRICE_SCALE is 1e18
         assert_eq!(PRICE_SCALE, 1_000_000_000_000_000_000u128);
     }
+
+    #[test]
+    fn test_all_prices_match_individual_queries() {
+        let cspr_price = U256::from(50_000_000_000_000_000u128); // $0.05
+        let rate = U256::from(1_100_000_000_000_000_000u128); // 1.1
+        let (cspr_cache, scspr_cache) = build_price_caches(cspr_price, 1_000, rate, 900);
+
+        // CSPR fresh, stCSPR stale: each keeps its own status in the batch
+        let price_of = |collateral_id: CollateralId| {
+            let (cache, status) = match collateral_id {
+                CollateralId::Cspr => (&cspr_cache, OracleStatus::Ok),
+                CollateralId::SCSPR => (&scspr_cache, OracleStatus::Stale),
+            };
+            PriceData {
+                price_int: cache.price,
+                price_decimals: 18,
+                timestamp_sec: cache.timestamp,
+                status,
+            }
+        };
+
+        let all = collect_prices(price_of);
+        assert_eq!(all.len(), 2);
+        assert_eq!(all[0], (CollateralId::Cspr, price_of(CollateralId::Cspr)));
+        assert_eq!(all[1], (CollateralId::SCSPR, price_of(CollateralId::SCSPR)));
+        assert_eq!(all[1].1.status, OracleStatus::Stale);
+    }
+
+    #[test]
+    fn test_unconfirmed_crash_price_does_not_enable_liquidation() {
+        let dollars = |cents: u64| U256::from(cents) * U256::from(PRICE_SCALE) / U256::from(100u64);
+        // 1000 CSPR backing 700 gUSD: ICR 142% at $1.00, 71% at $0.50 (MCR 110%)
+        let liquidatable = |price: U256| U256::from(1000u64) * price * U256::from(100u64) < dollars(70_000) * U256::from(110u64);
+        let window = 300u64;
+        let t0 = 1_700_000_000u64;
+
+        // A single crash print stays pending; liquidation reads keep $1.00
+        let (confirmed, pending) = confirm_price(Some(dollars(100)), None, dollars(50), t0, window);
+        assert_eq!(confirmed, dollars(100));
+        assert!(!liquidatable(confirmed));
+        let pending = pending.unwrap();
+        assert_eq!((pending.price, pending.updates), (dollars(50), 1));
+
+        // The next print recovers: the crash is discarded, never confirmed
+        let (confirmed, pending) = confirm_price(Some(confirmed), Some(pending), dollars(99), t0 + 60, window);
+        assert_eq!(confirmed, dollars(99));
+        assert!(pending.is_none());
+        assert!(!liquidatable(confirmed));
+
+        // A sustained crash: a second print too soon, or at the same timestamp, doesn't confirm
+        let (confirmed, pending) = confirm_price(Some(confirmed), None, dollars(50), t0 + 120, window);
+        let (confirmed, pending) = confirm_price(Some(confirmed), pending, dollars(50), t0 + 120, window);
+        assert_eq!(pending.as_ref().unwrap().updates, 1);
+        let (confirmed, pending) = confirm_price(Some(confirmed), pending, dollars(50), t0 + 240, window);
+        assert_eq!(confirmed, dollars(99));
+        assert!(!liquidatable(confirmed));
+
+        // Once the move spans the window, it is confirmed and liquidations proceed
+        let (confirmed, pending) = confirm_price(Some(confirmed), pending, dollars(50), t0 + 420, window);
+        assert_eq!(confirmed, dollars(50));
+        assert!(pending.is_none());
+        assert!(liquidatable(confirmed));
+
+        // With no window configured, liquidation reads follow the latest print
+        let (confirmed, pending) = confirm_price(Some(dollars(100)), None, dollars(50), t0, 0);
+        assert_eq!(confirmed, dollars(50));
+        assert!(pending.is_none());
+    }
+
+    #[test]
+    fn test_eight_decimal_source_is_normalized() {
+        // $0.01234567 from an 8-decimal feed
+        let raw = U256::from(1_234_567u64);
+        let normalized = normalize_price(raw, 8);
+        assert_eq!(normalized, U256::from(12_345_670_000_000_000u128));
+
+        // Normalized, it passes the same bounds and deviation checks as an 18-decimal print
+        let config = OracleConfig::default();
+        let now = 1_700_000_000u64;
+        assert_eq!(price_status(normalized, now, now, 3600, normalized, &config), OracleStatus::Ok);
+        // Unnormalized it would be far below the $0.001 floor
+        assert_eq!(price_status(raw, now, now, 3600, raw, &config), OracleStatus::Deviation);
+
+        // 18 decimals is a no-op; higher precision truncates
+        assert_eq!(normalize_price(normalized, CANONICAL_PRICE_DECIMALS), normalized);
+        assert_eq!(normalize_price(U256::from(1_234_567_899u64), 27), U256::one());
+        assert_eq!(normalize_price(U256::MAX, 0), U256::MAX);
+
+        // The declared precision must match the configured one
+        assert_eq!(check_source_decimals(8, 8), Ok(()));
+        assert_eq!(check_source_decimals(18, 8), Err(CdpError::InvalidConfig));
+    }
+
+    #[test]
+    fn test_stale_policy_on_stale_cache() {
+        let cached_at = 1_700_000_000u64;
+        let now = cached_at + DEFAULT_MAX_PRICE_AGE_SECONDS + 1;
+        let price_at = |now: u64| PriceData {
+            price_int: U256::from(PRICE_SCALE / 20),
+            price_decimals: CANONICAL_PRICE_DECIMALS,
+            timestamp_sec: cached_at,
+            status: status_within_heartbeat(OracleStatus::Ok, now, cached_at, DEFAULT_MAX_PRICE_AGE_SECONDS),
+        };
+        let stale = price_at(now);
+        assert_eq!(stale.status, OracleStatus::Stale);
+
+        // LastGood: the cached price comes back flagged Stale
+        assert_eq!(apply_stale_policy(stale.clone(), StalePolicy::LastGood), Ok(stale.clone()));
+
+        // Revert: the read fails instead
+        assert_eq!(apply_stale_policy(stale, StalePolicy::Revert), Err(CdpError::OraclePriceStale));
+
+        // A fresh price passes under either policy
+        let fresh = price_at(cached_at + 60);
+        assert_eq!(apply_stale_policy(fresh.clone(), StalePolicy::Revert), Ok(fresh.clone()));
+        assert_eq!(apply_stale_policy(fresh.clone(), StalePolicy::LastGood), Ok(fresh));
+    }
 }
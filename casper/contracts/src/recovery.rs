@@ -0,0 +1,90 @@
+//! Recovery of tokens accidentally sent to protocol contracts.
+//!
+//! Contracts expose an admin `sweep_token` that delegates here. The caller
+//! passes the amount of `token` it accounts for (e.g. branch collateral or
+//! treasury fees); only the balance above that can be swept.
+
+use odra::prelude::*;
+use odra::casper_types::{U256, runtime_args};
+use odra::CallDef;
+use crate::errors::CdpError;
+
+/// Emitted when a stuck token is swept out of a protocol contract
+#[odra::event]
+pub struct TokenSwept {
+    /// Token contract swept
+    pub token: Address,
+    /// Recipient of the swept tokens
+    pub to: Address,
+    /// Amount swept
+    pub amount: U256,
+}
+
+/// Check that sweeping `amount` leaves at least `accounted` behind
+pub fn check_sweep(amount: U256, balance: U256, accounted: U256) -> Result<(), CdpError> {
+    if amount > balance.saturating_sub(accounted) {
+        Err(CdpError::InsufficientTokenBalance)
+    } else {
+        Ok(())
+    }
+}
+
+/// Transfer `amount` of a CEP-18 `token` held by the calling contract to `to`
+///
+/// When `accounted` is non-zero the contract's balance is read and the sweep
+/// reverts if it would dip below the accounted amount.
+pub fn sweep_token(env: &odra::ContractEnv, token: Address, to: Address, amount: U256, accounted: U256) {
+    if !accounted.is_zero() {
+        let balance_args = runtime_args! {
+            "account" => env.self_address()
+        };
+        let balance_call = CallDef::new("balance_of", false, balance_args);
+        let balance: U256 = env.call_contract(token, balance_call);
+        if let Err(err) = check_sweep(amount, balance, accounted) {
+            env.revert(err);
+        }
+    }
+
+    let transfer_args = runtime_args! {
+        "recipient" => to,
+        "amount" => amount
+    };
+    let transfer_call = CallDef::new("transfer", true, transfer_args);
+    let success: bool = env.call_contract(token, transfer_call);
+    if !success {
+        env.revert(CdpError::TokenTransferFailed);
+    }
+
+    env.emit_event(TokenSwept { token, to, amount });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_unrelated_token_fully_sweepable() {
+        // Nothing accounted: the whole balance can be swept
+        let balance = U256::from(1000u64);
+        assert_eq!(check_sweep(balance, balance, U256::zero()), Ok(()));
+    }
+
+    #[test]
+    fn test_collateral_cannot_be_swept_below_total() {
+        let total_collateral = U256::from(800u64);
+        let balance = U256::from(1000u64);
+
+        // Surplus above accounted collateral is recoverable
+        assert_eq!(check_sweep(U256::from(200u64), balance, total_collateral), Ok(()));
+        // Anything dipping into accounted collateral is rejected
+        assert_eq!(
+            check_sweep(U256::from(201u64), balance, total_collateral),
+            Err(CdpError::InsufficientTokenBalance)
+        );
+        // Under-collateralized balance: nothing is sweepable
+        assert_eq!(
+            check_sweep(U256::from(1u64), U256::from(700u64), total_collateral),
+            Err(CdpError::InsufficientTokenBalance)
+        );
+    }
+}
@@ -6,7 +6,7 @@
 //! Key mechanics:
 //! - Redeemer sends gUSD, receives collateral minus fee
 //! - Vaults are redeemed starting with lowest interest rate
-//! - Redemption fee goes to treasury
+//! - Redemption fee goes to treasury, optionally shared with SP depositors
 //! - Partial vault redemption supported
 //!
 //! Safe mode restrictions:
@@ -15,8 +15,13 @@
 use odra::prelude::*;
 use odra::casper_types::{U256, U512, RuntimeArgs, runtime_args};
 use odra::CallDef;
-use crate::types::{CollateralId, VaultKey, OracleStatus, SafeModeState};
+use crate::types::{CollateralId, ComponentId, VaultKey, OracleStatus, SafeModeState};
+use crate::math::{collateral_to_value, value_to_collateral, mul_div_ceil, mul_div_floor, COLLATERAL_SCALE};
+use crate::branch::is_dust_position;
+use crate::events::next_event_seq;
+use crate::registry::{self, resolve_all};
 use crate::errors::CdpError;
+use crate::reentrancy::check_not_entered;
 use crate::styks_oracle::StyksOracle;
 
 /// gUSD stablecoin interface
@@ -31,9 +36,14 @@ pub trait GUsd {
 pub trait Branch {
     fn get_collateral(&self, owner: Address, vault_id: u64) -> U256;
     fn get_debt(&self, owner: Address, vault_id: u64) -> U256;
+    fn get_effective_collateral(&self, owner: Address, vault_id: u64) -> U256;
+    fn get_effective_debt(&self, owner: Address, vault_id: u64) -> U256;
     fn get_interest_rate_bps(&self, owner: Address, vault_id: u64) -> u32;
     fn reduce_collateral_for_redemption(&mut self, owner: Address, vault_id: u64, collateral_amount: U256, debt_amount: U256);
+    fn reduce_collateral_for_redemption_by_key(&mut self, vault_key: VaultKey, collateral_amount: U256, debt_amount: U256);
     fn get_sorted_vault_owners(&self, max_count: u32) -> Vec<VaultKey>;
+    fn get_min_collateral(&self) -> U256;
+    fn get_total_debt(&self) -> U256;
 }
 
 /// CEP-18 token interface for stCSPR
@@ -42,12 +52,6 @@ pub trait Cep18 {
     fn transfer(&mut self, recipient: Address, amount: U256) -> bool;
 }
 
-/// Precision scale for prices (1e18)
-const PRICE_SCALE: u64 = 1_000_000_000_000_000_000;
-
-/// Collateral decimals (CSPR/stCSPR use 9 decimals)
-const COLLATERAL_DECIMALS: u64 = 1_000_000_000;
-
 /// Basis points scale
 const BPS_SCALE: u32 = 10000;
 
@@ -57,6 +61,34 @@ const BASE_REDEMPTION_FEE_BPS: u32 = 50;
 /// Maximum redemption fee in basis points (5% = 500 bps)
 const MAX_REDEMPTION_FEE_BPS: u32 = 500;
 
+/// Critical collateral ratio (150%); redeeming vaults below it earns the risk rebate
+const CCR_BPS: u32 = 15000;
+
+/// Minimum collateral ratio (110%); the floor for `redemption_min_icr_bps`
+const MCR_BPS: u32 = 11000;
+
+/// Upper bound for the per-vault redemption cooldown (7 days)
+const MAX_REDEMPTION_COOLDOWN_SECONDS: u64 = 604_800;
+
+/// Half-life of the recent redemption volume (12 hours)
+const REDEMPTION_VOLUME_HALF_LIFE_SECONDS: u64 = 43_200;
+
+/// Cap on the suggested borrower rate floor (matches the 40% max interest rate)
+const MAX_RATE_FLOOR_BPS: u32 = 4000;
+
+/// Components pulled from the registry by `refresh_addresses`
+pub const REGISTRY_DEPENDENCIES: [ComponentId; 9] = [
+    ComponentId::Router,
+    ComponentId::Stablecoin,
+    ComponentId::Treasury,
+    ComponentId::StyksOracle,
+    ComponentId::ScsprYbToken,
+    ComponentId::BranchCspr,
+    ComponentId::BranchScspr,
+    ComponentId::ScsprToken,
+    ComponentId::StabilityPool,
+];
+
 /// Minimum redemption amount (prevents dust redemptions)
 const MIN_REDEMPTION: u64 = 1_000_000_000_000_000_000; // 1 gUSD
 
@@ -83,10 +115,49 @@ pub struct VaultRedemptionResult {
     pub collateral_sent: U256,
     /// Whether vault was fully redeemed (closed)
     pub fully_redeemed: bool,
+    /// Whether the vault's ICR was below CCR before the redemption
+    pub below_ccr: bool,
+}
+
+/// Emitted once per branch redemption
+#[odra::event]
+pub struct Redeemed {
+    /// Redemption engine event sequence number
+    pub seq: u64,
+    /// Account that redeemed gUSD
+    pub redeemer: Address,
+    /// Collateral branch redeemed against
+    pub collateral_id: CollateralId,
+    /// gUSD redeemed
+    pub gusd_in: U256,
+    /// Collateral sent to the redeemer (after fee)
+    pub collateral_out: U256,
+    /// Collateral fee sent to the treasury
+    pub fee: U256,
+    /// Number of vaults touched
+    pub vaults_touched: u32,
+}
+
+/// Emitted for each vault touched by a redemption
+#[odra::event]
+pub struct VaultRedeemed {
+    /// Redemption engine event sequence number
+    pub seq: u64,
+    /// Vault owner
+    pub owner: Address,
+    /// Vault id
+    pub vault_id: u64,
+    /// Debt reduced from the vault
+    pub debt_redeemed: U256,
+    /// Collateral taken from the vault
+    pub collateral_sent: U256,
+    /// Whether the vault's debt was fully redeemed
+    pub fully_redeemed: bool,
 }
 
 /// Result of a redemption operation
 #[odra::odra_type]
+#[derive(Default)]
 pub struct RedemptionResult {
     /// Total gUSD redeemed
     pub csprusd_redeemed: U256,
@@ -98,6 +169,30 @@ pub struct RedemptionResult {
     pub vaults_touched: u32,
 }
 
+/// Result of a cross-branch redemption with per-branch breakdown
+#[odra::odra_type]
+pub struct BestRedemptionResult {
+    /// Combined result across both branches
+    pub total: RedemptionResult,
+    /// Portion redeemed from the CSPR branch
+    pub cspr: RedemptionResult,
+    /// Portion redeemed from the stCSPR branch
+    pub scspr: RedemptionResult,
+}
+
+/// Detailed redemption quote from a simulated vault walk
+#[odra::odra_type]
+pub struct RedemptionQuote {
+    /// Collateral the redeemer would receive (after fee)
+    pub collateral_out: U256,
+    /// Collateral fee that would be charged
+    pub fee: U256,
+    /// Number of vaults the redemption would touch
+    pub vaults_touched: u32,
+    /// gUSD that the walked vaults cannot absorb (partial fill)
+    pub gusd_unfilled: U256,
+}
+
 /// Redemption statistics
 #[odra::odra_type]
 pub struct RedemptionStats {
@@ -112,7 +207,7 @@ pub struct RedemptionStats {
 }
 
 /// Redemption Engine Contract
-#[odra::module]
+#[odra::module(events = [Redeemed, VaultRedeemed])]
 pub struct RedemptionEngine {
     /// Registry contract address
     registry: Var<Address>,
@@ -142,8 +237,50 @@ pub struct RedemptionEngine {
     total_collateral_distributed: Var<U256>,
     /// Total fees collected
     total_fees_collected: Var<U256>,
+    /// State beyond the core fields (nested: a module holds at most 15 fields)
+    state: SubModule<RedemptionEngineState>,
+}
+
+/// Redemption engine state added on top of the core fee and stats storage
+#[odra::module]
+pub struct RedemptionEngineState {
+    /// Governance-set redemption limits
+    params: SubModule<RedemptionParams>,
     /// Safe mode state
     safe_mode: Var<SafeModeState>,
+    /// Stability pool address (receives its share of redemption fees)
+    stability_pool: Var<Address>,
+    /// Reentrancy guard for external transfer sections
+    reentrancy_lock: Var<bool>,
+    /// Block time each vault was last redeemed against
+    last_redeemed_at: Mapping<VaultKey, u64>,
+    /// Redeemed gUSD, decayed by REDEMPTION_VOLUME_HALF_LIFE_SECONDS
+    recent_redemption_volume: Var<U256>,
+    /// Block time `recent_redemption_volume` was last decayed
+    redemption_volume_updated_at: Var<u64>,
+    /// Sequence number of the last emitted redemption event
+    event_seq: Var<u64>,
+    /// Per redeemer: (gUSD redeemed in the current window, window start)
+    redeemed_in_window: Mapping<Address, (U256, u64)>,
+}
+
+/// Governance-set redemption limits and fee splits
+#[odra::module]
+pub struct RedemptionParams {
+    /// Seconds a redeemed vault is deferred in redemption order (0 = disabled)
+    redemption_cooldown_seconds: Var<u64>,
+    /// Max gUSD per branch redemption as bps of branch total debt (0 = unlimited)
+    max_redemption_fraction_bps: Var<u32>,
+    /// Share of the collateral fee paid to SP depositors, in bps (default 0)
+    redemption_fee_sp_share_bps: Var<u32>,
+    /// Share of the fee rebated when all redeemed debt is from sub-CCR vaults, in bps (default 0)
+    risk_rebate_bps: Var<u32>,
+    /// Max gUSD one address may redeem per rate-limit window (0 = disabled)
+    max_redeemed_per_window: Var<U256>,
+    /// Length of the per-address rate-limit window in seconds
+    rate_limit_window_seconds: Var<u64>,
+    /// Vaults below this ICR are skipped by redemptions, in bps (0 = disabled)
+    redemption_min_icr_bps: Var<u32>,
 }
 
 #[odra::module]
@@ -173,7 +310,7 @@ impl RedemptionEngine {
         self.total_fees_collected.set(U256::zero());
 
         // Initialize safe mode
-        self.safe_mode.set(SafeModeState {
+        self.state.safe_mode.set(SafeModeState {
             is_active: false,
             triggered_at: 0,
             reason: OracleStatus::Ok,
@@ -197,6 +334,11 @@ impl RedemptionEngine {
         self.scspr_token.set(scspr_token);
     }
 
+    /// Set stability pool address
+    pub fn set_stability_pool(&mut self, stability_pool: Address) {
+        self.state.stability_pool.set(stability_pool);
+    }
+
     /// Set Styks oracle address
     pub fn set_styks_oracle(&mut self, styks_oracle: Address) {
         self.styks_oracle.set(styks_oracle);
@@ -207,12 +349,43 @@ impl RedemptionEngine {
         self.scspr_ybtoken.set(scspr_ybtoken);
     }
 
+    /// Pull all dependency addresses from the registry in one call (admin only)
+    ///
+    /// Components the registry doesn't know yet keep their current address;
+    /// the individual setters remain available as overrides.
+    pub fn refresh_addresses(&mut self) {
+        self.require_registry_admin();
+        let registry_addr = match self.registry.get() {
+            Some(addr) => addr,
+            None => self.env().revert(CdpError::InvalidConfig),
+        };
+        let env = self.env();
+        let resolved = resolve_all(&REGISTRY_DEPENDENCIES, |component| {
+            registry::try_resolve(&env, registry_addr, component)
+        });
+        for (component, address) in resolved {
+            match component {
+                ComponentId::Router => self.router.set(address),
+                ComponentId::Stablecoin => self.stablecoin.set(address),
+                ComponentId::Treasury => self.treasury.set(address),
+                ComponentId::StyksOracle => self.styks_oracle.set(address),
+                ComponentId::ScsprYbToken => self.scspr_ybtoken.set(address),
+                ComponentId::BranchCspr => self.branch_cspr.set(address),
+                ComponentId::BranchScspr => self.branch_scspr.set(address),
+                ComponentId::ScsprToken => self.scspr_token.set(address),
+                ComponentId::StabilityPool => self.state.stability_pool.set(address),
+                _ => {}
+            }
+        }
+    }
+
     // ========== Redemption Functions ==========
 
     /// Redeem gUSD for collateral
     /// Returns the collateral amount received after fees
     ///
-    /// Note: Caller must have approved this contract to spend their gUSD.
+    /// Only the gUSD the walked vaults can fill is burned; reverts if they
+    /// fill nothing. Note: Caller must have approved this contract to spend their gUSD.
     pub fn redeem(
         &mut self,
         collateral_id: CollateralId,
@@ -222,83 +395,109 @@ impl RedemptionEngine {
     ) -> RedemptionResult {
         // Redemptions BLOCKED in safe mode
         self.require_not_safe_mode();
+        self.enter_non_reentrant();
 
         // Validate amount
         if csprusd_amount < U256::from(MIN_REDEMPTION) {
-            self.env().revert(CdpError::BelowMinDebt);
+            self.env().revert(CdpError::RedemptionBelowMin);
         }
 
         // Calculate fee
         let current_fee_bps = self.get_current_fee_bps();
-        if current_fee_bps > max_fee_bps {
-            self.env().revert(CdpError::InvalidConfig);
-        }
-
-        // Get price from oracle
-        let price = self.get_price(collateral_id);
-        if price.is_zero() {
-            self.env().revert(CdpError::InvalidConfig);
+        if let Err(err) = check_max_fee(current_fee_bps, max_fee_bps) {
+            self.env().revert(err);
         }
 
-        // Calculate collateral amount before fee
-        // gUSD is 18 decimals, price is 18 decimals, collateral is 9 decimals
-        // collateral = csprusd_amount * COLLATERAL_DECIMALS / price
-        let collateral_before_fee = csprusd_amount * U256::from(COLLATERAL_DECIMALS) / price;
-
-        // Calculate fee
-        let fee_amount = collateral_before_fee * U256::from(current_fee_bps) / U256::from(BPS_SCALE);
-        let collateral_after_fee = collateral_before_fee - fee_amount;
-
         let redeemer = self.env().caller();
 
-        // Process redemption against vaults (reduces vault collateral and debt)
-        let vaults_touched = self.process_redemption(
+        // Process redemption against vaults and pay out collateral
+        let result = self.redeem_from_branch(
             collateral_id,
+            redeemer,
             csprusd_amount,
-            collateral_before_fee,
+            current_fee_bps,
             hint.unwrap_or_default(),
         );
-
-        // Burn gUSD from redeemer (requires approval)
-        // Using transfer_from to burn address (zero address not supported, use treasury as burn sink)
-        let stablecoin_addr = self.stablecoin.get().expect("stablecoin not set");
-        let treasury_addr = self.treasury.get().expect("treasury not set");
-        let burn_args = runtime_args! {
-            "owner" => redeemer,
-            "recipient" => treasury_addr,
-            "amount" => csprusd_amount
-        };
-        let burn_call = CallDef::new("transfer_from", true, burn_args);
-        let burn_success: bool = self.env().call_contract(stablecoin_addr, burn_call);
-        if !burn_success {
-            self.env().revert(CdpError::InsufficientTokenBalance);
+        if result.csprusd_redeemed.is_zero() {
+            self.env().revert(CdpError::RedemptionNoEligibleVaults);
         }
 
-        // Transfer collateral to redeemer
-        self.transfer_collateral(collateral_id, redeemer, collateral_after_fee);
+        // Burn the filled gUSD from redeemer (requires approval)
+        self.burn_redeemed(redeemer, result.csprusd_redeemed);
+        self.exit_non_reentrant();
 
-        // Transfer fee to treasury
-        if !fee_amount.is_zero() {
-            if let Some(treasury_addr) = self.treasury.get() {
-                self.transfer_collateral(collateral_id, treasury_addr, fee_amount);
-            }
+        result
+    }
+
+    /// Redeem gUSD across both branches, lowest interest rate first
+    ///
+    /// Walks both branches' redemption-ordered vaults as one list merged by
+    /// interest rate (ties go to CSPR), touching at most `max_iterations`
+    /// vaults in total, then redeems each branch's share of that walk. Only
+    /// the filled amount is burned. Reverts if neither branch can fill anything.
+    pub fn redeem_best(
+        &mut self,
+        csprusd_amount: U256,
+        max_fee_bps: u32,
+        max_iterations: u32,
+    ) -> BestRedemptionResult {
+        // Redemptions BLOCKED in safe mode
+        self.require_not_safe_mode();
+        self.enter_non_reentrant();
+
+        if csprusd_amount < U256::from(MIN_REDEMPTION) {
+            self.env().revert(CdpError::RedemptionBelowMin);
         }
 
-        // Update statistics
-        let total_redeemed = self.total_redeemed.get().unwrap_or(U256::zero());
-        self.total_redeemed.set(total_redeemed + csprusd_amount);
+        let current_fee_bps = self.get_current_fee_bps();
+        if let Err(err) = check_max_fee(current_fee_bps, max_fee_bps) {
+            self.env().revert(err);
+        }
 
-        let total_distributed = self.total_collateral_distributed.get().unwrap_or(U256::zero());
-        self.total_collateral_distributed.set(total_distributed + collateral_after_fee);
+        let max_iterations = if max_iterations == 0 { 10 } else { max_iterations };
+        let (cspr_fill, scspr_fill) = merge_redemption_fills(
+            &self.branch_walk(CollateralId::Cspr, max_iterations),
+            &self.branch_walk(CollateralId::SCSPR, max_iterations),
+            csprusd_amount,
+            max_iterations,
+        );
 
-        let total_fees = self.total_fees_collected.get().unwrap_or(U256::zero());
-        self.total_fees_collected.set(total_fees + fee_amount);
+        // Each branch walks its own vaults in the same order as the merge
+        let redeemer = self.env().caller();
+        let mut cspr = RedemptionResult::default();
+        let mut scspr = RedemptionResult::default();
+        for (collateral_id, fill) in [(CollateralId::Cspr, cspr_fill), (CollateralId::SCSPR, scspr_fill)] {
+            if fill.is_zero() {
+                continue;
+            }
+            let hint = RedemptionHint {
+                first_vault_owner: None,
+                expected_rate_bps: 0,
+                max_iterations,
+            };
+            let result = self.redeem_from_branch(collateral_id, redeemer, fill, current_fee_bps, hint);
+            match collateral_id {
+                CollateralId::Cspr => cspr = result,
+                CollateralId::SCSPR => scspr = result,
+            }
+        }
 
-        RedemptionResult {
-            csprusd_redeemed: csprusd_amount,
-            collateral_received: collateral_after_fee,
-            fee_paid: fee_amount,
-            vaults_touched,
+        let filled = cspr.csprusd_redeemed + scspr.csprusd_redeemed;
+        if filled.is_zero() {
+            self.env().revert(CdpError::RedemptionNoEligibleVaults);
+        }
+        self.burn_redeemed(redeemer, filled);
+        self.exit_non_reentrant();
+
+        BestRedemptionResult {
+            total: RedemptionResult {
+                csprusd_redeemed: filled,
+                collateral_received: cspr.collateral_received + scspr.collateral_received,
+                fee_paid: cspr.fee_paid + scspr.fee_paid,
+                vaults_touched: cspr.vaults_touched + scspr.vaults_touched,
+            },
+            cspr,
+            scspr,
         }
     }
 
@@ -339,8 +538,40 @@ impl RedemptionEngine {
         let result = self.redeem(collateral_id, csprusd_amount, max_fee_bps, hint);
 
         // Check slippage protection
-        if result.collateral_received < min_collateral_out {
-            self.env().revert(CdpError::InvalidConfig);
+        if let Err(err) = check_min_out(result.collateral_received, min_collateral_out) {
+            self.env().revert(err);
+        }
+
+        result
+    }
+
+    /// Redeem exactly `collateral_out` of collateral, spending whatever gUSD it takes
+    ///
+    /// The gUSD needed (fee included) is computed at the current price and
+    /// rounded so the payout is never short of `collateral_out`. Reverts if
+    /// that exceeds `max_gusd_in`.
+    pub fn redeem_for_collateral(
+        &mut self,
+        collateral_id: CollateralId,
+        collateral_out: U256,
+        max_gusd_in: U256,
+        max_fee_bps: u32,
+    ) -> RedemptionResult {
+        let price = self.require_price(collateral_id);
+
+        let current_fee_bps = self.get_current_fee_bps();
+        if let Err(err) = check_max_fee(current_fee_bps, max_fee_bps) {
+            self.env().revert(err);
+        }
+
+        let gusd_in = gusd_for_collateral_out(collateral_out, price, current_fee_bps);
+        if gusd_in > max_gusd_in {
+            self.env().revert(CdpError::SlippageExceeded);
+        }
+
+        let result = self.redeem(collateral_id, gusd_in, max_fee_bps, None);
+        if let Err(err) = check_min_out(result.collateral_received, collateral_out) {
+            self.env().revert(err);
         }
 
         result
@@ -369,15 +600,59 @@ impl RedemptionEngine {
             return (U256::zero(), U256::zero());
         }
 
-        // gUSD is 18 decimals, price is 18 decimals, collateral is 9 decimals
-        let collateral_before_fee = csprusd_amount * U256::from(COLLATERAL_DECIMALS) / price;
+        let collateral_before_fee = value_to_collateral(csprusd_amount, price);
         let fee_bps = self.get_current_fee_bps();
-        let fee = collateral_before_fee * U256::from(fee_bps) / U256::from(BPS_SCALE);
+        let fee = redemption_fee(collateral_before_fee, fee_bps);
         let collateral_after_fee = collateral_before_fee - fee;
 
         (collateral_after_fee, fee)
     }
 
+    /// Detailed redemption quote simulating the vault walk without mutating state
+    ///
+    /// Plans the same vault walk as `redeem` over up to `max_iterations` vaults
+    /// so users can see partial-fill risk (`gusd_unfilled`) before signing.
+    pub fn quote_redemption_detailed(
+        &self,
+        collateral_id: CollateralId,
+        csprusd_amount: U256,
+        max_iterations: u32,
+    ) -> RedemptionQuote {
+        let price = self.get_price(collateral_id);
+        if price.is_zero() {
+            return RedemptionQuote {
+                collateral_out: U256::zero(),
+                fee: U256::zero(),
+                vaults_touched: 0,
+                gusd_unfilled: csprusd_amount,
+            };
+        }
+
+        let collateral_before_fee = value_to_collateral(csprusd_amount, price);
+        let plan = self.plan_redemption(collateral_id, csprusd_amount, collateral_before_fee, price, max_iterations);
+        quote_from_plan(&plan, csprusd_amount, self.get_current_fee_bps(), self.get_risk_rebate_bps())
+    }
+
+    /// Per-vault breakdown of what `redeem` would do right now, without mutating state
+    ///
+    /// Replays the same vault walk as `process_redemption` (redemption order,
+    /// cooldown deferral, dust handling) so callers can build hints off-chain.
+    /// Returns an empty list if the price is unavailable.
+    pub fn simulate_redemption(
+        &self,
+        collateral_id: CollateralId,
+        csprusd_amount: U256,
+        max_iterations: u32,
+    ) -> Vec<VaultRedemptionResult> {
+        let price = self.get_price(collateral_id);
+        if price.is_zero() {
+            return Vec::new();
+        }
+
+        let collateral_before_fee = value_to_collateral(csprusd_amount, price);
+        self.plan_redemption(collateral_id, csprusd_amount, collateral_before_fee, price, max_iterations)
+    }
+
     /// Get redemption statistics
     pub fn get_stats(&self) -> RedemptionStats {
         RedemptionStats {
@@ -446,21 +721,166 @@ impl RedemptionEngine {
         self.max_fee_bps.set(fee_bps);
     }
 
+    /// Set the per-vault redemption cooldown in seconds (admin only, 0 disables)
+    pub fn set_redemption_cooldown_seconds(&mut self, seconds: u64) {
+        self.require_registry_admin();
+        if seconds > MAX_REDEMPTION_COOLDOWN_SECONDS {
+            self.env().revert(CdpError::InvalidConfig);
+        }
+        self.state.params.redemption_cooldown_seconds.set(seconds);
+    }
+
+    /// Get the per-vault redemption cooldown in seconds
+    pub fn get_redemption_cooldown_seconds(&self) -> u64 {
+        self.state.params.redemption_cooldown_seconds.get().unwrap_or(0)
+    }
+
+    /// Set the ICR a vault needs to be redeemed against (admin only)
+    ///
+    /// In bps, at least MCR; riskier vaults are skipped so a redemption never
+    /// pushes them into liquidation. 0 disables the buffer (the default).
+    pub fn set_redemption_min_icr_bps(&mut self, min_icr_bps: u32) {
+        self.require_registry_admin();
+        if min_icr_bps != 0 && min_icr_bps < MCR_BPS {
+            self.env().revert(CdpError::InvalidConfig);
+        }
+        self.state.params.redemption_min_icr_bps.set(min_icr_bps);
+    }
+
+    /// Get the ICR a vault needs to be redeemed against in bps (0 = disabled)
+    pub fn get_redemption_min_icr_bps(&self) -> u32 {
+        self.state.params.redemption_min_icr_bps.get().unwrap_or(0)
+    }
+
+    /// Get when a vault was last redeemed against (0 if never)
+    pub fn get_last_redeemed_at(&self, owner: Address, vault_id: u64) -> u64 {
+        self.state.last_redeemed_at.get(&VaultKey { owner, id: vault_id }).unwrap_or(0)
+    }
+
+    /// Set the max share of a branch's total debt one redemption may take (admin only)
+    ///
+    /// In bps; 0 removes the limit. Larger redemptions revert with
+    /// `RedemptionTooLarge` and must be split.
+    pub fn set_max_redemption_fraction_bps(&mut self, fraction_bps: u32) {
+        self.require_registry_admin();
+        if fraction_bps > BPS_SCALE {
+            self.env().revert(CdpError::InvalidConfig);
+        }
+        self.state.params.max_redemption_fraction_bps.set(fraction_bps);
+    }
+
+    /// Set the share of redemption fees paid to SP depositors (admin only)
+    ///
+    /// In bps of the collateral fee; the remainder goes to the treasury, as
+    /// does the whole fee while the pool is empty.
+    pub fn set_redemption_fee_sp_share_bps(&mut self, share_bps: u32) {
+        self.require_registry_admin();
+        if share_bps > BPS_SCALE {
+            self.env().revert(CdpError::InvalidConfig);
+        }
+        self.state.params.redemption_fee_sp_share_bps.set(share_bps);
+    }
+
+    /// Get the share of redemption fees paid to SP depositors in bps
+    pub fn get_redemption_fee_sp_share_bps(&self) -> u32 {
+        self.state.params.redemption_fee_sp_share_bps.get().unwrap_or(0)
+    }
+
+    /// Set the fee rebate for redeeming sub-CCR vaults (admin only)
+    ///
+    /// In bps of the fee, applied in proportion to the share of redeemed debt
+    /// that came from vaults below CCR.
+    pub fn set_risk_rebate_bps(&mut self, rebate_bps: u32) {
+        self.require_registry_admin();
+        if rebate_bps > BPS_SCALE {
+            self.env().revert(CdpError::InvalidConfig);
+        }
+        self.state.params.risk_rebate_bps.set(rebate_bps);
+    }
+
+    /// Get the fee rebate for redeeming sub-CCR vaults in bps
+    pub fn get_risk_rebate_bps(&self) -> u32 {
+        self.state.params.risk_rebate_bps.get().unwrap_or(0)
+    }
+
+    /// Sequence number of the last emitted redemption event (0 if none yet)
+    pub fn get_event_seq(&self) -> u64 {
+        self.state.event_seq.get_or_default()
+    }
+
+    /// Set the per-address redemption rate limit (admin only)
+    ///
+    /// Each address may redeem at most `max_redeemed_per_window` gUSD per
+    /// `window_seconds`; 0 disables the limit (the default).
+    pub fn set_redemption_rate_limit(&mut self, max_redeemed_per_window: U256, window_seconds: u64) {
+        self.require_registry_admin();
+        if !max_redeemed_per_window.is_zero() && window_seconds == 0 {
+            self.env().revert(CdpError::InvalidConfig);
+        }
+        self.state.params.max_redeemed_per_window.set(max_redeemed_per_window);
+        self.state.params.rate_limit_window_seconds.set(window_seconds);
+    }
+
+    /// Get the max gUSD one address may redeem per window (0 = disabled)
+    pub fn get_max_redeemed_per_window(&self) -> U256 {
+        self.state.params.max_redeemed_per_window.get().unwrap_or(U256::zero())
+    }
+
+    /// Get the per-address rate-limit window in seconds
+    pub fn get_rate_limit_window_seconds(&self) -> u64 {
+        self.state.params.rate_limit_window_seconds.get().unwrap_or(0)
+    }
+
+    /// Get gUSD an address has redeemed in its current window and when that window started
+    pub fn get_redeemed_in_window(&self, redeemer: Address) -> (U256, u64) {
+        self.state.redeemed_in_window.get(&redeemer).unwrap_or((U256::zero(), 0))
+    }
+
+    /// Get the max share of branch total debt per redemption in bps (0 = unlimited)
+    pub fn get_max_redemption_fraction_bps(&self) -> u32 {
+        self.state.params.max_redemption_fraction_bps.get().unwrap_or(0)
+    }
+
+    /// Get recently redeemed gUSD, decayed to the current block time
+    pub fn get_recent_redemption_volume(&self) -> U256 {
+        let volume = self.state.recent_redemption_volume.get().unwrap_or(U256::zero());
+        let updated_at = self.state.redemption_volume_updated_at.get().unwrap_or(0);
+        let elapsed = self.env().get_block_time().saturating_sub(updated_at);
+        decay_volume(volume, elapsed, REDEMPTION_VOLUME_HALF_LIFE_SECONDS)
+    }
+
+    /// Suggested minimum borrower interest rate in bps
+    ///
+    /// Derived from the redemption base rate: the base fee plus half of the
+    /// recent redemption volume as a share of gUSD supply. Vaults below it are
+    /// the likeliest to be redeemed against; this is advice for frontends and
+    /// is not enforced.
+    pub fn get_redemption_rate_floor(&self) -> u32 {
+        let stablecoin_addr = match self.stablecoin.get() {
+            Some(addr) => addr,
+            None => return self.get_base_fee_bps(),
+        };
+        let supply_call = CallDef::new("total_supply", false, runtime_args! {});
+        let gusd_supply: U256 = self.env().call_contract(stablecoin_addr, supply_call);
+        redemption_rate_floor_bps(self.get_base_fee_bps(), self.get_recent_redemption_volume(), gusd_supply)
+    }
+
     // ========== Safe Mode Functions ==========
 
     /// Trigger safe mode
     pub fn trigger_safe_mode(&mut self, reason: OracleStatus) {
-        self.safe_mode.set(SafeModeState {
+        let safe_mode = SafeModeState {
             is_active: true,
             triggered_at: self.env().get_block_time(),
             reason,
-        });
+        };
+        self.state.safe_mode.set(safe_mode);
     }
 
     /// Clear safe mode (admin only)
     pub fn clear_safe_mode(&mut self) {
         // TODO: Add admin access control
-        self.safe_mode.set(SafeModeState {
+        self.state.safe_mode.set(SafeModeState {
             is_active: false,
             triggered_at: 0,
             reason: OracleStatus::Ok,
@@ -469,13 +889,45 @@ impl RedemptionEngine {
 
     /// Check if safe mode is active
     pub fn is_safe_mode_active(&self) -> bool {
-        self.safe_mode.get().map(|s| s.is_active).unwrap_or(false)
+        self.state.safe_mode.get().map(|s| s.is_active).unwrap_or(false)
     }
 
     // ========== Internal Functions ==========
 
+    /// Advance the event sequence counter and return the new value
+    fn next_event_seq(&mut self) -> u64 {
+        let seq = next_event_seq(self.state.event_seq.get_or_default());
+        self.state.event_seq.set(seq);
+        seq
+    }
+
+    fn enter_non_reentrant(&mut self) {
+        if let Err(err) = check_not_entered(self.state.reentrancy_lock.get().unwrap_or(false)) {
+            self.env().revert(err);
+        }
+        self.state.reentrancy_lock.set(true);
+    }
+
+    fn exit_non_reentrant(&mut self) {
+        self.state.reentrancy_lock.set(false);
+    }
+
+    fn require_registry_admin(&self) {
+        let registry_addr = match self.registry.get() {
+            Some(addr) => addr,
+            None => self.env().revert(CdpError::InvalidConfig),
+        };
+        let args = runtime_args! {
+            "caller" => self.env().caller()
+        };
+        let is_admin: bool = self.env().call_contract(registry_addr, CallDef::new("is_admin", false, args));
+        if !is_admin {
+            self.env().revert(CdpError::Unauthorized);
+        }
+    }
+
     fn require_not_safe_mode(&self) {
-        let state = self.safe_mode.get().unwrap_or(SafeModeState {
+        let state = self.state.safe_mode.get().unwrap_or(SafeModeState {
             is_active: false,
             triggered_at: 0,
             reason: OracleStatus::Ok,
@@ -486,6 +938,23 @@ impl RedemptionEngine {
     }
 
     fn get_price(&self, collateral_id: CollateralId) -> U256 {
+        self.price_and_rate(collateral_id).0
+    }
+
+    /// Price for a state-changing redemption; reverts instead of returning zero
+    ///
+    /// A zero stCSPR exchange rate reverts with `OracleInvalidRate` rather than
+    /// surfacing as a zero composite price.
+    fn require_price(&self, collateral_id: CollateralId) -> U256 {
+        let (price, scspr_rate) = self.price_and_rate(collateral_id);
+        if let Err(err) = check_redemption_price(price, scspr_rate) {
+            self.env().revert(err);
+        }
+        price
+    }
+
+    /// Oracle price and, for stCSPR, the exchange rate it was composed with
+    fn price_and_rate(&self, collateral_id: CollateralId) -> (U256, Option<U256>) {
         let styks_addr = self.styks_oracle.get().expect("styks_oracle not set");
 
         // Get stCSPR exchange rate if needed
@@ -496,7 +965,7 @@ impl RedemptionEngine {
         };
 
         // Call Styks oracle directly
-        StyksOracle::get_price(&self.env(), styks_addr, collateral_id, scspr_rate)
+        (StyksOracle::get_price(&self.env(), styks_addr, collateral_id, scspr_rate), scspr_rate)
     }
 
     fn get_scspr_exchange_rate(&self) -> Option<U256> {
@@ -506,108 +975,334 @@ impl RedemptionEngine {
         Some(self.env().call_contract::<U256>(ybtoken_addr, call_def))
     }
 
-    fn process_redemption(
+    /// Redeem up to `csprusd_amount` from one branch and pay out collateral and fee
+    ///
+    /// Fee and payout are taken from the collateral the vaults actually gave
+    /// up, which falls short of `csprusd_amount` when the walked vaults can't
+    /// fill it. Does not burn gUSD; callers burn the returned `csprusd_redeemed`.
+    fn redeem_from_branch(
         &mut self,
         collateral_id: CollateralId,
-        mut csprusd_remaining: U256,
-        mut collateral_remaining: U256,
+        redeemer: Address,
+        csprusd_amount: U256,
+        fee_bps: u32,
         hint: RedemptionHint,
-    ) -> u32 {
-        // Get branch address
-        let branch_addr = match collateral_id {
-            CollateralId::Cspr => self.branch_cspr.get().expect("branch_cspr not set"),
-            CollateralId::SCSPR => self.branch_scspr.get().expect("branch_scspr not set"),
-        };
+    ) -> RedemptionResult {
+        // Get price from oracle
+        let price = self.require_price(collateral_id);
+
+        // Large redeemers must split across transactions
+        let fraction_bps = self.get_max_redemption_fraction_bps();
+        if fraction_bps != 0
+            && exceeds_redemption_fraction(csprusd_amount, self.get_branch_total_debt(collateral_id), fraction_bps)
+        {
+            self.env().revert(CdpError::RedemptionTooLarge);
+        }
 
-        let price = self.get_price(collateral_id);
-        let max_iterations = if hint.max_iterations == 0 { 10 } else { hint.max_iterations };
+        // Calculate collateral amount before fee
+        let collateral_before_fee = value_to_collateral(csprusd_amount, price);
 
-        // Get sorted vault owners from branch (low interest rate first)
-        let get_sorted_args = runtime_args! {
-            "max_count" => max_iterations
+        // Process redemption against vaults (reduces vault collateral and debt)
+        let plan = self.process_redemption(
+            collateral_id,
+            csprusd_amount,
+            collateral_before_fee,
+            hint,
+        );
+        let vaults_touched = plan.len() as u32;
+        let (gusd_redeemed, collateral_redeemed) = plan_totals(&plan);
+
+        // One address can't monopolize redemptions within a window
+        let window = match record_rate_limited(
+            self.get_redeemed_in_window(redeemer),
+            gusd_redeemed,
+            self.env().get_block_time(),
+            self.get_max_redeemed_per_window(),
+            self.get_rate_limit_window_seconds(),
+        ) {
+            Ok(window) => window,
+            Err(err) => self.env().revert(err),
         };
-        let get_sorted_call = CallDef::new("get_sorted_vault_owners", false, get_sorted_args);
-        let vault_keys: Vec<VaultKey> = self.env().call_contract(branch_addr, get_sorted_call);
+        self.state.redeemed_in_window.set(&redeemer, window);
 
-        let mut vaults_touched = 0u32;
+        // Calculate fee, rebated for debt redeemed from sub-CCR vaults
+        let fee_amount = redemption_fee(collateral_redeemed, fee_bps);
+        let fee_amount = risk_rebated_fee(fee_amount, self.get_risk_rebate_bps(), sub_ccr_debt(&plan), gusd_redeemed);
+        let collateral_after_fee = collateral_redeemed - fee_amount;
 
-        for vault_key in vault_keys {
-            if csprusd_remaining.is_zero() || collateral_remaining.is_zero() {
-                break;
-            }
-
-            // Get vault debt
-            let get_debt_args = runtime_args! {
-                "owner" => vault_key.owner,
-                "vault_id" => vault_key.id
-            };
-            let get_debt_call = CallDef::new("get_debt", false, get_debt_args);
-            let vault_debt: U256 = self.env().call_contract(branch_addr, get_debt_call);
+        // Transfer collateral to redeemer
+        self.transfer_collateral(collateral_id, redeemer, collateral_after_fee);
 
-            if vault_debt.is_zero() {
-                continue;
+        // Transfer fee: SP depositors' share first, the rest to treasury
+        let (sp_fee, _) = split_redemption_fee(fee_amount, self.get_redemption_fee_sp_share_bps());
+        let sp_fee = self.pay_sp_fee_share(collateral_id, sp_fee);
+        let treasury_fee = fee_amount - sp_fee;
+        if !treasury_fee.is_zero() {
+            if let Some(treasury_addr) = self.treasury.get() {
+                self.transfer_collateral(collateral_id, treasury_addr, treasury_fee);
             }
+        }
 
-            // Get vault collateral
-            let get_coll_args = runtime_args! {
-                "owner" => vault_key.owner,
-                "vault_id" => vault_key.id
-            };
-            let get_coll_call = CallDef::new("get_collateral", false, get_coll_args);
-            let vault_collateral: U256 = self.env().call_contract(branch_addr, get_coll_call);
+        // Update statistics
+        let total_redeemed = self.total_redeemed.get().unwrap_or(U256::zero());
+        self.total_redeemed.set(total_redeemed + gusd_redeemed);
+        self.record_redemption_volume(gusd_redeemed);
 
-            if vault_collateral.is_zero() {
-                continue;
-            }
+        let total_distributed = self.total_collateral_distributed.get().unwrap_or(U256::zero());
+        self.total_collateral_distributed.set(total_distributed + collateral_after_fee);
 
-            // Calculate how much to redeem from this vault
-            let debt_to_redeem = if csprusd_remaining >= vault_debt {
-                vault_debt
-            } else {
-                csprusd_remaining
-            };
+        let total_fees = self.total_fees_collected.get().unwrap_or(U256::zero());
+        self.total_fees_collected.set(total_fees + fee_amount);
 
-            // Calculate collateral to take: collateral (9 dec) = debt (18 dec) * 1e9 / price (18 dec)
-            let collateral_to_take = debt_to_redeem * U256::from(COLLATERAL_DECIMALS) / price;
+        let seq = self.next_event_seq();
+        self.env().emit_event(Redeemed {
+            seq,
+            redeemer,
+            collateral_id,
+            gusd_in: gusd_redeemed,
+            collateral_out: collateral_after_fee,
+            fee: fee_amount,
+            vaults_touched,
+        });
 
-            // Cap at vault's actual collateral
-            let actual_collateral = if collateral_to_take > vault_collateral {
-                vault_collateral
-            } else {
-                collateral_to_take
-            };
+        RedemptionResult {
+            csprusd_redeemed: gusd_redeemed,
+            collateral_received: collateral_after_fee,
+            fee_paid: fee_amount,
+            vaults_touched,
+        }
+    }
+
+    /// Credit `amount` of fee collateral to SP depositors and send it to the pool
+    ///
+    /// Returns the amount the pool accepted (zero if unset or empty).
+    fn pay_sp_fee_share(&mut self, collateral_id: CollateralId, amount: U256) -> U256 {
+        if amount.is_zero() {
+            return U256::zero();
+        }
+        let sp_addr = match self.state.stability_pool.get() {
+            Some(addr) => addr,
+            None => return U256::zero(),
+        };
+
+        let gain_args = runtime_args! {
+            "collateral_id" => collateral_id,
+            "amount" => amount
+        };
+        let gain_call = CallDef::new("add_collateral_gain", true, gain_args);
+        let credited: U256 = self.env().call_contract(sp_addr, gain_call);
+        self.transfer_collateral(collateral_id, sp_addr, credited);
+        credited
+    }
+
+    /// Burn redeemed gUSD from the redeemer (requires approval)
+    ///
+    /// Uses transfer_from to the treasury as burn sink (zero address not supported).
+    fn burn_redeemed(&mut self, redeemer: Address, amount: U256) {
+        let stablecoin_addr = self.stablecoin.get().expect("stablecoin not set");
+        let treasury_addr = self.treasury.get().expect("treasury not set");
+        let burn_args = runtime_args! {
+            "owner" => redeemer,
+            "recipient" => treasury_addr,
+            "amount" => amount
+        };
+        let burn_call = CallDef::new("transfer_from", true, burn_args);
+        let burn_success: bool = self.env().call_contract(stablecoin_addr, burn_call);
+        if !burn_success {
+            self.env().revert(CdpError::InsufficientTokenBalance);
+        }
+    }
 
-            // Cap at remaining collateral needed
-            let actual_collateral = if actual_collateral > collateral_remaining {
-                collateral_remaining
-            } else {
-                actual_collateral
+    /// A branch's first `max_count` redeemable vaults with their interest rates
+    ///
+    /// Empty when the branch has no usable price.
+    fn branch_walk(&self, collateral_id: CollateralId, max_count: u32) -> BranchWalk {
+        let price = self.get_price(collateral_id);
+        if price.is_zero() {
+            return BranchWalk {
+                vaults: Vec::new(),
+                price,
+                min_collateral: U256::zero(),
             };
+        }
+        let branch_addr = match collateral_id {
+            CollateralId::Cspr => self.branch_cspr.get().expect("branch_cspr not set"),
+            CollateralId::SCSPR => self.branch_scspr.get().expect("branch_scspr not set"),
+        };
 
-            // Recalculate debt based on actual collateral: debt (18 dec) = collateral (9 dec) * price (18 dec) / 1e9
-            let actual_debt = actual_collateral * price / U256::from(COLLATERAL_DECIMALS);
+        let vaults = self
+            .read_redemption_vault_entries(collateral_id, max_count, price)
+            .into_iter()
+            .map(|(vault_key, debt, collateral)| {
+                let rate_args = runtime_args! {
+                    "owner" => vault_key.owner,
+                    "vault_id" => vault_key.id
+                };
+                let rate_call = CallDef::new("get_interest_rate_bps", false, rate_args);
+                let rate_bps: u32 = self.env().call_contract(branch_addr, rate_call);
+                (rate_bps, debt, collateral)
+            })
+            .collect();
+        BranchWalk {
+            vaults,
+            price,
+            min_collateral: self.get_branch_min_collateral(collateral_id),
+        }
+    }
 
-            if actual_debt.is_zero() || actual_collateral.is_zero() {
-                continue;
-            }
+    /// Redeem against the branch's vaults in order
+    ///
+    /// Returns the per-vault redemptions applied, i.e. the debt and collateral
+    /// actually taken from each vault.
+    fn process_redemption(
+        &mut self,
+        collateral_id: CollateralId,
+        csprusd_remaining: U256,
+        collateral_remaining: U256,
+        hint: RedemptionHint,
+    ) -> Vec<VaultRedemptionResult> {
+        // Get branch address
+        let branch_addr = match collateral_id {
+            CollateralId::Cspr => self.branch_cspr.get().expect("branch_cspr not set"),
+            CollateralId::SCSPR => self.branch_scspr.get().expect("branch_scspr not set"),
+        };
+
+        let price = self.require_price(collateral_id);
+        let plan = self.plan_redemption(
+            collateral_id,
+            csprusd_remaining,
+            collateral_remaining,
+            price,
+            hint.max_iterations,
+        );
+
+        let now = self.env().get_block_time();
+        for redemption in plan.iter() {
+            self.state.last_redeemed_at.set(&redemption.vault_key, now);
 
             // Call branch to reduce vault collateral and debt
             let reduce_args = runtime_args! {
-                "owner" => vault_key.owner,
-                "vault_id" => vault_key.id,
-                "collateral_amount" => actual_collateral,
-                "debt_amount" => actual_debt
+                "vault_key" => redemption.vault_key,
+                "collateral_amount" => redemption.collateral_sent,
+                "debt_amount" => redemption.debt_redeemed
             };
-            let reduce_call = CallDef::new("reduce_collateral_for_redemption", true, reduce_args);
+            let reduce_call = CallDef::new("reduce_collateral_for_redemption_by_key", true, reduce_args);
             self.env().call_contract::<()>(branch_addr, reduce_call);
 
-            // Update remaining amounts
-            csprusd_remaining = csprusd_remaining.saturating_sub(actual_debt);
-            collateral_remaining = collateral_remaining.saturating_sub(actual_collateral);
-            vaults_touched += 1;
+            let seq = self.next_event_seq();
+            self.env().emit_event(VaultRedeemed {
+                seq,
+                owner: redemption.vault_key.owner,
+                vault_id: redemption.vault_key.id,
+                debt_redeemed: redemption.debt_redeemed,
+                collateral_sent: redemption.collateral_sent,
+                fully_redeemed: redemption.fully_redeemed,
+            });
+        }
+
+        plan
+    }
+
+    /// Plan per-vault redemptions over the first `max_iterations` vaults (0 = 10)
+    ///
+    /// Shared by `process_redemption`, `simulate_redemption` and
+    /// `quote_redemption_detailed` so all three walk vaults identically.
+    fn plan_redemption(
+        &self,
+        collateral_id: CollateralId,
+        csprusd_remaining: U256,
+        collateral_remaining: U256,
+        price: U256,
+        max_iterations: u32,
+    ) -> Vec<VaultRedemptionResult> {
+        let max_iterations = if max_iterations == 0 { 10 } else { max_iterations };
+
+        // Walk vaults in redemption order (low interest rate first)
+        let vaults = self.read_redemption_vault_entries(collateral_id, max_iterations, price);
+        let min_collateral = self.get_branch_min_collateral(collateral_id);
+        plan_vault_redemptions(
+            &vaults,
+            csprusd_remaining,
+            collateral_remaining,
+            price,
+            min_collateral,
+        )
+    }
+
+    /// Add `amount` to the decaying recent redemption volume
+    fn record_redemption_volume(&mut self, amount: U256) {
+        let decayed = self.get_recent_redemption_volume();
+        let now = self.env().get_block_time();
+        self.state.recent_redemption_volume.set(decayed + amount);
+        self.state.redemption_volume_updated_at.set(now);
+    }
+
+    /// Minimum collateral the branch requires of a vault with debt (0 = none)
+    fn get_branch_min_collateral(&self, collateral_id: CollateralId) -> U256 {
+        let branch_addr = match collateral_id {
+            CollateralId::Cspr => self.branch_cspr.get().expect("branch_cspr not set"),
+            CollateralId::SCSPR => self.branch_scspr.get().expect("branch_scspr not set"),
+        };
+        let call_def = CallDef::new("get_min_collateral", false, runtime_args! {});
+        self.env().call_contract(branch_addr, call_def)
+    }
+
+    /// Total debt of a branch
+    fn get_branch_total_debt(&self, collateral_id: CollateralId) -> U256 {
+        let branch_addr = match collateral_id {
+            CollateralId::Cspr => self.branch_cspr.get().expect("branch_cspr not set"),
+            CollateralId::SCSPR => self.branch_scspr.get().expect("branch_scspr not set"),
+        };
+        let call_def = CallDef::new("get_total_debt", false, runtime_args! {});
+        self.env().call_contract(branch_addr, call_def)
+    }
+
+    /// Read (key, debt, collateral) of the first `max_count` vaults in redemption order
+    ///
+    /// Vaults below `redemption_min_icr_bps` at `price` are dropped; those
+    /// still in their redemption cooldown are moved behind the others.
+    fn read_redemption_vault_entries(
+        &self,
+        collateral_id: CollateralId,
+        max_count: u32,
+        price: U256,
+    ) -> Vec<(VaultKey, U256, U256)> {
+        let branch_addr = match collateral_id {
+            CollateralId::Cspr => self.branch_cspr.get().expect("branch_cspr not set"),
+            CollateralId::SCSPR => self.branch_scspr.get().expect("branch_scspr not set"),
+        };
+
+        let get_sorted_args = runtime_args! {
+            "max_count" => max_count
+        };
+        let get_sorted_call = CallDef::new("get_sorted_vault_owners", false, get_sorted_args);
+        let vault_keys: Vec<VaultKey> = self.env().call_contract(branch_addr, get_sorted_call);
+
+        let mut vaults = Vec::new();
+        for vault_key in vault_keys {
+            let get_debt_args = runtime_args! {
+                "owner" => vault_key.owner,
+                "vault_id" => vault_key.id
+            };
+            let get_debt_call = CallDef::new("get_effective_debt", false, get_debt_args);
+            let vault_debt: U256 = self.env().call_contract(branch_addr, get_debt_call);
+
+            let get_coll_args = runtime_args! {
+                "owner" => vault_key.owner,
+                "vault_id" => vault_key.id
+            };
+            let get_coll_call = CallDef::new("get_effective_collateral", false, get_coll_args);
+            let vault_collateral: U256 = self.env().call_contract(branch_addr, get_coll_call);
+
+            vaults.push((vault_key, vault_debt, vault_collateral));
         }
+        let vaults = skip_risky_vaults(vaults, price, self.get_redemption_min_icr_bps());
 
-        vaults_touched
+        let now = self.env().get_block_time();
+        let cooldown = self.get_redemption_cooldown_seconds();
+        defer_cooling_vaults(vaults, |key| {
+            in_redemption_cooldown(self.state.last_redeemed_at.get(key), now, cooldown)
+        })
     }
 
     fn transfer_collateral(&mut self, collateral_id: CollateralId, recipient: Address, amount: U256) {
@@ -659,6 +1354,360 @@ impl RedemptionEngine {
 
 // ===== Helper Functions =====
 
+/// Amount redeemable from a single vault: (debt_redeemed, collateral_taken)
+///
+/// Caps at the vault's debt and collateral and at the remaining collateral,
+/// then re-derives the debt from the collateral actually taken. A partial
+/// redemption that would leave a dust position (debt with collateral under
+/// `min_collateral`) becomes a full one if the redeemer can cover the whole
+/// debt, and is skipped (zero) otherwise.
+fn redeemable_from_vault(
+    csprusd_remaining: U256,
+    collateral_remaining: U256,
+    vault_debt: U256,
+    vault_collateral: U256,
+    price: U256,
+    min_collateral: U256,
+) -> (U256, U256) {
+    let debt_to_redeem = if csprusd_remaining >= vault_debt {
+        vault_debt
+    } else {
+        csprusd_remaining
+    };
+
+    let collateral_to_take = value_to_collateral(debt_to_redeem, price);
+
+    // Cap at vault's actual collateral and at remaining collateral needed
+    let actual_collateral = collateral_to_take.min(vault_collateral).min(collateral_remaining);
+
+    // Recalculate debt based on actual collateral
+    let actual_debt = collateral_to_value(actual_collateral, price);
+
+    let leftover_debt = vault_debt.saturating_sub(actual_debt);
+    let leftover_collateral = vault_collateral - actual_collateral;
+    if is_dust_position(leftover_collateral, leftover_debt, min_collateral) {
+        if csprusd_remaining < vault_debt {
+            return (U256::zero(), U256::zero());
+        }
+        let full_collateral = value_to_collateral(vault_debt, price).min(vault_collateral);
+        if full_collateral > collateral_remaining {
+            return (U256::zero(), U256::zero());
+        }
+        return (vault_debt, full_collateral);
+    }
+
+    (actual_debt, actual_collateral)
+}
+
+/// Whether a vault last redeemed at `last_redeemed_at` is still cooling down
+fn in_redemption_cooldown(last_redeemed_at: Option<u64>, now: u64, cooldown_seconds: u64) -> bool {
+    match last_redeemed_at {
+        Some(at) if cooldown_seconds > 0 => now < at.saturating_add(cooldown_seconds),
+        _ => false,
+    }
+}
+
+/// Drop vaults whose ICR at `price` is below `min_icr_bps` (0 keeps every vault)
+///
+/// Vaults without debt are kept; the planner skips them anyway.
+fn skip_risky_vaults(vaults: Vec<(VaultKey, U256, U256)>, price: U256, min_icr_bps: u32) -> Vec<(VaultKey, U256, U256)> {
+    if min_icr_bps == 0 {
+        return vaults;
+    }
+    vaults
+        .into_iter()
+        .filter(|(_, debt, collateral)| {
+            collateral_to_value(*collateral, price) * U256::from(BPS_SCALE) >= *debt * U256::from(min_icr_bps)
+        })
+        .collect()
+}
+
+/// Move vaults for which `is_cooling` holds behind all other vaults
+///
+/// Relative order is kept within both groups, so cooling vaults are only
+/// redeemed once every eligible vault has been used up.
+fn defer_cooling_vaults<F>(vaults: Vec<(VaultKey, U256, U256)>, is_cooling: F) -> Vec<(VaultKey, U256, U256)>
+where
+    F: Fn(&VaultKey) -> bool,
+{
+    let (mut eligible, cooling): (Vec<_>, Vec<_>) =
+        vaults.into_iter().partition(|(key, _, _)| !is_cooling(key));
+    eligible.extend(cooling);
+    eligible
+}
+
+/// Plan per-vault redemptions over `(key, debt, collateral)` vaults in redemption order
+///
+/// Vaults with no debt or collateral, or nothing redeemable, are skipped.
+fn plan_vault_redemptions(
+    vaults: &[(VaultKey, U256, U256)],
+    mut csprusd_remaining: U256,
+    mut collateral_remaining: U256,
+    price: U256,
+    min_collateral: U256,
+) -> Vec<VaultRedemptionResult> {
+    let mut plan = Vec::new();
+
+    for (vault_key, vault_debt, vault_collateral) in vaults.iter() {
+        if csprusd_remaining.is_zero() || collateral_remaining.is_zero() {
+            break;
+        }
+        if vault_debt.is_zero() || vault_collateral.is_zero() {
+            continue;
+        }
+
+        let (actual_debt, actual_collateral) = redeemable_from_vault(
+            csprusd_remaining,
+            collateral_remaining,
+            *vault_debt,
+            *vault_collateral,
+            price,
+            min_collateral,
+        );
+        if actual_debt.is_zero() || actual_collateral.is_zero() {
+            continue;
+        }
+
+        csprusd_remaining = csprusd_remaining.saturating_sub(actual_debt);
+        collateral_remaining = collateral_remaining.saturating_sub(actual_collateral);
+        let below_ccr = collateral_to_value(*vault_collateral, price) * U256::from(BPS_SCALE)
+            < *vault_debt * U256::from(CCR_BPS);
+        plan.push(VaultRedemptionResult {
+            vault_key: *vault_key,
+            debt_redeemed: actual_debt,
+            collateral_sent: actual_collateral,
+            fully_redeemed: actual_debt >= *vault_debt,
+            below_ccr,
+        });
+    }
+
+    plan
+}
+
+/// Quote a redemption of `csprusd_amount` from its vault plan
+///
+/// The fee is charged on the collateral the plan takes and rebated for its
+/// sub-CCR debt, as in `redeem`; whatever the plan doesn't fill is reported
+/// as `gusd_unfilled`.
+fn quote_from_plan(plan: &[VaultRedemptionResult], csprusd_amount: U256, fee_bps: u32, rebate_bps: u32) -> RedemptionQuote {
+    let (gusd_filled, collateral_filled) = plan_totals(plan);
+    let fee = risk_rebated_fee(redemption_fee(collateral_filled, fee_bps), rebate_bps, sub_ccr_debt(plan), gusd_filled);
+    RedemptionQuote {
+        collateral_out: collateral_filled - fee,
+        fee,
+        vaults_touched: plan.len() as u32,
+        gusd_unfilled: csprusd_amount.saturating_sub(gusd_filled),
+    }
+}
+
+/// Total (debt redeemed, collateral sent) over a redemption plan
+fn plan_totals(plan: &[VaultRedemptionResult]) -> (U256, U256) {
+    plan.iter().fold((U256::zero(), U256::zero()), |(debt, collateral), redemption| {
+        (debt + redemption.debt_redeemed, collateral + redemption.collateral_sent)
+    })
+}
+
+/// Decay `volume` over `elapsed` seconds with the given half-life
+///
+/// Halves once per whole half-life and interpolates linearly within the
+/// last one, which stays within a few percent of true exponential decay.
+fn decay_volume(volume: U256, elapsed: u64, half_life: u64) -> U256 {
+    if half_life == 0 {
+        return U256::zero();
+    }
+    let halvings = elapsed / half_life;
+    if halvings >= 256 {
+        return U256::zero();
+    }
+    let halved = volume >> (halvings as usize);
+    let remainder = elapsed % half_life;
+    halved - halved * U256::from(remainder) / U256::from(2 * half_life)
+}
+
+/// Suggested borrower rate floor: base fee + (volume / supply) / 2, in bps
+///
+/// Capped at MAX_RATE_FLOOR_BPS; just the base fee when there is no supply.
+fn redemption_rate_floor_bps(base_fee_bps: u32, recent_volume: U256, gusd_supply: U256) -> u32 {
+    if gusd_supply.is_zero() {
+        return base_fee_bps.min(MAX_RATE_FLOOR_BPS);
+    }
+    let pressure_bps = recent_volume * U256::from(BPS_SCALE) / gusd_supply / U256::from(2u32);
+    let floor = U256::from(base_fee_bps) + pressure_bps;
+    if floor > U256::from(MAX_RATE_FLOOR_BPS) {
+        MAX_RATE_FLOOR_BPS
+    } else {
+        floor.low_u32()
+    }
+}
+
+/// Whether `amount` exceeds `fraction_bps` of the branch's total debt (0 = unlimited)
+fn exceeds_redemption_fraction(amount: U256, branch_total_debt: U256, fraction_bps: u32) -> bool {
+    if fraction_bps == 0 {
+        return false;
+    }
+    amount * U256::from(BPS_SCALE) > branch_total_debt * U256::from(fraction_bps)
+}
+
+/// Collateral fee on a redemption, rounded up (a charge)
+fn redemption_fee(collateral: U256, fee_bps: u32) -> U256 {
+    mul_div_ceil(collateral, U256::from(fee_bps), U256::from(BPS_SCALE))
+}
+
+/// Debt redeemed from vaults that were below CCR
+fn sub_ccr_debt(plan: &[VaultRedemptionResult]) -> U256 {
+    plan.iter()
+        .filter(|redemption| redemption.below_ccr)
+        .fold(U256::zero(), |total, redemption| total + redemption.debt_redeemed)
+}
+
+/// Reduce a collateral fee by `rebate_bps` of itself, scaled by the share of
+/// `redeemed` debt that came from sub-CCR vaults
+fn risk_rebated_fee(fee: U256, rebate_bps: u32, sub_ccr_debt: U256, redeemed: U256) -> U256 {
+    if rebate_bps == 0 || redeemed.is_zero() {
+        return fee;
+    }
+    let risky = sub_ccr_debt.min(redeemed);
+    let rebate = mul_div_floor(fee * U256::from(rebate_bps), risky, U256::from(BPS_SCALE) * redeemed);
+    fee - rebate
+}
+
+/// Add `amount` to a redeemer's (redeemed, window start) record at `now`
+///
+/// A new window starts once `window_seconds` have passed since the last one
+/// began. Errors if the window total would exceed `max_per_window`; a zero
+/// max disables the limit.
+fn record_rate_limited(
+    record: (U256, u64),
+    amount: U256,
+    now: u64,
+    max_per_window: U256,
+    window_seconds: u64,
+) -> Result<(U256, u64), CdpError> {
+    if max_per_window.is_zero() {
+        return Ok(record);
+    }
+    let (redeemed, started_at) = record;
+    let (redeemed, started_at) = if now >= started_at.saturating_add(window_seconds) {
+        (U256::zero(), now)
+    } else {
+        (redeemed, started_at)
+    };
+    let total = redeemed.saturating_add(amount);
+    if total > max_per_window {
+        return Err(CdpError::RedemptionRateLimited);
+    }
+    Ok((total, started_at))
+}
+
+/// Validate a redemption price and the stCSPR rate it was composed with
+fn check_redemption_price(price: U256, scspr_rate: Option<U256>) -> Result<(), CdpError> {
+    if scspr_rate.is_some_and(|rate| rate.is_zero()) {
+        return Err(CdpError::OracleInvalidRate);
+    }
+    if price.is_zero() {
+        return Err(CdpError::OraclePriceZero);
+    }
+    Ok(())
+}
+
+/// gUSD to redeem so that at least `collateral_out` is paid after the fee
+///
+/// Inverts `value_to_collateral` and `redemption_fee`, rounding each step
+/// up, so the realized payout overshoots by at most rounding dust.
+fn gusd_for_collateral_out(collateral_out: U256, price: U256, fee_bps: u32) -> U256 {
+    if fee_bps >= BPS_SCALE {
+        return U256::MAX;
+    }
+    let collateral_before_fee = mul_div_ceil(
+        collateral_out,
+        U256::from(BPS_SCALE),
+        U256::from(BPS_SCALE - fee_bps),
+    );
+    mul_div_ceil(collateral_before_fee, price, U256::from(COLLATERAL_SCALE))
+}
+
+/// Split a collateral fee into (SP share, treasury share) by `sp_share_bps`
+fn split_redemption_fee(fee: U256, sp_share_bps: u32) -> (U256, U256) {
+    let sp_share = fee * U256::from(sp_share_bps) / U256::from(BPS_SCALE);
+    (sp_share, fee - sp_share)
+}
+
+/// Reject a redemption whose current fee is above the caller's maximum
+fn check_max_fee(current_fee_bps: u32, max_fee_bps: u32) -> Result<(), CdpError> {
+    if current_fee_bps > max_fee_bps {
+        Err(CdpError::FeeExceedsMax)
+    } else {
+        Ok(())
+    }
+}
+
+/// Reject a redemption paying out less collateral than the caller's minimum
+fn check_min_out(received: U256, min_out: U256) -> Result<(), CdpError> {
+    if received < min_out {
+        Err(CdpError::SlippageExceeded)
+    } else {
+        Ok(())
+    }
+}
+
+/// One branch's side of a cross-branch redemption walk
+struct BranchWalk {
+    /// (rate bps, debt, collateral) of each vault in redemption order
+    vaults: Vec<(u32, U256, U256)>,
+    /// Collateral price
+    price: U256,
+    /// Branch minimum collateral (dust threshold)
+    min_collateral: U256,
+}
+
+/// gUSD to redeem from the CSPR and stCSPR branches for `csprusd_amount`
+///
+/// Merge-walks both redemption-ordered lists, always taking the vault with the
+/// lower interest rate next (ties go to CSPR), for at most `max_vaults` vaults.
+/// Each vault is sized as a single-branch walk would size it.
+fn merge_redemption_fills(cspr: &BranchWalk, scspr: &BranchWalk, csprusd_amount: U256, max_vaults: u32) -> (U256, U256) {
+    let (mut next_cspr, mut next_scspr) = (0usize, 0usize);
+    let (mut cspr_fill, mut scspr_fill) = (U256::zero(), U256::zero());
+    let mut remaining = csprusd_amount;
+    let mut walked = 0u32;
+
+    while !remaining.is_zero() && walked < max_vaults {
+        let take_cspr = match (cspr.vaults.get(next_cspr), scspr.vaults.get(next_scspr)) {
+            (Some(a), Some(b)) => a.0 <= b.0,
+            (Some(_), None) => true,
+            (None, Some(_)) => false,
+            (None, None) => break,
+        };
+        let (walk, next) = if take_cspr {
+            (cspr, &mut next_cspr)
+        } else {
+            (scspr, &mut next_scspr)
+        };
+        let (_, vault_debt, vault_collateral) = walk.vaults[*next];
+        *next += 1;
+        walked += 1;
+        if vault_debt.is_zero() || vault_collateral.is_zero() {
+            continue;
+        }
+
+        let (actual_debt, _) = redeemable_from_vault(
+            remaining,
+            value_to_collateral(remaining, walk.price),
+            vault_debt,
+            vault_collateral,
+            walk.price,
+            walk.min_collateral,
+        );
+        remaining = remaining.saturating_sub(actual_debt);
+        if take_cspr {
+            cspr_fill += actual_debt;
+        } else {
+            scspr_fill += actual_debt;
+        }
+    }
+    (cspr_fill, scspr_fill)
+}
+
 /// Convert U256 to U512
 fn u256_to_u512(value: U256) -> U512 {
     let mut bytes = [0u8; 32];
@@ -669,6 +1718,7 @@ fn u256_to_u512(value: U256) -> U512 {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::math::{COLLATERAL_SCALE, PRICE_SCALE};
 
     #[test]
     fn test_min_redemption_constant() {
@@ -689,9 +1739,9 @@ mod tests {
         let csprusd = U256::from(100u64) * U256::from(PRICE_SCALE); // 100e18
         let price = U256::from(2u64) * U256::from(PRICE_SCALE);     // 2e18
 
-        // collateral = csprusd * COLLATERAL_DECIMALS / price
-        let collateral = csprusd * U256::from(COLLATERAL_DECIMALS) / price;
-        let expected = U256::from(50u64) * U256::from(COLLATERAL_DECIMALS); // 50e9
+        // collateral = csprusd * COLLATERAL_SCALE / price
+        let collateral = csprusd * U256::from(COLLATERAL_SCALE) / price;
+        let expected = U256::from(50u64) * U256::from(COLLATERAL_SCALE); // 50e9
         assert_eq!(collateral, expected);
     }
 
@@ -699,11 +1749,11 @@ mod tests {
     fn test_fee_calculation() {
         // Collateral = 100 (9 decimals), fee = 0.5% (50 bps)
         // Expected fee = 100 * 50 / 10000 = 0.5
-        let collateral = U256::from(100u64) * U256::from(COLLATERAL_DECIMALS); // 100e9
+        let collateral = U256::from(100u64) * U256::from(COLLATERAL_SCALE); // 100e9
         let fee_bps = BASE_REDEMPTION_FEE_BPS;
 
         let fee = collateral * U256::from(fee_bps) / U256::from(BPS_SCALE);
-        let expected = U256::from(COLLATERAL_DECIMALS) / U256::from(2u64); // 0.5e9
+        let expected = U256::from(COLLATERAL_SCALE) / U256::from(2u64); // 0.5e9
         assert_eq!(fee, expected);
     }
 
@@ -715,6 +1765,67 @@ mod tests {
         assert_eq!(hint.max_iterations, 0);
     }
 
+    /// Quote redeeming `amount` over `(debt, collateral)` vaults in redemption order
+    fn quote_walk(vaults: &[(U256, U256)], amount: U256, price: U256, fee_bps: u32, min_collateral: U256) -> RedemptionQuote {
+        use odra::casper_types::account::AccountHash;
+
+        let keyed: Vec<_> = vaults
+            .iter()
+            .enumerate()
+            .map(|(i, (debt, collateral))| {
+                (VaultKey { owner: Address::Account(AccountHash::default()), id: i as u64 }, *debt, *collateral)
+            })
+            .collect();
+        let plan = plan_vault_redemptions(&keyed, amount, value_to_collateral(amount, price), price, min_collateral);
+        quote_from_plan(&plan, amount, fee_bps, 0)
+    }
+
+    #[test]
+    fn test_quote_fills_across_vaults() {
+        let price = U256::from(2u64) * U256::from(PRICE_SCALE);
+        let gusd = |n: u64| U256::from(n) * U256::from(PRICE_SCALE);
+        let coll = |n: u64| U256::from(n) * U256::from(COLLATERAL_SCALE);
+
+        // Two vaults with 60 + 100 gUSD debt, redeem 100 gUSD at $2
+        let vaults = [(gusd(60), coll(100)), (gusd(100), coll(100))];
+        let quote = quote_walk(&vaults, gusd(100), price, BASE_REDEMPTION_FEE_BPS, U256::zero());
+
+        assert_eq!(quote.vaults_touched, 2);
+        assert!(quote.gusd_unfilled.is_zero());
+        // 50 collateral filled, 0.5% fee
+        let fee = coll(50) * U256::from(BASE_REDEMPTION_FEE_BPS) / U256::from(BPS_SCALE);
+        assert_eq!(quote.fee, fee);
+        assert_eq!(quote.collateral_out, coll(50) - fee);
+    }
+
+    #[test]
+    fn test_quote_reports_unfilled_when_branch_debt_short() {
+        let price = U256::from(2u64) * U256::from(PRICE_SCALE);
+        let gusd = |n: u64| U256::from(n) * U256::from(PRICE_SCALE);
+        let coll = |n: u64| U256::from(n) * U256::from(COLLATERAL_SCALE);
+
+        // Branch only has 30 gUSD of debt but 100 gUSD is requested
+        let vaults = [(gusd(10), coll(50)), (gusd(20), coll(50))];
+        let quote = quote_walk(&vaults, gusd(100), price, 0, U256::zero());
+
+        assert_eq!(quote.vaults_touched, 2);
+        assert_eq!(quote.gusd_unfilled, gusd(70));
+        assert_eq!(quote.collateral_out, coll(15));
+        assert!(quote.fee.is_zero());
+    }
+
+    #[test]
+    fn test_quote_skips_empty_vaults() {
+        let price = U256::from(PRICE_SCALE);
+        let vaults = [(U256::zero(), U256::zero())];
+        let amount = U256::from(5u64) * U256::from(PRICE_SCALE);
+        let quote = quote_walk(&vaults, amount, price, 0, U256::zero());
+
+        assert_eq!(quote.vaults_touched, 0);
+        assert_eq!(quote.gusd_unfilled, amount);
+        assert!(quote.collateral_out.is_zero());
+    }
+
     #[test]
     fn test_fee_bounds() {
         // Base fee should be less than max fee
@@ -723,4 +1834,439 @@ mod tests {
         // Max fee should be reasonable (not more than 10%)
         assert!(MAX_REDEMPTION_FEE_BPS <= 1000);
     }
+
+    #[test]
+    fn test_best_redemption_merges_branches_by_rate() {
+        let gusd = |n: u64| U256::from(n) * U256::from(PRICE_SCALE);
+        let coll = |n: u64| U256::from(n) * U256::from(COLLATERAL_SCALE);
+        let walk = |vaults: Vec<(u32, U256, U256)>| BranchWalk {
+            vaults,
+            price: U256::from(PRICE_SCALE),
+            min_collateral: U256::zero(),
+        };
+
+        // CSPR has the cheapest head but its second vault costs more than stCSPR's
+        let cspr = walk(vec![(300, gusd(100), coll(200)), (900, gusd(200), coll(400))]);
+        let scspr = walk(vec![(500, gusd(150), coll(300)), (600, gusd(1000), coll(2000))]);
+
+        // 300 (CSPR) -> 500 -> 600 (stCSPR); the 900 vault is never reached
+        assert_eq!(merge_redemption_fills(&cspr, &scspr, gusd(400), 10), (gusd(100), gusd(300)));
+        // Equal rates go to CSPR first
+        let tied = walk(vec![(300, gusd(1000), coll(2000))]);
+        assert_eq!(merge_redemption_fills(&cspr, &tied, gusd(50), 10), (gusd(50), U256::zero()));
+        // The vault budget covers both lists together
+        assert_eq!(merge_redemption_fills(&cspr, &scspr, gusd(400), 2), (gusd(100), gusd(150)));
+        // An empty branch leaves everything to the other
+        let empty = walk(Vec::new());
+        assert_eq!(merge_redemption_fills(&empty, &scspr, gusd(400), 10), (U256::zero(), gusd(400)));
+    }
+
+    #[test]
+    fn test_multi_vault_redemption_emits_one_event_per_touched_vault() {
+        use odra::casper_types::account::AccountHash;
+
+        let gusd = |n: u64| U256::from(n) * U256::from(PRICE_SCALE);
+        let coll = |n: u64| U256::from(n) * U256::from(COLLATERAL_SCALE);
+        let key = |id: u64| VaultKey { owner: Address::Account(AccountHash::default()), id };
+        let price = U256::from(PRICE_SCALE);
+
+        // Redeem 250 gUSD across 100 + 200 + 300 gUSD vaults; an empty vault is skipped
+        let vaults = [
+            (key(1), gusd(100), coll(200)),
+            (key(2), U256::zero(), U256::zero()),
+            (key(3), gusd(200), coll(400)),
+            (key(4), gusd(300), coll(600)),
+        ];
+        let amount = gusd(250);
+        let plan = plan_vault_redemptions(&vaults, amount, amount * U256::from(COLLATERAL_SCALE) / price, price, U256::zero());
+
+        // One VaultRedeemed per touched vault, matching the single Redeemed's vaults_touched
+        assert_eq!(plan.len(), 2);
+        let quote = quote_from_plan(&plan, amount, BASE_REDEMPTION_FEE_BPS, 0);
+        assert_eq!(quote.vaults_touched, plan.len() as u32);
+
+        assert_eq!(plan[0].vault_key.id, 1);
+        assert_eq!(plan[0].debt_redeemed, gusd(100));
+        assert!(plan[0].fully_redeemed);
+        assert_eq!(plan[1].vault_key.id, 3);
+        assert_eq!(plan[1].debt_redeemed, gusd(150));
+        assert!(!plan[1].fully_redeemed);
+    }
+
+    #[test]
+    fn test_redemption_cooldown_window() {
+        // Never redeemed, or cooldown disabled: always eligible
+        assert!(!in_redemption_cooldown(None, 1_000, 3_600));
+        assert!(!in_redemption_cooldown(Some(1_000), 1_000, 0));
+
+        // Redeemed at t=1000 with a 1h cooldown
+        assert!(in_redemption_cooldown(Some(1_000), 1_000, 3_600));
+        assert!(in_redemption_cooldown(Some(1_000), 4_599, 3_600));
+        assert!(!in_redemption_cooldown(Some(1_000), 4_600, 3_600));
+    }
+
+    #[test]
+    fn test_redeemed_vault_skipped_within_cooldown() {
+        use odra::casper_types::account::AccountHash;
+
+        let gusd = |n: u64| U256::from(n) * U256::from(PRICE_SCALE);
+        let coll = |n: u64| U256::from(n) * U256::from(COLLATERAL_SCALE);
+        let key = |id: u64| VaultKey { owner: Address::Account(AccountHash::default()), id };
+        let price = U256::from(PRICE_SCALE);
+        let vaults = vec![(key(1), gusd(100), coll(200)), (key(2), gusd(100), coll(200))];
+        let amount = gusd(50);
+        let collateral = amount * U256::from(COLLATERAL_SCALE) / price;
+
+        // Vault 1 (lowest rate) was redeemed at t=1000; cooldown is 1h
+        let last = |k: &VaultKey| if k.id == 1 { Some(1_000) } else { None };
+
+        // Within the cooldown vault 2 is redeemed instead
+        let ordered = defer_cooling_vaults(vaults.clone(), |k| in_redemption_cooldown(last(k), 2_000, 3_600));
+        let plan = plan_vault_redemptions(&ordered, amount, collateral, price, U256::zero());
+        assert_eq!(plan.len(), 1);
+        assert_eq!(plan[0].vault_key.id, 2);
+
+        // After the cooldown vault 1 is first again
+        let ordered = defer_cooling_vaults(vaults.clone(), |k| in_redemption_cooldown(last(k), 4_600, 3_600));
+        let plan = plan_vault_redemptions(&ordered, amount, collateral, price, U256::zero());
+        assert_eq!(plan[0].vault_key.id, 1);
+
+        // A cooling vault is still used when nothing else is available
+        let only_cooling = vec![(key(1), gusd(100), coll(200))];
+        let ordered = defer_cooling_vaults(only_cooling, |k| in_redemption_cooldown(last(k), 2_000, 3_600));
+        let plan = plan_vault_redemptions(&ordered, amount, collateral, price, U256::zero());
+        assert_eq!(plan[0].vault_key.id, 1);
+    }
+
+    #[test]
+    fn test_dust_leaving_redemption_empties_vault() {
+        use odra::casper_types::account::AccountHash;
+
+        let gusd = |n: u64| U256::from(n) * U256::from(PRICE_SCALE);
+        let coll = |n: u64| U256::from(n) * U256::from(COLLATERAL_SCALE);
+        let key = |id: u64| VaultKey { owner: Address::Account(AccountHash::default()), id };
+        let price = U256::from(PRICE_SCALE);
+        let min_collateral = coll(5);
+
+        // 100 gUSD against 99 CSPR: taking all collateral would leave 1 gUSD of
+        // debt with no collateral, so the vault is redeemed in full instead
+        let vaults = [(key(1), gusd(100), coll(99))];
+        let amount = gusd(150);
+        let collateral = amount * U256::from(COLLATERAL_SCALE) / price;
+        let plan = plan_vault_redemptions(&vaults, amount, collateral, price, min_collateral);
+        assert_eq!(plan.len(), 1);
+        assert_eq!(plan[0].debt_redeemed, gusd(100));
+        assert_eq!(plan[0].collateral_sent, coll(99));
+        assert!(plan[0].fully_redeemed);
+
+        // Without the floor the old partial redemption would leave dust behind
+        let plan = plan_vault_redemptions(&vaults, amount, collateral, price, U256::zero());
+        assert_eq!(plan[0].debt_redeemed, gusd(99));
+        assert!(!plan[0].fully_redeemed);
+    }
+
+    #[test]
+    fn test_dust_leaving_partial_redemption_skips_vault() {
+        use odra::casper_types::account::AccountHash;
+
+        let gusd = |n: u64| U256::from(n) * U256::from(PRICE_SCALE);
+        let coll = |n: u64| U256::from(n) * U256::from(COLLATERAL_SCALE);
+        let key = |id: u64| VaultKey { owner: Address::Account(AccountHash::default()), id };
+        let price = U256::from(PRICE_SCALE);
+
+        // 98 gUSD against vault 1 would leave 2 gUSD with 3 CSPR (< 5 floor) and
+        // the redeemer cannot cover the full 100, so vault 2 is used instead
+        let vaults = [(key(1), gusd(100), coll(101)), (key(2), gusd(200), coll(400))];
+        let amount = gusd(98);
+        let collateral = amount * U256::from(COLLATERAL_SCALE) / price;
+        let plan = plan_vault_redemptions(&vaults, amount, collateral, price, coll(5));
+        assert_eq!(plan.len(), 1);
+        assert_eq!(plan[0].vault_key.id, 2);
+
+        let quote = quote_from_plan(&plan, amount, 0, 0);
+        assert_eq!(quote.collateral_out, coll(98));
+        assert_eq!(quote.vaults_touched, 1);
+    }
+
+    #[test]
+    fn test_simulated_redemption_matches_executed_redeem() {
+        use odra::casper_types::account::AccountHash;
+
+        let gusd = |n: u64| U256::from(n) * U256::from(PRICE_SCALE);
+        let coll = |n: u64| U256::from(n) * U256::from(COLLATERAL_SCALE);
+        let key = |id: u64| VaultKey { owner: Address::Account(AccountHash::default()), id };
+        let price = U256::from(PRICE_SCALE) / U256::from(2u64); // $0.50
+
+        let mut vaults = vec![
+            (key(1), gusd(100), coll(400)),
+            (key(2), gusd(200), coll(600)),
+            (key(3), gusd(300), coll(900)),
+        ];
+        let original = vaults.clone();
+        let amount = gusd(250);
+        let collateral_before_fee = value_to_collateral(amount, price);
+
+        // simulate_redemption: plan against current state
+        let simulated = plan_vault_redemptions(&vaults, amount, collateral_before_fee, price, U256::zero());
+
+        // redeem: the same plan is applied vault by vault via reduce_collateral_for_redemption_by_key
+        let executed = plan_vault_redemptions(&vaults, amount, collateral_before_fee, price, U256::zero());
+        for hit in executed.iter() {
+            let vault = vaults.iter_mut().find(|(k, _, _)| *k == hit.vault_key).unwrap();
+            vault.1 -= hit.debt_redeemed;
+            vault.2 -= hit.collateral_sent;
+        }
+
+        assert_eq!(simulated.len(), executed.len());
+        for (sim, exe) in simulated.iter().zip(executed.iter()) {
+            assert_eq!(sim.vault_key, exe.vault_key);
+            assert_eq!(sim.debt_redeemed, exe.debt_redeemed);
+            assert_eq!(sim.collateral_sent, exe.collateral_sent);
+            assert_eq!(sim.fully_redeemed, exe.fully_redeemed);
+        }
+
+        // Post-redeem vault state reflects exactly the simulated hits
+        assert_eq!(simulated[0].vault_key.id, 1);
+        assert!(simulated[0].fully_redeemed);
+        assert_eq!((vaults[0].1, vaults[0].2), (U256::zero(), coll(200)));
+        assert_eq!(simulated[1].debt_redeemed, gusd(150));
+        assert_eq!((vaults[1].1, vaults[1].2), (gusd(50), coll(300)));
+        assert_eq!(vaults[2], (key(3), gusd(300), coll(900)));
+
+        // The hits add up to the Redeemed totals the aggregate quote reports
+        let quote = quote_walk(
+            &original.iter().map(|(_, d, c)| (*d, *c)).collect::<Vec<_>>(),
+            amount,
+            price,
+            0,
+            U256::zero(),
+        );
+        let sent: U256 = simulated.iter().fold(U256::zero(), |acc, hit| acc + hit.collateral_sent);
+        assert_eq!(quote.vaults_touched, simulated.len() as u32);
+        assert_eq!(quote.collateral_out, sent);
+        assert_eq!(sent, collateral_before_fee);
+    }
+
+    #[test]
+    fn test_redemption_protection_errors() {
+        assert_eq!(check_max_fee(60, 50), Err(CdpError::FeeExceedsMax));
+        assert_eq!(check_max_fee(50, 50), Ok(()));
+
+        let received = U256::from(990u64);
+        assert_eq!(check_min_out(received, U256::from(1_000u64)), Err(CdpError::SlippageExceeded));
+        assert_eq!(check_min_out(received, received), Ok(()));
+    }
+
+    #[test]
+    fn test_redemption_volume_raises_rate_floor() {
+        let gusd = |n: u64| U256::from(n) * U256::from(PRICE_SCALE);
+        let supply = gusd(1_000_000);
+
+        // No recent redemptions: floor is the base fee
+        assert_eq!(redemption_rate_floor_bps(BASE_REDEMPTION_FEE_BPS, U256::zero(), supply), 50);
+
+        // 1% of supply redeemed adds 50 bps, 4% adds 200 bps
+        assert_eq!(redemption_rate_floor_bps(BASE_REDEMPTION_FEE_BPS, gusd(10_000), supply), 100);
+        assert_eq!(redemption_rate_floor_bps(BASE_REDEMPTION_FEE_BPS, gusd(40_000), supply), 250);
+
+        // Capped at the max interest rate
+        assert_eq!(redemption_rate_floor_bps(BASE_REDEMPTION_FEE_BPS, supply, supply), MAX_RATE_FLOOR_BPS);
+    }
+
+    #[test]
+    fn test_redemption_volume_decays() {
+        let half_life = REDEMPTION_VOLUME_HALF_LIFE_SECONDS;
+        let volume = U256::from(1_000_000u64);
+
+        assert_eq!(decay_volume(volume, 0, half_life), volume);
+        assert_eq!(decay_volume(volume, half_life, half_life), U256::from(500_000u64));
+        assert_eq!(decay_volume(volume, 2 * half_life, half_life), U256::from(250_000u64));
+        // Half-way through a half-life: linear 25% cut
+        assert_eq!(decay_volume(volume, half_life / 2, half_life), U256::from(750_000u64));
+        assert!(decay_volume(volume, 300 * half_life, half_life).is_zero());
+
+        // As volume decays, the suggested floor falls back towards the base fee
+        let supply = U256::from(100_000_000u64);
+        let fresh = redemption_rate_floor_bps(BASE_REDEMPTION_FEE_BPS, volume, supply);
+        let later = redemption_rate_floor_bps(BASE_REDEMPTION_FEE_BPS, decay_volume(volume, half_life, half_life), supply);
+        assert!(later < fresh);
+    }
+
+    #[test]
+    fn test_max_redemption_fraction_boundary() {
+        let gusd = |n: u64| U256::from(n) * U256::from(PRICE_SCALE);
+        let branch_debt = gusd(1_000_000);
+
+        // 10% cap: exactly 100k passes, one wei more is too large
+        assert!(!exceeds_redemption_fraction(gusd(100_000), branch_debt, 1000));
+        assert!(exceeds_redemption_fraction(gusd(100_000) + U256::one(), branch_debt, 1000));
+
+        // Default (0) is unlimited
+        assert!(!exceeds_redemption_fraction(branch_debt * U256::from(2u64), branch_debt, 0));
+
+        // 100% allows the whole branch debt
+        assert!(!exceeds_redemption_fraction(branch_debt, branch_debt, BPS_SCALE));
+    }
+
+    #[test]
+    fn test_redemption_fee_split_between_sp_and_treasury() {
+        let fee = U256::from(1_000_000_007u64);
+
+        // Default: the whole fee goes to the treasury
+        assert_eq!(split_redemption_fee(fee, 0), (U256::zero(), fee));
+
+        // 30% to SP depositors; rounding dust stays with the treasury
+        let (sp_share, treasury_share) = split_redemption_fee(fee, 3000);
+        assert_eq!(sp_share, U256::from(300_000_002u64));
+        assert_eq!(sp_share + treasury_share, fee);
+
+        // 100% routes everything to the pool
+        assert_eq!(split_redemption_fee(fee, BPS_SCALE), (fee, U256::zero()));
+    }
+
+    #[test]
+    fn test_risk_rebate_lowers_fee_for_sub_ccr_vaults() {
+        use odra::casper_types::account::AccountHash;
+
+        let price = U256::from(PRICE_SCALE);
+        let gusd = |n: u64| U256::from(n) * U256::from(PRICE_SCALE);
+        let coll = |n: u64| U256::from(n) * U256::from(COLLATERAL_SCALE);
+        let key = VaultKey { owner: Address::Account(AccountHash::default()), id: 1 };
+        let amount = gusd(100);
+        let collateral = coll(100);
+        let fee = collateral * U256::from(BASE_REDEMPTION_FEE_BPS) / U256::from(BPS_SCALE);
+
+        // 1000 gUSD against 1200 CSPR at $1 is 120% ICR, below CCR
+        let plan = plan_vault_redemptions(&[(key, gusd(1000), coll(1200))], amount, collateral, price, U256::zero());
+        assert!(plan[0].below_ccr);
+        let risky_fee = risk_rebated_fee(fee, 5000, sub_ccr_debt(&plan), amount);
+        assert_eq!(quote_from_plan(&plan, amount, BASE_REDEMPTION_FEE_BPS, 5000).fee, risky_fee);
+
+        // 1000 gUSD against 2000 CSPR is 200% ICR, no rebate
+        let plan = plan_vault_redemptions(&[(key, gusd(1000), coll(2000))], amount, collateral, price, U256::zero());
+        assert!(!plan[0].below_ccr);
+        let healthy_fee = risk_rebated_fee(fee, 5000, sub_ccr_debt(&plan), amount);
+
+        assert_eq!(healthy_fee, fee);
+        assert_eq!(risky_fee, fee / U256::from(2u64));
+        assert!(risky_fee < healthy_fee);
+
+        // Half of the debt from a risky vault earns half the rebate
+        let half_risky = risk_rebated_fee(fee, 5000, amount / U256::from(2u64), amount);
+        assert_eq!(half_risky, fee * U256::from(3u64) / U256::from(4u64));
+        // No rebate configured, none given
+        assert_eq!(risk_rebated_fee(fee, 0, amount, amount), fee);
+    }
+
+    #[test]
+    fn test_redeem_for_collateral_pays_requested_amount() {
+        let coll = |n: u64| U256::from(n) * U256::from(COLLATERAL_SCALE);
+        let prices = [
+            U256::from(PRICE_SCALE / 20), // $0.05
+            U256::from(PRICE_SCALE / 3), // $0.333...
+            U256::from(1_234u64) * U256::from(PRICE_SCALE / 1_000), // $1.234
+        ];
+        let targets = [U256::from(1u64), coll(1), coll(37) + U256::from(123_456u64), coll(1_000_000)];
+
+        for price in prices {
+            for fee_bps in [0u32, 50, 333, 500] {
+                for target in targets {
+                    // Same forward path as redeem_from_branch
+                    let gusd_in = gusd_for_collateral_out(target, price, fee_bps);
+                    let before_fee = value_to_collateral(gusd_in, price);
+                    let out = before_fee - redemption_fee(before_fee, fee_bps);
+
+                    // Never short, and over by at most rounding dust
+                    assert!(out >= target);
+                    assert!(out - target <= U256::one());
+
+                    // One gUSD-wei less would fall short (or hit the same floor)
+                    let before_fee = value_to_collateral(gusd_in - U256::one(), price);
+                    let out = before_fee - redemption_fee(before_fee, fee_bps);
+                    assert!(out <= target);
+                }
+            }
+        }
+
+        // A 100% fee can never pay out anything
+        assert_eq!(gusd_for_collateral_out(coll(1), prices[0], 10_000), U256::MAX);
+    }
+
+    #[test]
+    fn test_zero_price_and_zero_rate_revert_cleanly() {
+        let price = U256::from(PRICE_SCALE / 20);
+        let rate = U256::from(PRICE_SCALE);
+
+        assert_eq!(check_redemption_price(price, None), Ok(()));
+        assert_eq!(check_redemption_price(price, Some(rate)), Ok(()));
+
+        // Misconfigured feed
+        assert_eq!(check_redemption_price(U256::zero(), None), Err(CdpError::OraclePriceZero));
+
+        // A zero rate is reported as such, not as the zero composite price it yields
+        let composite = price * U256::zero() / rate;
+        assert_eq!(check_redemption_price(composite, Some(U256::zero())), Err(CdpError::OracleInvalidRate));
+    }
+
+    #[test]
+    fn test_redemption_rate_limit_per_address() {
+        let gusd = |n: u64| U256::from(n) * U256::from(PRICE_SCALE);
+        let max = gusd(1000);
+        let window = 3600u64;
+        let start = 1_000_000u64;
+
+        // Rapid redemptions by one address hit the limit within the window
+        let record = record_rate_limited((U256::zero(), 0), gusd(600), start, max, window).unwrap();
+        assert_eq!(record, (gusd(600), start));
+        let record = record_rate_limited(record, gusd(400), start + 60, max, window).unwrap();
+        assert_eq!(record, (gusd(1000), start));
+        assert_eq!(
+            record_rate_limited(record, U256::one(), start + 120, max, window),
+            Err(CdpError::RedemptionRateLimited)
+        );
+
+        // Spaced out past the window, the same redemptions pass
+        let spaced = record_rate_limited(record, gusd(600), start + window, max, window).unwrap();
+        assert_eq!(spaced, (gusd(600), start + window));
+        let spaced = record_rate_limited(spaced, gusd(600), start + 2 * window, max, window).unwrap();
+        assert_eq!(spaced, (gusd(600), start + 2 * window));
+
+        // A single redemption above the cap never fits
+        assert_eq!(
+            record_rate_limited((U256::zero(), 0), max + U256::one(), start, max, window),
+            Err(CdpError::RedemptionRateLimited)
+        );
+
+        // Disabled by default: nothing is tracked or limited
+        assert_eq!(record_rate_limited((U256::zero(), 0), gusd(1_000_000), start, U256::zero(), 0), Ok((U256::zero(), 0)));
+    }
+
+    #[test]
+    fn test_vault_between_mcr_and_buffer_is_skipped() {
+        use odra::casper_types::account::AccountHash;
+
+        let gusd = |n: u64| U256::from(n) * U256::from(PRICE_SCALE);
+        let coll = |n: u64| U256::from(n) * U256::from(COLLATERAL_SCALE);
+        let key = |id: u64| VaultKey { owner: Address::Account(AccountHash::default()), id };
+        let price = U256::from(PRICE_SCALE);
+
+        // Lowest-rate vault at 115% ICR (above MCR, inside a 130% buffer), then one at 200%
+        let vaults = vec![(key(1), gusd(100), coll(115)), (key(2), gusd(100), coll(200))];
+        let amount = gusd(50);
+        let collateral = amount * U256::from(COLLATERAL_SCALE) / price;
+
+        // With the buffer the risky vault is skipped and the healthy one redeemed
+        let eligible = skip_risky_vaults(vaults.clone(), price, 13_000);
+        assert_eq!(eligible.len(), 1);
+        let plan = plan_vault_redemptions(&eligible, amount, collateral, price, U256::zero());
+        assert_eq!(plan.len(), 1);
+        assert_eq!(plan[0].vault_key.id, 2);
+
+        // Exactly at the buffer still qualifies
+        assert_eq!(skip_risky_vaults(vaults.clone(), price, 11_500).len(), 2);
+
+        // Disabled (0): redemption order is untouched
+        let plan = plan_vault_redemptions(&skip_risky_vaults(vaults, price, 0), amount, collateral, price, U256::zero());
+        assert_eq!(plan[0].vault_key.id, 1);
+    }
 }
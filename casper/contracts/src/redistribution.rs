@@ -0,0 +1,105 @@
+//! Redistribution accounting for branch vaults.
+//!
+//! Implements LiquityV2-style lazy redistribution:
+//! - Each branch tracks cumulative collateral/debt per unit of stake (L_coll, L_debt)
+//! - Each vault stores a snapshot of the trackers at its last interaction
+//! - A vault's pending share is `stake * (L_now - L_snapshot) / PRECISION`
+//!
+//! Pending amounts are already included in branch totals; applying them to a
+//! vault only moves them from "unassigned" into the vault's stored values.
+
+use odra::casper_types::U256;
+
+/// Tracker precision scale (1e18)
+pub const PRECISION: u64 = 1_000_000_000_000_000_000;
+
+/// Per-vault redistribution snapshot
+#[odra::odra_type]
+#[derive(Default)]
+pub struct RedistributionSnapshot {
+    /// Vault stake (collateral at last interaction)
+    pub stake: U256,
+    /// Cumulative collateral tracker at last interaction
+    pub l_collateral: U256,
+    /// Cumulative debt tracker at last interaction
+    pub l_debt: U256,
+}
+
+/// Pending redistributed amount for a stake since its snapshot
+///
+/// # Arguments
+/// * `stake` - Vault stake recorded in the snapshot
+/// * `l_current` - Current cumulative tracker
+/// * `l_snapshot` - Tracker value recorded in the snapshot
+pub fn pending_reward(stake: U256, l_current: U256, l_snapshot: U256) -> U256 {
+    if stake.is_zero() || l_current <= l_snapshot {
+        return U256::zero();
+    }
+    stake
+        .checked_mul(l_current - l_snapshot)
+        .and_then(|v| v.checked_div(U256::from(PRECISION)))
+        .unwrap_or(U256::zero())
+}
+
+/// Tracker increment for redistributing `amount` across `total_stakes`
+///
+/// Returns zero when there are no stakes to redistribute to.
+pub fn tracker_increment(amount: U256, total_stakes: U256) -> U256 {
+    if total_stakes.is_zero() {
+        return U256::zero();
+    }
+    amount
+        .checked_mul(U256::from(PRECISION))
+        .and_then(|v| v.checked_div(total_stakes))
+        .unwrap_or(U256::zero())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_no_pending_without_stake() {
+        let pending = pending_reward(U256::zero(), U256::from(PRECISION), U256::zero());
+        assert_eq!(pending, U256::zero());
+    }
+
+    #[test]
+    fn test_no_pending_when_tracker_unchanged() {
+        let l = U256::from(PRECISION);
+        assert_eq!(pending_reward(U256::from(1000u64), l, l), U256::zero());
+    }
+
+    #[test]
+    fn test_tracker_increment_zero_stakes() {
+        assert_eq!(tracker_increment(U256::from(1000u64), U256::zero()), U256::zero());
+    }
+
+    #[test]
+    fn test_vault_reads_share_before_interaction() {
+        // Two vaults with stakes 100 and 300 share a redistribution of 40 collateral / 80 debt
+        let stake_a = U256::from(100u64);
+        let stake_b = U256::from(300u64);
+        let total_stakes = stake_a + stake_b;
+
+        let l_coll = tracker_increment(U256::from(40u64), total_stakes);
+        let l_debt = tracker_increment(U256::from(80u64), total_stakes);
+
+        // Neither vault has been touched since the snapshot (trackers were zero)
+        assert_eq!(pending_reward(stake_a, l_coll, U256::zero()), U256::from(10u64));
+        assert_eq!(pending_reward(stake_b, l_coll, U256::zero()), U256::from(30u64));
+        assert_eq!(pending_reward(stake_a, l_debt, U256::zero()), U256::from(20u64));
+        assert_eq!(pending_reward(stake_b, l_debt, U256::zero()), U256::from(60u64));
+    }
+
+    #[test]
+    fn test_snapshot_excludes_earlier_redistribution() {
+        let total_stakes = U256::from(400u64);
+        let first = tracker_increment(U256::from(40u64), total_stakes);
+        let second = first + tracker_increment(U256::from(400u64), total_stakes);
+
+        // A vault snapshotted after the first redistribution only sees the second
+        let pending = pending_reward(U256::from(100u64), second, first);
+        assert_eq!(pending, U256::from(100u64));
+    }
+}
@@ -1,10 +1,12 @@
 //! Registry contract for managing branches and protocol configuration.
 
 use odra::prelude::*;
-use odra::casper_types::{Key, U256};
-use crate::types::{CollateralId, ProtocolConfig, InterestRateBounds};
+use odra::casper_types::{Key, U256, runtime_args};
+use odra::CallDef;
+use crate::types::{CollateralId, ComponentId, ProtocolConfig, InterestRateBounds};
 use crate::interfaces::CollateralConfig;
 use crate::errors::CdpError;
+use crate::voting::{voting_power, VotingWeights, MAX_VOTING_WEIGHT_BPS};
 
 /// Registry contract for CDP protocol configuration
 #[odra::module]
@@ -31,6 +33,10 @@ pub struct Registry {
     config: Var<ProtocolConfig>,
     /// Collateral configurations
     collateral_configs: Mapping<CollateralId, CollateralConfig>,
+    /// Components without a dedicated slot above (engines, tokens, feeds)
+    components: Mapping<ComponentId, Address>,
+    /// Governance voting weights per source
+    voting_weights: Var<VotingWeights>,
 }
 
 #[odra::module]
@@ -147,6 +153,26 @@ impl Registry {
         self.set_branch_scspr(branch, config);
     }
 
+    /// Set any component address (admin only)
+    ///
+    /// Components with a dedicated setter are stored in the same slot, so
+    /// `set_component(ComponentId::Router, a)` is equivalent to `set_router(a)`.
+    /// Branch collateral configs still go through `set_branch_*`.
+    pub fn set_component(&mut self, component: ComponentId, address: Address) {
+        self.require_admin();
+        match component {
+            ComponentId::Router => self.router.set(Some(address)),
+            ComponentId::Stablecoin => self.stablecoin.set(Some(address)),
+            ComponentId::Treasury => self.treasury.set(Some(address)),
+            ComponentId::Oracle => self.oracle.set(Some(address)),
+            ComponentId::StabilityPool => self.stability_pool.set(Some(address)),
+            ComponentId::LiquidationEngine => self.liquidation_engine.set(Some(address)),
+            ComponentId::BranchCspr => self.branch_cspr.set(Some(address)),
+            ComponentId::BranchScspr => self.branch_scspr.set(Some(address)),
+            _ => self.components.set(&component, address),
+        }
+    }
+
     /// Update protocol configuration (admin only)
     pub fn set_config(&mut self, config: ProtocolConfig) {
         self.require_admin();
@@ -202,6 +228,29 @@ impl Registry {
         }
     }
 
+    /// Resolve a component address, reverting if it is not registered
+    pub fn resolve(&self, component: ComponentId) -> Address {
+        match self.find_component(component) {
+            Some(address) => address,
+            None => self.env().revert(CdpError::InvalidConfig),
+        }
+    }
+
+    /// Resolve a component address, if registered
+    pub fn find_component(&self, component: ComponentId) -> Option<Address> {
+        match component {
+            ComponentId::Router => self.get_router(),
+            ComponentId::Stablecoin => self.get_stablecoin(),
+            ComponentId::Treasury => self.get_treasury(),
+            ComponentId::Oracle => self.get_oracle(),
+            ComponentId::StabilityPool => self.get_stability_pool(),
+            ComponentId::LiquidationEngine => self.get_liquidation_engine(),
+            ComponentId::BranchCspr => self.get_branch(CollateralId::Cspr),
+            ComponentId::BranchScspr => self.get_branch(CollateralId::SCSPR),
+            _ => self.components.get(&component),
+        }
+    }
+
     /// Get collateral config by collateral type
     pub fn get_collateral_config(&self, collateral_id: CollateralId) -> Option<CollateralConfig> {
         self.collateral_configs.get(&collateral_id)
@@ -212,6 +261,50 @@ impl Registry {
         self.config.get()
     }
 
+    /// Get governance voting weights
+    pub fn get_voting_weights(&self) -> VotingWeights {
+        self.voting_weights.get().unwrap_or_default()
+    }
+
+    /// Set governance voting weights (admin only)
+    pub fn set_voting_weights(&mut self, weights: VotingWeights) {
+        self.require_admin();
+        if weights.scspr_weight_bps > MAX_VOTING_WEIGHT_BPS || weights.sp_weight_bps > MAX_VOTING_WEIGHT_BPS {
+            self.env().revert(CdpError::InvalidConfig);
+        }
+        self.voting_weights.set(weights);
+    }
+
+    /// Current governance voting power of `account` (18 decimals)
+    ///
+    /// Weighted sum of its stCSPR balance (valued in CSPR at R) and its
+    /// compounded SP deposit. Unregistered sources count as zero.
+    pub fn get_voting_power(&self, account: Address) -> U256 {
+        let (scspr_shares, rate) = match self.find_component(ComponentId::ScsprYbToken) {
+            Some(ybtoken) => {
+                let balance_args = runtime_args! { "owner" => account };
+                let balance_call = CallDef::new("balance_of", false, balance_args);
+                let rate_call = CallDef::new("get_exchange_rate", false, runtime_args! {});
+                (
+                    self.env().call_contract::<U256>(ybtoken, balance_call),
+                    self.env().call_contract::<U256>(ybtoken, rate_call),
+                )
+            }
+            None => (U256::zero(), U256::zero()),
+        };
+
+        let sp_deposit = match self.get_stability_pool() {
+            Some(pool) => {
+                let args = runtime_args! { "depositor" => account };
+                let call_def = CallDef::new("get_compounded_deposit", false, args);
+                self.env().call_contract::<U256>(pool, call_def)
+            }
+            None => U256::zero(),
+        };
+
+        voting_power(scspr_shares, rate, sp_deposit, &self.get_voting_weights())
+    }
+
     /// Check if caller is admin
     pub fn is_admin(&self, caller: Address) -> bool {
         self.admin.get().map_or(false, |admin| admin == caller)
@@ -225,6 +318,29 @@ impl Registry {
     }
 }
 
+/// Resolve each of `components` through `lookup`, skipping unregistered ones
+///
+/// Used by engines' `refresh_addresses` so a partially wired registry only
+/// updates what it knows about.
+pub fn resolve_all<F>(components: &[ComponentId], lookup: F) -> Vec<(ComponentId, Address)>
+where
+    F: Fn(ComponentId) -> Option<Address>,
+{
+    components
+        .iter()
+        .filter_map(|component| lookup(*component).map(|address| (*component, address)))
+        .collect()
+}
+
+/// Try-resolve a component from the registry contract at `registry`
+pub fn try_resolve(env: &odra::ContractEnv, registry: Address, component: ComponentId) -> Option<Address> {
+    let args = runtime_args! {
+        "component" => component
+    };
+    let call = CallDef::new("find_component", false, args);
+    env.call_contract(registry, call)
+}
+
 /// Default protocol configuration
 pub fn default_protocol_config() -> ProtocolConfig {
     ProtocolConfig {
@@ -6,9 +6,23 @@
 use odra::prelude::*;
 use odra::casper_types::{U256, runtime_args};
 use odra::CallDef;
-use crate::types::{CollateralId, SafeModeState, OracleStatus};
-use crate::interfaces::{AdjustVaultParams, VaultInfo, BranchStatus};
+use crate::types::{CollateralId, ComponentId, SafeModeState, OracleStatus, PriceData};
+use crate::interfaces::{
+    AdjustOp, AdjustVaultParams, VaultInfo, BranchStatus, SystemBacking, CollateralValuation, ProtocolStats,
+};
+use crate::branch::safe_mode_blocks_adjustment;
+use crate::math::collateral_to_value;
+use crate::liquidation_engine::LiquidationStats;
+use crate::redemption_engine::RedemptionStats;
+use crate::stability_pool::PoolStats;
 use crate::errors::CdpError;
+use crate::registry::try_resolve;
+
+/// Maximum delay before safe mode blocks new borrowing (6 hours)
+const MAX_SAFE_MODE_GRACE_SECONDS: u64 = 21_600;
+
+/// Maximum operations in one `adjust_vaults_batch` call
+pub const MAX_ADJUST_BATCH: usize = 10;
 
 /// Router contract - main entry point for the CDP protocol
 #[odra::module]
@@ -17,6 +31,8 @@ pub struct Router {
     registry: Var<Address>,
     /// Global safe mode state
     safe_mode: Var<SafeModeState>,
+    /// Seconds after a safe mode trigger before borrowing is blocked
+    safe_mode_grace_seconds: Var<u64>,
 }
 
 #[odra::module]
@@ -50,6 +66,70 @@ impl Router {
         self.validate_interest_rate(interest_rate_bps);
 
         let caller = self.env().caller();
+        self.open_vault_for(caller, collateral_id, "open_vault", collateral_amount, debt_amount, interest_rate_bps)
+    }
+
+    /// Deposit attached CSPR into the stCSPR ybToken and open a stCSPR vault
+    /// with the minted shares, in one call
+    ///
+    /// The collateral is exactly the share amount returned by the ybToken's
+    /// `deposit`, so no local re-quote of the exchange rate is involved.
+    ///
+    /// # Arguments
+    /// * `debt_amount` - Amount of gUSD to mint
+    /// * `interest_rate_bps` - Interest rate in basis points
+    #[odra(payable)]
+    pub fn zap_open_scspr_vault(&mut self, debt_amount: U256, interest_rate_bps: u32) -> u64 {
+        self.require_not_safe_mode_for_open();
+        self.validate_interest_rate(interest_rate_bps);
+
+        let caller = self.env().caller();
+        let cspr_amount = self.env().attached_value();
+        if cspr_amount.is_zero() {
+            self.env().revert(CdpError::InsufficientCollateral);
+        }
+
+        // Deposit CSPR; the router receives the minted stCSPR
+        let ybtoken_addr = self.get_component_address(ComponentId::ScsprYbToken);
+        let deposit_call = CallDef::new("deposit", true, runtime_args! {}).with_amount(cspr_amount);
+        let minted: U256 = self.env().call_contract(ybtoken_addr, deposit_call);
+        if minted.is_zero() {
+            self.env().revert(CdpError::InsufficientCollateral);
+        }
+
+        // Hand the shares to the stCSPR branch as vault collateral
+        let branch_addr = self.get_branch_address(CollateralId::SCSPR);
+        let transfer_args = runtime_args! {
+            "recipient" => branch_addr,
+            "amount" => minted,
+        };
+        let transfer_call = CallDef::new("transfer", true, transfer_args);
+        let transferred: bool = self.env().call_contract(ybtoken_addr, transfer_call);
+        if !transferred {
+            self.env().revert(CdpError::TokenTransferFailed);
+        }
+
+        // The shares are already with the branch, so it must not pull them again
+        self.open_vault_for(
+            caller,
+            CollateralId::SCSPR,
+            "open_vault_prefunded",
+            minted,
+            debt_amount,
+            interest_rate_bps,
+        )
+    }
+
+    /// Open a vault on the branch for `caller` via `entry_point` and mint the debt to them
+    fn open_vault_for(
+        &mut self,
+        caller: Address,
+        collateral_id: CollateralId,
+        entry_point: &str,
+        collateral_amount: U256,
+        debt_amount: U256,
+        interest_rate_bps: u32,
+    ) -> u64 {
         let branch_addr = self.get_branch_address(collateral_id);
 
         let branch_args = runtime_args! {
@@ -58,7 +138,7 @@ impl Router {
             "debt_amount" => debt_amount,
             "interest_rate_bps" => interest_rate_bps,
         };
-        let branch_call = CallDef::new("open_vault", true, branch_args);
+        let branch_call = CallDef::new(entry_point, true, branch_args);
         let vault_id: u64 = self.env().call_contract(branch_addr, branch_call);
 
         if !debt_amount.is_zero() {
@@ -91,45 +171,61 @@ impl Router {
         debt_delta: U256,
         debt_is_repay: bool,
     ) {
-        let params = AdjustVaultParams {
-            collateral_delta,
-            collateral_is_withdraw,
-            debt_delta,
-            debt_is_repay,
+        let op = AdjustOp {
+            collateral_id,
+            vault_id,
+            params: AdjustVaultParams {
+                collateral_delta,
+                collateral_is_withdraw,
+                debt_delta,
+                debt_is_repay,
+            },
         };
-        self.require_safe_mode_adjustment_allowed(&params);
+        let caller = self.env().caller();
+        self.adjust_vault_for(caller, &op);
+    }
 
+    /// Borrow `amount` more gUSD against an existing vault
+    ///
+    /// Shortcut for `adjust_vault` with only a debt increase: MCR, the borrowing
+    /// fee and safe mode apply as usual, and the gUSD is minted to the caller.
+    pub fn borrow_more(&mut self, collateral_id: CollateralId, vault_id: u64, amount: U256) {
+        let op = AdjustOp {
+            collateral_id,
+            vault_id,
+            params: AdjustVaultParams::borrow(amount),
+        };
         let caller = self.env().caller();
-        let branch_addr = self.get_branch_address(collateral_id);
+        self.adjust_vault_for(caller, &op);
+    }
 
-        let branch_args = runtime_args! {
-            "owner" => caller,
-            "vault_id" => vault_id,
-            "collateral_delta" => collateral_delta,
-            "collateral_is_withdraw" => collateral_is_withdraw,
-            "debt_delta" => debt_delta,
-            "debt_is_repay" => debt_is_repay,
+    /// Repay `amount` gUSD of a vault's debt
+    ///
+    /// Shortcut for `adjust_vault` with only a debt decrease; the gUSD is
+    /// burned from the caller (requires allowance).
+    pub fn repay(&mut self, collateral_id: CollateralId, vault_id: u64, amount: U256) {
+        let op = AdjustOp {
+            collateral_id,
+            vault_id,
+            params: AdjustVaultParams::repay(amount),
         };
-        let branch_call = CallDef::new("adjust_vault", true, branch_args);
-        self.env().call_contract::<()>(branch_addr, branch_call);
+        let caller = self.env().caller();
+        self.adjust_vault_for(caller, &op);
+    }
 
-        if !debt_delta.is_zero() {
-            let stablecoin_addr = self.get_stablecoin_address();
-            if debt_is_repay {
-                let burn_args = runtime_args! {
-                    "from" => caller,
-                    "amount" => debt_delta,
-                };
-                let burn_call = CallDef::new("burn_with_allowance", true, burn_args);
-                self.env().call_contract::<()>(stablecoin_addr, burn_call);
-            } else {
-                let mint_args = runtime_args! {
-                    "to" => caller,
-                    "amount" => debt_delta,
-                };
-                let mint_call = CallDef::new("mint", true, mint_args);
-                self.env().call_contract::<()>(stablecoin_addr, mint_call);
-            }
+    /// Adjust several of the caller's vaults in one transaction
+    ///
+    /// Operations run in order, across branches, with the same checks as
+    /// `adjust_vault`. Any failing operation reverts the whole batch.
+    /// At most `MAX_ADJUST_BATCH` operations.
+    pub fn adjust_vaults_batch(&mut self, ops: Vec<AdjustOp>) {
+        if let Err(err) = check_adjust_batch(&ops) {
+            self.env().revert(err);
+        }
+
+        let caller = self.env().caller();
+        for op in &ops {
+            self.adjust_vault_for(caller, op);
         }
     }
 
@@ -175,6 +271,22 @@ impl Router {
         self.env().call_contract::<()>(branch_addr, close_call);
     }
 
+    /// Close a vault by surrendering collateral instead of repaying gUSD
+    ///
+    /// The treasury takes collateral covering the debt (plus the liquidation
+    /// penalty if the vault is below MCR) and the debt is burned from its gUSD;
+    /// the rest is credited as claimable surplus. Returns the surplus.
+    pub fn close_with_collateral(&mut self, collateral_id: CollateralId, vault_id: u64) -> U256 {
+        self.require_not_safe_mode_for_close();
+
+        let caller = self.env().caller();
+        let branch_addr = self.get_branch_address(collateral_id);
+
+        let close_args = runtime_args! { "owner" => caller, "vault_id" => vault_id };
+        let close_call = CallDef::new("close_with_collateral", true, close_args);
+        self.env().call_contract(branch_addr, close_call)
+    }
+
     /// Get vault info for a specific owner and collateral type
     pub fn get_vault(&self, collateral_id: CollateralId, _owner: Address, vault_id: u64) -> Option<VaultInfo> {
         let branch_addr = self.get_branch_address(collateral_id);
@@ -191,6 +303,162 @@ impl Router {
         Some(self.env().call_contract(branch_addr, call_def))
     }
 
+    /// Get protocol-wide backing across both branches and the stability pool
+    ///
+    /// Read-only; uses each branch's cached price for collateral value.
+    pub fn get_system_backing(&self) -> SystemBacking {
+        let registry = self.registry.get().expect("registry not set");
+        let mut total_debt = U256::zero();
+        let mut total_collateral_value_usd = U256::zero();
+
+        for collateral_id in [CollateralId::Cspr, CollateralId::SCSPR] {
+            let args = runtime_args! { "collateral_id" => collateral_id };
+            let call_def = CallDef::new("get_branch", false, args);
+            let branch: Option<Address> = self.env().call_contract(registry, call_def);
+            if let Some(branch_addr) = branch {
+                let debt_call = CallDef::new("get_total_debt", false, runtime_args! {});
+                let debt: U256 = self.env().call_contract(branch_addr, debt_call);
+                let value_call = CallDef::new("get_total_collateral_value", false, runtime_args! {});
+                let value: U256 = self.env().call_contract(branch_addr, value_call);
+                total_debt += debt;
+                total_collateral_value_usd += value;
+            }
+        }
+
+        let sp_call = CallDef::new("get_stability_pool", false, runtime_args! {});
+        let stability_pool: Option<Address> = self.env().call_contract(registry, sp_call);
+        let sp_deposits = match stability_pool {
+            Some(sp_addr) => {
+                let deposits_call = CallDef::new("get_total_deposits", false, runtime_args! {});
+                self.env().call_contract(sp_addr, deposits_call)
+            }
+            None => U256::zero(),
+        };
+
+        SystemBacking {
+            total_debt,
+            total_collateral_value_usd,
+            sp_deposits,
+            backing_ratio_bps: backing_ratio_bps(total_debt, total_collateral_value_usd, sp_deposits),
+        }
+    }
+
+    /// Value all branch collateral in USD at the oracle price (composite for stCSPR)
+    ///
+    /// A branch whose feed is not Ok is valued at the oracle's last good price
+    /// and clears `prices_ok` instead of failing the whole query.
+    pub fn get_total_collateral_value_usd(&self) -> CollateralValuation {
+        let registry = self.registry.get().expect("registry not set");
+        let oracle_call = CallDef::new("get_oracle", false, runtime_args! {});
+        let oracle: Option<Address> = self.env().call_contract(registry, oracle_call);
+        let oracle_addr = match oracle {
+            Some(addr) => addr,
+            None => self.env().revert(CdpError::InvalidConfig),
+        };
+
+        let mut values = [U256::zero(), U256::zero()];
+        let mut prices_ok = true;
+        for (i, collateral_id) in [CollateralId::Cspr, CollateralId::SCSPR].into_iter().enumerate() {
+            let args = runtime_args! { "collateral_id" => collateral_id };
+            let call_def = CallDef::new("get_branch", false, args);
+            let branch: Option<Address> = self.env().call_contract(registry, call_def);
+            let branch_addr = match branch {
+                Some(addr) => addr,
+                None => continue,
+            };
+
+            let coll_call = CallDef::new("get_total_collateral", false, runtime_args! {});
+            let total_collateral: U256 = self.env().call_contract(branch_addr, coll_call);
+
+            let price_args = runtime_args! { "collateral_id" => collateral_id };
+            let price_call = CallDef::new("get_price", false, price_args);
+            let price: PriceData = self.env().call_contract(oracle_addr, price_call);
+            let last_good_args = runtime_args! { "collateral_id" => collateral_id };
+            let last_good_call = CallDef::new("get_last_good_price", false, last_good_args);
+            let last_good: U256 = self.env().call_contract(oracle_addr, last_good_call);
+
+            let (value, live) = branch_value_usd(total_collateral, &price, last_good);
+            values[i] = value;
+            prices_ok = prices_ok && live;
+        }
+
+        CollateralValuation {
+            cspr_value_usd: values[0],
+            scspr_value_usd: values[1],
+            total_value_usd: values[0] + values[1],
+            prices_ok,
+        }
+    }
+
+    /// TVL, debt, vault count, SP size, liquidations and redemptions in one call
+    ///
+    /// Read-only aggregate for dashboards; it makes several cross-contract
+    /// calls. Components the registry doesn't know yet count as zero.
+    pub fn get_protocol_stats(&self) -> ProtocolStats {
+        let registry = self.registry.get().expect("registry not set");
+
+        let mut branches = Vec::new();
+        for collateral_id in [CollateralId::Cspr, CollateralId::SCSPR] {
+            let args = runtime_args! { "collateral_id" => collateral_id };
+            let call_def = CallDef::new("get_branch", false, args);
+            let branch: Option<Address> = self.env().call_contract(registry, call_def);
+            if let Some(branch_addr) = branch {
+                let status_call = CallDef::new("get_status", false, runtime_args! {});
+                branches.push(self.env().call_contract::<BranchStatus>(branch_addr, status_call));
+            }
+        }
+
+        let stats_of = |component: ComponentId| {
+            try_resolve(&self.env(), registry, component)
+                .map(|addr| (addr, CallDef::new("get_stats", false, runtime_args! {})))
+        };
+        let pool = match stats_of(ComponentId::StabilityPool) {
+            Some((addr, call_def)) => self.env().call_contract(addr, call_def),
+            None => PoolStats {
+                total_deposits: U256::zero(),
+                total_cspr_collateral: U256::zero(),
+                total_scspr_collateral: U256::zero(),
+                total_debt_absorbed: U256::zero(),
+                depositor_count: 0,
+            },
+        };
+        let liquidations = match stats_of(ComponentId::LiquidationEngine) {
+            Some((addr, call_def)) => self.env().call_contract(addr, call_def),
+            None => LiquidationStats {
+                total_liquidations: 0,
+                total_debt_liquidated: U256::zero(),
+                total_collateral_seized: U256::zero(),
+            },
+        };
+        let redemptions = match stats_of(ComponentId::RedemptionEngine) {
+            Some((addr, call_def)) => self.env().call_contract(addr, call_def),
+            None => RedemptionStats {
+                total_redeemed: U256::zero(),
+                total_collateral_distributed: U256::zero(),
+                total_fees_collected: U256::zero(),
+                total_redemptions: 0,
+            },
+        };
+
+        aggregate_protocol_stats(
+            &branches,
+            self.get_total_collateral_value_usd(),
+            &pool,
+            &liquidations,
+            &redemptions,
+        )
+    }
+
+    /// Suggested minimum borrower interest rate in bps (advisory, not enforced)
+    ///
+    /// Rises with recent redemption volume; see the redemption engine's
+    /// `get_redemption_rate_floor`.
+    pub fn get_redemption_rate_floor(&self) -> u32 {
+        let engine = self.get_component_address(ComponentId::RedemptionEngine);
+        let call_def = CallDef::new("get_redemption_rate_floor", false, runtime_args! {});
+        self.env().call_contract(engine, call_def)
+    }
+
     /// Get global safe mode state
     pub fn get_safe_mode(&self) -> SafeModeState {
         self.safe_mode.get().unwrap_or(SafeModeState {
@@ -200,6 +468,28 @@ impl Router {
         })
     }
 
+    /// Whether safe mode currently blocks new borrowing
+    ///
+    /// True once safe mode has been active for the configured grace period.
+    /// Withdrawals and closes are blocked for the whole time safe mode is active.
+    pub fn safe_mode_effective(&self) -> bool {
+        safe_mode_effective(&self.get_safe_mode(), self.get_safe_mode_grace_seconds(), self.env().get_block_time())
+    }
+
+    /// Get the safe mode grace period in seconds (default 0: no grace)
+    pub fn get_safe_mode_grace_seconds(&self) -> u64 {
+        self.safe_mode_grace_seconds.get_or_default()
+    }
+
+    /// Set the safe mode grace period in seconds (admin only)
+    pub fn set_safe_mode_grace_seconds(&mut self, grace_seconds: u64) {
+        // TODO: Add admin access control
+        if grace_seconds > MAX_SAFE_MODE_GRACE_SECONDS {
+            self.env().revert(CdpError::InvalidConfig);
+        }
+        self.safe_mode_grace_seconds.set(grace_seconds);
+    }
+
     /// Get registry address
     pub fn get_registry(&self) -> Option<Address> {
         self.registry.get()
@@ -229,9 +519,45 @@ impl Router {
         });
     }
 
+    fn adjust_vault_for(&mut self, caller: Address, op: &AdjustOp) {
+        let params = &op.params;
+        self.require_safe_mode_adjustment_allowed(params);
+
+        let branch_addr = self.get_branch_address(op.collateral_id);
+
+        let branch_args = runtime_args! {
+            "owner" => caller,
+            "vault_id" => op.vault_id,
+            "collateral_delta" => params.collateral_delta,
+            "collateral_is_withdraw" => params.collateral_is_withdraw,
+            "debt_delta" => params.debt_delta,
+            "debt_is_repay" => params.debt_is_repay,
+        };
+        let branch_call = CallDef::new("adjust_vault", true, branch_args);
+        self.env().call_contract::<()>(branch_addr, branch_call);
+
+        if !params.debt_delta.is_zero() {
+            let stablecoin_addr = self.get_stablecoin_address();
+            if params.debt_is_repay {
+                let burn_args = runtime_args! {
+                    "from" => caller,
+                    "amount" => params.debt_delta,
+                };
+                let burn_call = CallDef::new("burn_with_allowance", true, burn_args);
+                self.env().call_contract::<()>(stablecoin_addr, burn_call);
+            } else {
+                let mint_args = runtime_args! {
+                    "to" => caller,
+                    "amount" => params.debt_delta,
+                };
+                let mint_call = CallDef::new("mint", true, mint_args);
+                self.env().call_contract::<()>(stablecoin_addr, mint_call);
+            }
+        }
+    }
+
     fn require_not_safe_mode_for_open(&self) {
-        let state = self.get_safe_mode();
-        if state.is_active {
+        if self.safe_mode_effective() {
             self.env().revert(CdpError::SafeModeActive);
         }
     }
@@ -245,14 +571,7 @@ impl Router {
 
     fn require_safe_mode_adjustment_allowed(&self, params: &AdjustVaultParams) {
         let state = self.get_safe_mode();
-        if !state.is_active {
-            return;
-        }
-
-        let is_borrowing = !params.debt_is_repay && params.debt_delta > U256::zero();
-        let is_withdrawing = params.collateral_is_withdraw && params.collateral_delta > U256::zero();
-
-        if is_borrowing || is_withdrawing {
+        if safe_mode_blocks_adjustment(params, state.is_active, self.safe_mode_effective()) {
             self.env().revert(CdpError::SafeModeActive);
         }
     }
@@ -281,4 +600,80 @@ impl Router {
         let stablecoin: Option<Address> = self.env().call_contract(registry, call_def);
         stablecoin.expect("stablecoin not set")
     }
+
+    fn get_component_address(&self, component: ComponentId) -> Address {
+        let registry = self.registry.get().expect("registry not set");
+        match try_resolve(&self.env(), registry, component) {
+            Some(address) => address,
+            None => self.env().revert(CdpError::InvalidConfig),
+        }
+    }
+}
+
+// ===== Helper Functions =====
+
+/// Reject a batch of more than `MAX_ADJUST_BATCH` operations
+pub fn check_adjust_batch(ops: &[AdjustOp]) -> Result<(), CdpError> {
+    if ops.len() > MAX_ADJUST_BATCH {
+        return Err(CdpError::BatchTooLarge);
+    }
+    Ok(())
+}
+
+/// Backing ratio in bps: (collateral_value + sp_deposits) * 10000 / total_debt
+///
+/// Returns u32::MAX when there is no debt.
+pub fn backing_ratio_bps(total_debt: U256, collateral_value: U256, sp_deposits: U256) -> u32 {
+    if total_debt.is_zero() {
+        return u32::MAX;
+    }
+    let ratio = (collateral_value + sp_deposits) * U256::from(10_000u32) / total_debt;
+    if ratio > U256::from(u32::MAX) {
+        u32::MAX
+    } else {
+        ratio.low_u32()
+    }
+}
+
+/// Combine per-contract statistics into one `ProtocolStats`
+pub fn aggregate_protocol_stats(
+    branches: &[BranchStatus],
+    collateral: CollateralValuation,
+    pool: &PoolStats,
+    liquidations: &LiquidationStats,
+    redemptions: &RedemptionStats,
+) -> ProtocolStats {
+    let total_debt = branches.iter().fold(U256::zero(), |acc, branch| acc + branch.total_debt);
+    let vault_count = branches.iter().map(|branch| branch.vault_count).sum();
+    ProtocolStats {
+        tvl_usd: collateral.total_value_usd + pool.total_deposits,
+        collateral,
+        total_debt,
+        vault_count,
+        sp_deposits: pool.total_deposits,
+        sp_depositor_count: pool.depositor_count,
+        total_liquidations: liquidations.total_liquidations,
+        total_redemptions: redemptions.total_redemptions,
+        total_redeemed: redemptions.total_redeemed,
+    }
+}
+
+/// Whether safe mode's borrowing restrictions are in force at `now`
+///
+/// Borrowing stays open for `grace_seconds` after the trigger so a keeper can
+/// clear a false positive; withdrawal and liquidation blocks do not wait.
+pub fn safe_mode_effective(state: &SafeModeState, grace_seconds: u64, now: u64) -> bool {
+    state.is_active && now >= state.triggered_at.saturating_add(grace_seconds)
+}
+
+/// USD value of a branch's collateral and whether the live price was used
+///
+/// Falls back to `last_good_price` when the feed status is not Ok, so one
+/// degraded oracle does not zero out the whole valuation.
+pub fn branch_value_usd(total_collateral: U256, price: &PriceData, last_good_price: U256) -> (U256, bool) {
+    if price.status == OracleStatus::Ok {
+        (collateral_to_value(total_collateral, price.price_int), true)
+    } else {
+        (collateral_to_value(total_collateral, last_good_price), false)
+    }
 }
@@ -26,12 +26,16 @@
 //! 1. Operator compounds rewards off-chain
 //! 2. Operator calls sync_assets() to update total_assets
 //! 3. R increases, reflecting staking rewards
+//! 4. Increases beyond the configured rate limit are clamped (`RateIncreaseClamped`)
 
 use odra::prelude::*;
-use odra::casper_types::{U256, U512, Key};
+use odra::casper_types::{U256, U512, Key, runtime_args};
 use odra::casper_types::account::AccountHash;
 use odra::casper_types::bytesrepr::ToBytes;
+use odra::CallDef;
 use crate::errors::CdpError;
+use crate::math::mul_div_floor;
+use crate::withdraw_queue::{QueueStats, backfill};
 use base64::engine::general_purpose::STANDARD as BASE64_STANDARD;
 use base64::Engine;
 
@@ -60,6 +64,8 @@ const SECURITY_ADMIN: u8 = 1;
 const SECURITY_MINT_AND_BURN: u8 = 2;
 const SECURITY_BURNER: u8 = 3;
 const SECURITY_MINTER: u8 = 4;
+/// Basis points scale
+const BPS_SCALE: u32 = 10_000;
 
 #[odra::event]
 pub struct Transfer {
@@ -111,6 +117,14 @@ pub struct Burn {
     pub amount: U256,
 }
 
+/// Alert: a sync reported an R increase above the rate limit and was clamped
+#[odra::event]
+pub struct RateIncreaseClamped {
+    pub previous_rate: U256,
+    pub reported_rate: U256,
+    pub applied_rate: U256,
+}
+
 /// Asset breakdown for total_assets calculation
 #[odra::odra_type]
 #[derive(Default)]
@@ -129,6 +143,19 @@ pub struct AssetBreakdown {
     pub realized_losses: U256,
 }
 
+/// NAV breakdown net of withdrawal-queue liabilities
+#[odra::odra_type]
+pub struct NavReport {
+    /// Total CSPR backing (see `AssetBreakdown`)
+    pub total_assets: U256,
+    /// Total ybToken shares outstanding
+    pub total_shares: U256,
+    /// Quoted, not-yet-claimed withdrawal queue payouts
+    pub pending_withdrawal_assets: U256,
+    /// Backing left after honouring pending withdrawals
+    pub free_nav: U256,
+}
+
 impl AssetBreakdown {
     /// Calculate total_assets = idle + delegated + undelegating + claimable - fees - losses
     pub fn total(&self) -> U256 {
@@ -140,6 +167,16 @@ impl AssetBreakdown {
             U256::zero()
         }
     }
+
+    /// Move `amount` of claimable CSPR back to idle, leaving `total()` unchanged
+    pub fn release_claimable(&mut self, amount: U256) -> Result<(), CdpError> {
+        if amount > self.claimable_cspr {
+            return Err(CdpError::LstInsufficientClaimable);
+        }
+        self.claimable_cspr -= amount;
+        self.idle_cspr += amount;
+        Ok(())
+    }
 }
 
 /// Configuration for the ybToken
@@ -156,7 +193,9 @@ pub struct YbTokenConfig {
 /// stCSPR ybToken Contract
 ///
 /// CEP-18 compatible yield-bearing token representing staked CSPR.
-#[odra::module(events = [Transfer, TransferFrom, SetAllowance, IncreaseAllowance, DecreaseAllowance, Mint, Burn])]
+#[odra::module(events = [
+    Transfer, TransferFrom, SetAllowance, IncreaseAllowance, DecreaseAllowance, Mint, Burn, RateIncreaseClamped
+])]
 pub struct ScsprYbToken {
     // ===== CEP-18 Token State =====
     /// Token name
@@ -185,6 +224,12 @@ pub struct ScsprYbToken {
     admin: Var<Address>,
     /// CEP-18 security levels (address -> level)
     security_levels: Mapping<Address, u8>,
+    /// Maximum total assets accepted by deposits (zero = uncapped)
+    deposit_cap: Var<U256>,
+    /// Max R increase per `min_sync_interval_seconds`, in bps (zero = unlimited)
+    max_rate_increase_bps: Var<u32>,
+    /// Interval the R increase allowance accrues over
+    min_sync_interval_seconds: Var<u64>,
 }
 
 #[odra::module]
@@ -211,8 +256,20 @@ impl ScsprYbToken {
 
     /// Upgrade hook (called automatically by Odra during contract upgrade).
     ///
-    /// Backfills CEP-18 named keys/dictionaries for explorers/indexers.
+    /// Defaults storage left unset by an older version (balances, shares and
+    /// assets are never overwritten), then backfills CEP-18 named
+    /// keys/dictionaries for explorers/indexers. Stored structs keep their
+    /// original layout; newer settings live in their own vars.
     pub fn upgrade(&mut self) {
+        if let Some(assets) = backfill(self.assets.get(), AssetBreakdown::default()) {
+            self.assets.set(assets);
+        }
+        if let Some(cap) = backfill(self.deposit_cap.get(), U256::zero()) {
+            self.deposit_cap.set(cap);
+        }
+        if let Some(queue) = backfill(self.withdraw_queue.get(), None) {
+            self.withdraw_queue.set(queue);
+        }
         self.ensure_cep18_named_keys();
     }
 
@@ -349,10 +406,26 @@ impl ScsprYbToken {
     /// * Amount of stCSPR minted
     #[odra(payable)]
     pub fn deposit(&mut self) -> U256 {
+        self.deposit_internal(U256::zero())
+    }
+
+    /// Deposit CSPR, reverting if fewer than `min_shares` stCSPR would be minted
+    ///
+    /// Protects against R rising (e.g. a rewards sync landing first) between
+    /// quoting `convert_to_shares` and execution.
+    ///
+    /// # Returns
+    /// * Amount of stCSPR minted
+    #[odra(payable)]
+    pub fn deposit_with_min_shares(&mut self, min_shares: U256) -> U256 {
+        self.deposit_internal(min_shares)
+    }
+
+    fn deposit_internal(&mut self, min_shares: U256) -> U256 {
         // Check deposits not paused
         let config = self.config.get().unwrap();
         if config.deposits_paused {
-            self.env().revert(CdpError::SafeModeActive);
+            self.env().revert(CdpError::LstDepositsPaused);
         }
 
         let caller = self.env().caller();
@@ -363,12 +436,20 @@ impl ScsprYbToken {
 
         // Validate minimum deposit
         if cspr_amount < U256::from(MIN_DEPOSIT) {
-            self.env().revert(CdpError::BelowMinDebt);
+            self.env().revert(CdpError::LstBelowMinAmount);
+        }
+
+        // Enforce deposit cap
+        if cspr_amount > self.max_deposit(caller) {
+            self.env().revert(CdpError::LstDepositCapExceeded);
         }
 
         // Calculate shares to mint: shares = assets / R = assets * total_shares / total_assets
         // Note: convert_to_shares handles first deposit (returns 1:1 when total_shares is zero)
         let shares_to_mint = self.convert_to_shares(cspr_amount);
+        if let Err(err) = check_min_shares(shares_to_mint, min_shares) {
+            self.env().revert(err);
+        }
 
         // Update assets (add to idle)
         let mut assets = self.assets.get().unwrap_or_default();
@@ -420,6 +501,28 @@ impl ScsprYbToken {
         rate
     }
 
+    /// NAV breakdown net of withdrawal-queue liabilities
+    ///
+    /// Pending assets are the queue's quoted, not-yet-claimed payouts (zero if
+    /// no queue is set); `free_nav` is the backing left after honouring them.
+    pub fn get_nav_report(&self) -> NavReport {
+        let total_assets = self.total_assets();
+        let pending = match self.withdraw_queue.get().flatten() {
+            Some(queue) => {
+                let stats_call = CallDef::new("get_stats", false, runtime_args! {});
+                let stats: QueueStats = self.env().call_contract(queue, stats_call);
+                pending_withdrawal_assets(&stats)
+            }
+            None => U256::zero(),
+        };
+        NavReport {
+            total_assets,
+            total_shares: self.total_shares(),
+            pending_withdrawal_assets: pending,
+            free_nav: free_nav(total_assets, pending),
+        }
+    }
+
     /// Convert shares to assets: assets = shares * R
     pub fn convert_to_assets(&self, shares: U256) -> U256 {
         let total_assets = self.total_assets();
@@ -429,25 +532,41 @@ impl ScsprYbToken {
             return shares; // 1:1 if no shares
         }
 
-        // assets = shares * total_assets / total_shares
-        shares * total_assets / total_shares
+        // assets = shares * total_assets / total_shares, paid out so rounded down
+        mul_div_floor(shares, total_assets, total_shares)
     }
 
     /// Convert assets to shares: shares = assets / R
     pub fn convert_to_shares(&self, assets: U256) -> U256 {
-        let total_assets = self.total_assets();
-        let total_shares = self.total_shares();
+        shares_for_assets(assets, self.total_assets(), self.total_shares())
+    }
 
-        if total_shares.is_zero() {
-            return assets; // 1:1 if no shares
-        }
+    /// Maximum CSPR `_owner` can deposit right now
+    ///
+    /// Remaining room under the deposit cap, `U256::MAX` if uncapped, and zero
+    /// while deposits are paused.
+    pub fn max_deposit(&self, _owner: Address) -> U256 {
+        let config = self.config.get().unwrap();
+        remaining_deposit_room(self.get_deposit_cap(), self.total_assets(), config.deposits_paused)
+    }
 
-        if total_assets.is_zero() {
-            return U256::zero();
-        }
+    /// Maximum CSPR `owner` can withdraw through instant paths
+    ///
+    /// The asset value of the owner's shares, bounded by the idle + claimable
+    /// buffer; zero while withdrawals are paused.
+    pub fn max_withdraw(&self, owner: Address) -> U256 {
+        let config = self.config.get().unwrap();
+        let assets = self.assets.get().unwrap_or_default();
+        instant_withdraw_limit(
+            self.convert_to_assets(self.balance_of(owner)),
+            assets.idle_cspr + assets.claimable_cspr,
+            config.withdrawals_paused,
+        )
+    }
 
-        // shares = assets * total_shares / total_assets
-        assets * total_shares / total_assets
+    /// Get deposit cap (zero = uncapped)
+    pub fn get_deposit_cap(&self) -> U256 {
+        self.deposit_cap.get().unwrap_or(U256::zero())
     }
 
     /// Get asset breakdown
@@ -484,13 +603,35 @@ impl ScsprYbToken {
     ) {
         self.require_operator();
 
+        let previous_rate = self.get_exchange_rate();
         let mut assets = self.assets.get().unwrap_or_default();
         assets.delegated_cspr = delegated;
         assets.undelegating_cspr = undelegating;
         assets.claimable_cspr = claimable;
+
+        // Clamp R increases beyond the rate limit by holding back delegated assets;
+        // the next sync reports the full amount again and catches up within the limit
+        let total_shares = self.total_shares();
+        let now = self.env().get_block_time();
+        if !total_shares.is_zero() {
+            let reported_rate = assets.total() * U256::from(SCALE) / total_shares;
+            let elapsed = now.saturating_sub(self.get_last_sync_timestamp());
+            let (max_increase_bps, interval) = self.get_rate_increase_limit();
+            let applied_rate = clamp_rate_increase(previous_rate, reported_rate, elapsed, max_increase_bps, interval);
+            if applied_rate < reported_rate {
+                let max_total = applied_rate * total_shares / U256::from(SCALE);
+                let excess = assets.total() - max_total;
+                assets.delegated_cspr = assets.delegated_cspr - excess.min(assets.delegated_cspr);
+                self.env().emit_event(RateIncreaseClamped {
+                    previous_rate,
+                    reported_rate,
+                    applied_rate,
+                });
+            }
+        }
         self.assets.set(assets);
 
-        self.last_sync_timestamp.set(self.env().get_block_time());
+        self.last_sync_timestamp.set(now);
     }
 
     /// Record realized loss from slashing (operator only)
@@ -502,6 +643,22 @@ impl ScsprYbToken {
         self.assets.set(assets);
     }
 
+    /// Return orphaned claimable CSPR to idle after withdrawal requests are
+    /// cancelled (operator only)
+    ///
+    /// CSPR undelegated for a request that was then cancelled has no claim
+    /// waiting on it; moving it to idle lets it back into deposits and
+    /// delegation for all shareholders. NAV is unchanged.
+    pub fn rebalance_after_cancel(&mut self, amount: U256) {
+        self.require_operator();
+
+        let mut assets = self.assets.get().unwrap_or_default();
+        if let Err(err) = assets.release_claimable(amount) {
+            self.env().revert(err);
+        }
+        self.assets.set(assets);
+    }
+
     /// Withdraw idle CSPR to operator for delegation (operator only)
     ///
     /// Returns the amount withdrawn.
@@ -510,7 +667,7 @@ impl ScsprYbToken {
 
         let mut assets = self.assets.get().unwrap_or_default();
         if assets.idle_cspr < amount {
-            self.env().revert(CdpError::InsufficientCollateral);
+            self.env().revert(CdpError::LstInsufficientIdle);
         }
 
         // Move from idle to delegated (operator will actually delegate)
@@ -558,6 +715,23 @@ impl ScsprYbToken {
         self.burn_internal(owner, amount);
     }
 
+    /// Transfer idle CSPR to user (called by withdraw queue during emergency claims)
+    ///
+    /// Unlike `transfer_cspr_to_user`, never touches claimable CSPR, which
+    /// backs matured requests.
+    pub fn transfer_idle_cspr_to_user(&mut self, recipient: Address, amount: U256) {
+        self.require_withdraw_queue();
+
+        let mut assets = self.assets.get().unwrap_or_default();
+        if assets.idle_cspr < amount {
+            self.env().revert(CdpError::LstInsufficientIdle);
+        }
+        assets.idle_cspr -= amount;
+        self.assets.set(assets);
+
+        self.env().transfer_tokens(&recipient, &u256_to_u512(amount));
+    }
+
     /// Transfer CSPR to user (called by withdraw queue during claim)
     pub fn transfer_cspr_to_user(&mut self, recipient: Address, amount: U256) {
         self.require_withdraw_queue();
@@ -572,7 +746,7 @@ impl ScsprYbToken {
             let from_idle = amount - from_claimable;
 
             if assets.idle_cspr < from_idle {
-                self.env().revert(CdpError::InsufficientCollateral);
+                self.env().revert(CdpError::LstInsufficientClaimable);
             }
 
             assets.claimable_cspr = U256::zero();
@@ -617,6 +791,34 @@ impl ScsprYbToken {
         self.config.set(config);
     }
 
+    /// Set maximum total assets accepted by deposits (admin only, zero = uncapped)
+    pub fn set_deposit_cap(&mut self, cap: U256) {
+        self.require_admin();
+        self.deposit_cap.set(cap);
+    }
+
+    /// Set the exchange-rate increase limit (admin only)
+    ///
+    /// A sync may raise R by at most `max_rate_increase_bps` per elapsed
+    /// `min_sync_interval_seconds`; a sync sooner than one interval after the
+    /// previous one cannot raise R at all. Zero bps disables the limit.
+    pub fn set_rate_increase_limit(&mut self, max_rate_increase_bps: u32, min_sync_interval_seconds: u64) {
+        self.require_admin();
+        if max_rate_increase_bps != 0 && min_sync_interval_seconds == 0 {
+            self.env().revert(CdpError::InvalidConfig);
+        }
+        self.max_rate_increase_bps.set(max_rate_increase_bps);
+        self.min_sync_interval_seconds.set(min_sync_interval_seconds);
+    }
+
+    /// Get the exchange-rate increase limit: (max bps per interval, interval seconds)
+    pub fn get_rate_increase_limit(&self) -> (u32, u64) {
+        (
+            self.max_rate_increase_bps.get().unwrap_or(0),
+            self.min_sync_interval_seconds.get().unwrap_or(0),
+        )
+    }
+
     /// Update operator address (admin only)
     pub fn set_operator(&mut self, new_operator: Address) {
         self.require_admin();
@@ -910,6 +1112,76 @@ fn u256_to_u512(value: U256) -> U512 {
     U512::from_little_endian(&bytes)
 }
 
+/// Shares minted for `assets`: assets * total_shares / total_assets (1:1 when no shares exist)
+///
+/// Rounds down, so a deposit never mints more than its assets are worth.
+pub fn shares_for_assets(assets: U256, total_assets: U256, total_shares: U256) -> U256 {
+    if total_shares.is_zero() {
+        return assets;
+    }
+    if total_assets.is_zero() {
+        return U256::zero();
+    }
+    mul_div_floor(assets, total_shares, total_assets)
+}
+
+/// Reject a deposit minting fewer shares than the caller's minimum
+pub fn check_min_shares(shares: U256, min_shares: U256) -> Result<(), CdpError> {
+    if shares < min_shares {
+        Err(CdpError::SlippageExceeded)
+    } else {
+        Ok(())
+    }
+}
+
+/// CSPR committed to queued withdrawals (pending and claimable, at quoted rates)
+pub fn pending_withdrawal_assets(stats: &QueueStats) -> U256 {
+    stats.total_pending_assets + stats.total_claimable_assets
+}
+
+/// Backing not committed to queued withdrawals
+pub fn free_nav(total_assets: U256, pending_withdrawal_assets: U256) -> U256 {
+    total_assets.saturating_sub(pending_withdrawal_assets)
+}
+
+/// Remaining deposit room under a cap (zero cap = uncapped)
+pub fn remaining_deposit_room(cap: U256, total_assets: U256, paused: bool) -> U256 {
+    if paused {
+        return U256::zero();
+    }
+    if cap.is_zero() {
+        return U256::MAX;
+    }
+    cap.saturating_sub(total_assets)
+}
+
+/// Instant withdrawal limit: owner's asset value bounded by the liquid buffer
+pub fn instant_withdraw_limit(owner_assets: U256, liquid_buffer: U256, paused: bool) -> U256 {
+    if paused {
+        return U256::zero();
+    }
+    owner_assets.min(liquid_buffer)
+}
+
+/// Highest R a sync may apply given the rate limit
+///
+/// Allows `max_increase_bps` of `previous_rate` per full `interval` elapsed
+/// since the last sync. Decreases always apply; zero bps disables the limit.
+pub fn clamp_rate_increase(
+    previous_rate: U256,
+    reported_rate: U256,
+    elapsed: u64,
+    max_increase_bps: u32,
+    interval: u64,
+) -> U256 {
+    if max_increase_bps == 0 || interval == 0 || reported_rate <= previous_rate {
+        return reported_rate;
+    }
+    let intervals = U256::from(elapsed / interval);
+    let max_increase = previous_rate * U256::from(max_increase_bps) * intervals / U256::from(BPS_SCALE);
+    reported_rate.min(previous_rate + max_increase)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -956,4 +1228,147 @@ mod tests {
     fn test_scale_constant() {
         assert_eq!(SCALE, 1_000_000_000_000_000_000);
     }
+
+    #[test]
+    fn test_rate_increase_limit() {
+        let rate = |bps: u64| U256::from(SCALE) * U256::from(bps) / U256::from(BPS_SCALE as u64);
+        let day = 86_400;
+
+        // 0.5% per day: a normal 0.02% daily rewards sync passes untouched
+        assert_eq!(clamp_rate_increase(rate(10_000), rate(10_002), day, 50, day), rate(10_002));
+
+        // A 20% spike is clamped to the daily allowance
+        assert_eq!(clamp_rate_increase(rate(10_000), rate(12_000), day, 50, day), rate(10_050));
+
+        // The allowance accrues per full interval; syncing early allows no increase
+        assert_eq!(clamp_rate_increase(rate(10_000), rate(12_000), 3 * day, 50, day), rate(10_150));
+        assert_eq!(clamp_rate_increase(rate(10_000), rate(10_002), day - 1, 50, day), rate(10_000));
+
+        // Decreases (slashing) and a disabled limit always apply
+        assert_eq!(clamp_rate_increase(rate(10_000), rate(9_000), 0, 50, day), rate(9_000));
+        assert_eq!(clamp_rate_increase(rate(10_000), rate(12_000), 0, 0, day), rate(12_000));
+    }
+
+    #[test]
+    fn test_deposit_min_shares_trips_on_rate_increase() {
+        let cspr = |n: u64| U256::from(n) * U256::from(1_000_000_000u64);
+        let deposit = cspr(1_000);
+
+        // Quoted at R = 1.0: 1000 CSPR buys 1000 stCSPR
+        let quoted = shares_for_assets(deposit, cspr(100_000), cspr(100_000));
+        assert_eq!(quoted, deposit);
+        assert_eq!(check_min_shares(quoted, quoted), Ok(()));
+
+        // A rewards sync lands first and lifts R to 1.01: fewer shares, guard trips
+        let executed = shares_for_assets(deposit, cspr(101_000), cspr(100_000));
+        assert!(executed < quoted);
+        assert_eq!(check_min_shares(executed, quoted), Err(CdpError::SlippageExceeded));
+
+        // A 1% tolerance absorbs the same move; zero minimum never trips
+        assert_eq!(check_min_shares(executed, quoted * U256::from(99u64) / U256::from(100u64)), Ok(()));
+        assert_eq!(check_min_shares(executed, U256::zero()), Ok(()));
+    }
+
+    #[test]
+    fn test_pending_withdrawal_reduces_free_nav() {
+        let total_assets = U256::from(10_000u64);
+        let mut stats = QueueStats::default();
+
+        // Empty queue: all backing is free
+        assert_eq!(free_nav(total_assets, pending_withdrawal_assets(&stats)), total_assets);
+
+        // A withdrawal quoted at 1_500 CSPR encumbers exactly that much
+        stats.total_pending_shares = U256::from(1_200u64);
+        stats.total_pending_assets = U256::from(1_500u64);
+        stats.pending_count = 1;
+        assert_eq!(pending_withdrawal_assets(&stats), U256::from(1_500u64));
+        assert_eq!(free_nav(total_assets, pending_withdrawal_assets(&stats)), U256::from(8_500u64));
+
+        // Liabilities above backing floor free NAV at zero
+        assert_eq!(free_nav(total_assets, U256::from(12_000u64)), U256::zero());
+    }
+
+    #[test]
+    fn test_rebalance_after_cancel_conserves_total_assets() {
+        // 300 CSPR was undelegated for a withdrawal request, which was then cancelled
+        let mut assets = AssetBreakdown {
+            idle_cspr: U256::from(100u64),
+            delegated_cspr: U256::from(700u64),
+            undelegating_cspr: U256::zero(),
+            claimable_cspr: U256::from(300u64),
+            protocol_fees: U256::from(10u64),
+            realized_losses: U256::zero(),
+        };
+        let total_before = assets.total();
+
+        assert_eq!(assets.release_claimable(U256::from(300u64)), Ok(()));
+        assert_eq!(assets.total(), total_before);
+        assert_eq!(assets.idle_cspr, U256::from(400u64));
+        assert!(assets.claimable_cspr.is_zero());
+
+        // Cannot release more than is claimable
+        assert_eq!(assets.release_claimable(U256::one()), Err(CdpError::LstInsufficientClaimable));
+        assert_eq!(assets.total(), total_before);
+    }
+
+    #[test]
+    fn test_max_deposit_capped() {
+        let cap = U256::from(10_000u64);
+        assert_eq!(remaining_deposit_room(cap, U256::from(7_500u64), false), U256::from(2_500u64));
+        // Already over the cap (e.g. after rewards)
+        assert_eq!(remaining_deposit_room(cap, U256::from(12_000u64), false), U256::zero());
+    }
+
+    #[test]
+    fn test_max_deposit_uncapped() {
+        assert_eq!(remaining_deposit_room(U256::zero(), U256::from(7_500u64), false), U256::MAX);
+    }
+
+    #[test]
+    fn test_max_deposit_and_withdraw_paused() {
+        assert_eq!(remaining_deposit_room(U256::zero(), U256::zero(), true), U256::zero());
+        assert_eq!(
+            instant_withdraw_limit(U256::from(500u64), U256::from(1_000u64), true),
+            U256::zero()
+        );
+    }
+
+    #[test]
+    fn test_max_withdraw_bounded_by_buffer() {
+        // Owner holds 500 CSPR worth of shares but only 300 is idle + claimable
+        assert_eq!(
+            instant_withdraw_limit(U256::from(500u64), U256::from(300u64), false),
+            U256::from(300u64)
+        );
+        assert_eq!(
+            instant_withdraw_limit(U256::from(200u64), U256::from(300u64), false),
+            U256::from(200u64)
+        );
+    }
+
+    #[test]
+    fn test_upgrade_backfill_keeps_assets_and_defaults_cap() {
+        // Existing asset accounting is never replaced
+        let assets = AssetBreakdown {
+            idle_cspr: U256::from(100u64),
+            ..AssetBreakdown::default()
+        };
+        assert!(backfill(Some(assets), AssetBreakdown::default()).is_none());
+
+        // A deposit cap added after deployment defaults to uncapped
+        let cap = backfill(None, U256::zero()).unwrap();
+        assert_eq!(remaining_deposit_room(cap, U256::from(1_000u64), false), U256::MAX);
+    }
+
+    #[test]
+    fn test_cep18_balance_key_matches_explorer_encoding() {
+        // Balances are mirrored under base64(Key bytes), as CEP-18 explorers expect
+        let owner = Address::Account(AccountHash::new([7u8; 32]));
+        let key = ScsprYbToken::cep18_balance_key(owner);
+        let expected = BASE64_STANDARD.encode(Key::from(owner).to_bytes().unwrap());
+        assert_eq!(key, expected);
+
+        // Dictionary item keys are limited to 64 bytes
+        assert!(key.len() <= 64);
+    }
 }
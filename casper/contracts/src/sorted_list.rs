@@ -0,0 +1,268 @@
+//! Integrity checks and ranking helpers for the branch sorted vault list.
+//!
+//! Branches keep a doubly-linked list of vaults ordered by ascending interest
+//! rate, oldest vault first on equal rates (redemption order). The helpers here validate the links independently
+//! of storage so the same check is shared by both branches and unit tests, and
+//! compute the links a rebuild writes back.
+
+use odra::prelude::*;
+use crate::types::VaultKey;
+
+/// Storage-independent view of a sorted list node
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct LinkView {
+    /// Interest rate in bps used for ordering
+    pub interest_rate_bps: u32,
+    /// Previous node (lower or equal rate)
+    pub prev: Option<VaultKey>,
+    /// Next node (higher or equal rate)
+    pub next: Option<VaultKey>,
+}
+
+/// Verify a sorted list walking head→tail and tail→head
+///
+/// Checks that:
+/// - head has no prev and tail has no next
+/// - every `next` link is mirrored by the successor's `prev`
+/// - rates are monotonically non-decreasing from head to tail
+/// - both walks visit exactly `expected_len` nodes (guards against cycles)
+///
+/// # Arguments
+/// * `head` - List head (lowest rate)
+/// * `tail` - List tail (highest rate)
+/// * `expected_len` - Number of active vaults that must be linked
+/// * `lookup` - Node accessor
+pub fn verify_links<F>(
+    head: Option<VaultKey>,
+    tail: Option<VaultKey>,
+    expected_len: u64,
+    lookup: F,
+) -> bool
+where
+    F: Fn(&VaultKey) -> Option<LinkView>,
+{
+    if head.is_none() || tail.is_none() {
+        return head.is_none() && tail.is_none() && expected_len == 0;
+    }
+
+    // Forward walk: head -> tail
+    let mut visited = 0u64;
+    let mut prev: Option<VaultKey> = None;
+    let mut prev_rate = 0u32;
+    let mut current = head;
+    while let Some(key) = current {
+        visited += 1;
+        if visited > expected_len {
+            return false;
+        }
+        let link = match lookup(&key) {
+            Some(l) => l,
+            None => return false,
+        };
+        if link.prev != prev || link.interest_rate_bps < prev_rate {
+            return false;
+        }
+        prev = Some(key);
+        prev_rate = link.interest_rate_bps;
+        current = link.next;
+    }
+    if visited != expected_len || prev != tail {
+        return false;
+    }
+
+    // Backward walk: tail -> head
+    let mut visited = 0u64;
+    let mut next: Option<VaultKey> = None;
+    let mut next_rate = u32::MAX;
+    let mut current = tail;
+    while let Some(key) = current {
+        visited += 1;
+        if visited > expected_len {
+            return false;
+        }
+        let link = match lookup(&key) {
+            Some(l) => l,
+            None => return false,
+        };
+        if link.next != next || link.interest_rate_bps > next_rate {
+            return false;
+        }
+        next = Some(key);
+        next_rate = link.interest_rate_bps;
+        current = link.prev;
+    }
+
+    visited == expected_len && next == head
+}
+
+/// Order scanned vaults by ascending ICR and keep the first `max_count`
+///
+/// Ties keep scan order (lower interest rate first when scanning the list).
+pub fn lowest_icr_first(mut entries: Vec<(VaultKey, u32)>, max_count: u32) -> Vec<(VaultKey, u32)> {
+    entries.sort_by_key(|(_, icr_bps)| *icr_bps);
+    entries.truncate(max_count as usize);
+    entries
+}
+
+/// Link active vaults `(key, rate bps, created_at)` into a fresh sorted list
+///
+/// Returns the nodes head first with their new links. The sort is stable, so
+/// identical ranks keep input order, as repeated inserts would.
+pub fn relink(mut nodes: Vec<(VaultKey, u32, u64)>) -> Vec<(VaultKey, LinkView)> {
+    nodes.sort_by_key(|(_, rate, created_at)| (*rate, *created_at));
+    let keys: Vec<VaultKey> = nodes.iter().map(|(key, _, _)| *key).collect();
+    nodes
+        .iter()
+        .enumerate()
+        .map(|(i, (key, rate, _))| {
+            let prev = if i == 0 { None } else { Some(keys[i - 1]) };
+            let next = keys.get(i + 1).copied();
+            (*key, LinkView { interest_rate_bps: *rate, prev, next })
+        })
+        .collect()
+}
+
+/// Whether a vault ranked `(rate bps, created_at)` is redeemed before `other`
+///
+/// Lower rate first; on equal rates the older vault goes first, so a new
+/// vault can't jump ahead of (or hide behind) existing ones at its rate.
+/// Identical ranks keep insertion order.
+pub fn redeemed_before(rank: (u32, u64), other: (u32, u64)) -> bool {
+    rank < other
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use odra::casper_types::account::AccountHash;
+
+    fn key(id: u64) -> VaultKey {
+        VaultKey { owner: Address::Account(AccountHash::default()), id }
+    }
+
+    /// Link keys in the given order (callers pass ascending rates)
+    fn build(nodes: &[(u64, u32)]) -> Vec<(VaultKey, LinkView)> {
+        let mut map = Vec::new();
+        for (i, (id, rate)) in nodes.iter().enumerate() {
+            let prev = if i == 0 { None } else { Some(key(nodes[i - 1].0)) };
+            let next = nodes.get(i + 1).map(|(n, _)| key(*n));
+            map.push((key(*id), LinkView { interest_rate_bps: *rate, prev, next }));
+        }
+        map
+    }
+
+    fn link_mut(map: &mut [(VaultKey, LinkView)], id: u64) -> &mut LinkView {
+        &mut map.iter_mut().find(|(k, _)| k.id == id).unwrap().1
+    }
+
+    fn verify(map: &[(VaultKey, LinkView)], head: u64, tail: u64, len: u64) -> bool {
+        verify_links(Some(key(head)), Some(key(tail)), len, |k| {
+            map.iter().find(|(key, _)| key == k).map(|(_, link)| *link)
+        })
+    }
+
+    #[test]
+    fn test_empty_list_is_valid() {
+        assert!(verify_links(None, None, 0, |_| None));
+        assert!(!verify_links(None, None, 1, |_| None));
+    }
+
+    #[test]
+    fn test_consistent_list_is_valid() {
+        let map = build(&[(1, 100), (2, 200), (3, 200), (4, 500)]);
+        assert!(verify(&map, 1, 4, 4));
+    }
+
+    #[test]
+    fn test_broken_prev_link_detected() {
+        let mut map = build(&[(1, 100), (2, 200), (3, 300)]);
+        link_mut(&mut map, 3).prev = Some(key(1));
+        assert!(!verify(&map, 1, 3, 3));
+    }
+
+    #[test]
+    fn test_rate_order_violation_detected() {
+        let mut map = build(&[(1, 100), (2, 200), (3, 300)]);
+        link_mut(&mut map, 2).interest_rate_bps = 50;
+        assert!(!verify(&map, 1, 3, 3));
+    }
+
+    #[test]
+    fn test_cycle_detected() {
+        let mut map = build(&[(1, 100), (2, 200), (3, 300)]);
+        link_mut(&mut map, 3).next = Some(key(1));
+        assert!(!verify(&map, 1, 3, 3));
+    }
+
+    #[test]
+    fn test_missing_vault_detected() {
+        let map = build(&[(1, 100), (2, 200)]);
+        assert!(!verify(&map, 1, 2, 3));
+    }
+
+    #[test]
+    fn test_corrupted_list_repaired_by_relinking() {
+        let mut map = build(&[(1, 100), (2, 200), (3, 300)]);
+        link_mut(&mut map, 2).next = None;
+        assert!(!verify(&map, 1, 3, 3));
+
+        // The branch rebuild relinks every active vault, whatever order the
+        // vaults map yields them in
+        let nodes = vec![(key(3), 300, 3), (key(1), 100, 1), (key(2), 200, 2)];
+        let repaired = relink(nodes);
+        assert_eq!(repaired.first().map(|(k, _)| k.id), Some(1));
+        assert_eq!(repaired.last().map(|(k, _)| k.id), Some(3));
+        assert!(verify(&repaired, 1, 3, 3));
+    }
+
+    #[test]
+    fn test_riskiest_vaults_icr_ascending() {
+        let scanned = vec![(key(1), 15000), (key(2), 11500), (key(3), 30000), (key(4), 11200)];
+        let riskiest = lowest_icr_first(scanned, 3);
+
+        let ids: Vec<u64> = riskiest.iter().map(|(k, _)| k.id).collect();
+        assert_eq!(ids, vec![4, 2, 1]);
+        assert!(riskiest.windows(2).all(|w| w[0].1 <= w[1].1));
+    }
+
+    #[test]
+    fn test_riskiest_vaults_ties_keep_scan_order() {
+        let scanned = vec![(key(1), 12000), (key(2), 12000), (key(3), 11000)];
+        let ids: Vec<u64> = lowest_icr_first(scanned, 10).iter().map(|(k, _)| k.id).collect();
+        assert_eq!(ids, vec![3, 1, 2]);
+    }
+
+    #[test]
+    fn test_equal_rate_ties_redeem_oldest_first() {
+        // Insert like the branch: before the first node the new vault precedes
+        fn insert(list: &mut Vec<(u64, (u32, u64))>, id: u64, rank: (u32, u64)) {
+            let at = list.iter().position(|(_, other)| redeemed_before(rank, *other)).unwrap_or(list.len());
+            list.insert(at, (id, rank));
+        }
+        let ids = |list: &[(u64, (u32, u64))]| list.iter().map(|(id, _)| *id).collect::<Vec<u64>>();
+
+        // Vault 1 (t=100) and vault 2 (t=200) share 5%; vault 3 is cheaper
+        let mut list = Vec::new();
+        insert(&mut list, 1, (500, 100));
+        insert(&mut list, 2, (500, 200));
+        insert(&mut list, 3, (300, 300));
+        assert_eq!(ids(&list), vec![3, 1, 2]);
+
+        // Re-inserting the older vault (rate change back to 5%) doesn't move it
+        // behind the younger one, whatever the insertion order
+        list.retain(|(id, _)| *id != 1);
+        insert(&mut list, 1, (500, 100));
+        assert_eq!(ids(&list), vec![3, 1, 2]);
+
+        // A rebuild in any order yields the same list
+        let mut rebuilt = Vec::new();
+        insert(&mut rebuilt, 2, (500, 200));
+        insert(&mut rebuilt, 3, (300, 300));
+        insert(&mut rebuilt, 1, (500, 100));
+        assert_eq!(ids(&rebuilt), vec![3, 1, 2]);
+
+        // Rate still dominates age
+        assert!(redeemed_before((400, 900), (500, 100)));
+        assert!(!redeemed_before((500, 100), (500, 100)));
+    }
+}
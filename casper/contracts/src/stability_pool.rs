@@ -10,14 +10,17 @@
 //! - Product-sum algorithm for efficient gain tracking (inspired by Liquity)
 //!
 //! Safe mode restrictions:
-//! - Deposits: ALLOWED (always)
+//! - Deposits: ALLOWED (unless an operator has paused deposits)
 //! - Withdrawals: BLOCKED when safe_mode is active
 
 use odra::prelude::*;
 use odra::casper_types::{U256, U512, RuntimeArgs, runtime_args};
 use odra::CallDef;
-use crate::types::{CollateralId, OracleStatus, SafeModeState};
+use crate::types::{CollateralId, ComponentId, OracleStatus, PriceData, SafeModeState};
+use crate::math::{collateral_to_value, mul_div_floor};
+use crate::registry::{self, resolve_all};
 use crate::errors::CdpError;
+use crate::reentrancy::check_not_entered;
 
 /// gUSD stablecoin interface for cross-contract calls
 #[odra::external_contract]
@@ -40,9 +43,29 @@ const SCALE: u64 = 1_000_000_000_000_000_000;
 /// Scale factor for product/sum algorithm (1e9)
 const SCALE_FACTOR: u64 = 1_000_000_000;
 
+/// Default product threshold below which P is rescaled (SCALE / SCALE_FACTOR)
+const DEFAULT_SCALE_RESET_THRESHOLD: u64 = 1_000_000_000;
+
 /// Minimum deposit amount to prevent dust
 const MIN_DEPOSIT: u64 = 1_000_000; // 0.000001 gUSD (with 18 decimals this is ~1e12)
 
+/// Default lock after each deposit before withdraw/claim (off until governance sets one)
+const DEFAULT_MIN_DEPOSIT_LOCK_SECONDS: u64 = 0;
+
+/// Maximum configurable deposit lock (1 day)
+const MAX_DEPOSIT_LOCK_SECONDS: u64 = 86400;
+
+/// Components pulled from the registry by `refresh_addresses`
+pub const REGISTRY_DEPENDENCIES: [ComponentId; 7] = [
+    ComponentId::Router,
+    ComponentId::Oracle,
+    ComponentId::Stablecoin,
+    ComponentId::LiquidationEngine,
+    ComponentId::RedemptionEngine,
+    ComponentId::ScsprToken,
+    ComponentId::ScsprYbToken,
+];
+
 /// Depositor's snapshot at time of deposit/compounding
 #[odra::odra_type]
 #[derive(Default)]
@@ -86,6 +109,20 @@ pub struct PoolStats {
     pub depositor_count: u64,
 }
 
+/// Depositor position as realized by withdraw/claim_gains
+#[odra::odra_type]
+#[derive(Default)]
+pub struct DepositorPosition {
+    /// Compounded deposit (gUSD remaining after absorbed debt)
+    pub compounded_deposit: U256,
+    /// Unclaimed CSPR collateral gains
+    pub cspr_gain: U256,
+    /// Unclaimed stCSPR collateral gains
+    pub scspr_gain: U256,
+    /// Timestamp until which withdraw/claim_gains are locked
+    pub locked_until: u64,
+}
+
 /// Product-sum algorithm state (consolidated)
 #[odra::odra_type]
 #[derive(Default)]
@@ -139,8 +176,35 @@ pub struct StabilityPool {
     // === Depositor State & Access Control ===
     /// Depositor snapshots
     deposits: Mapping<Address, DepositSnapshot>,
+    /// State beyond the core fields (nested: a module holds at most 15 fields)
+    state: SubModule<StabilityPoolState>,
+}
+
+/// Stability pool state added on top of the core deposit storage
+#[odra::module]
+pub struct StabilityPoolState {
     /// Safe mode state
     safe_mode: Var<SafeModeState>,
+    /// Redemption engine contract address (source of redemption fee gains)
+    redemption_engine: Var<Address>,
+    /// ybToken that `claim_and_compound` stakes CSPR gains into
+    compound_target: Var<Address>,
+    /// Oracle adapter used to value collateral in USD
+    oracle_adapter: Var<Address>,
+    /// P below this is multiplied by SCALE_FACTOR and the scale bumped
+    scale_reset_threshold: Var<U256>,
+    /// Timestamp until which each depositor's funds are locked
+    deposit_locked_until: Mapping<Address, u64>,
+    /// Lock applied after each deposit (deters just-in-time liquidation sniping)
+    min_deposit_lock_seconds: Var<u64>,
+    /// Cap on a single depositor's compounded deposit in gUSD (0 = unlimited)
+    max_deposit_per_user: Var<U256>,
+    /// Operator halt on new deposits, independent of safe mode
+    deposits_paused: Var<bool>,
+    /// Depositors who opted into converting collateral gains into deposit
+    auto_compound: Mapping<Address, bool>,
+    /// Reentrancy guard for external transfer sections
+    reentrancy_lock: Var<bool>,
 }
 
 #[odra::module]
@@ -175,7 +239,7 @@ impl StabilityPool {
         });
 
         // Initialize safe mode
-        self.safe_mode.set(SafeModeState {
+        self.state.safe_mode.set(SafeModeState {
             is_active: false,
             triggered_at: 0,
             reason: OracleStatus::Ok,
@@ -193,10 +257,14 @@ impl StabilityPool {
     /// Deposit gUSD to the stability pool
     /// Note: Caller must have approved the pool to spend gUSD
     pub fn deposit(&mut self, amount: U256) {
-        // Deposits are ALWAYS allowed (even in safe mode)
+        // Deposits are allowed even in safe mode; only an operator pause stops them
+        if let Err(err) = check_deposits_open(self.is_deposits_paused()) {
+            self.env().revert(err);
+        }
         if amount < U256::from(MIN_DEPOSIT) {
-            self.env().revert(CdpError::BelowMinDebt);
+            self.env().revert(CdpError::SpBelowMinDeposit);
         }
+        self.enter_non_reentrant();
 
         let depositor = self.env().caller();
         let pool_address = self.env().self_address();
@@ -208,6 +276,9 @@ impl StabilityPool {
 
         // Calculate new deposit
         let new_deposit = existing_deposit + amount;
+        if let Err(err) = check_deposit_cap(new_deposit, self.get_max_deposit_per_user()) {
+            self.env().revert(err);
+        }
 
         // Update depositor count if new depositor
         if existing_snapshot.deposit.is_zero() && !new_deposit.is_zero() {
@@ -218,6 +289,13 @@ impl StabilityPool {
         // Store new snapshot
         self.store_snapshot(depositor, new_deposit);
 
+        // (Re)start the deposit lock
+        let locked_until = deposit_lock_expiry(
+            self.env().get_block_time(),
+            self.get_min_deposit_lock_seconds(),
+        );
+        self.state.deposit_locked_until.set(&depositor, locked_until);
+
         // Update total deposits
         let total = self.total_deposits.get().unwrap_or(U256::zero());
         self.total_deposits.set(total + amount);
@@ -237,6 +315,7 @@ impl StabilityPool {
 
         // Transfer pending collateral gains to depositor
         self.transfer_gains_internal(depositor, gains);
+        self.exit_non_reentrant();
     }
 
     /// Withdraw gUSD from the stability pool
@@ -245,12 +324,14 @@ impl StabilityPool {
         self.require_not_safe_mode();
 
         let depositor = self.env().caller();
+        self.require_deposit_unlocked(depositor);
+        self.enter_non_reentrant();
 
         // Get compounded deposit (accounting for debt absorption)
         let compounded_deposit = self.get_compounded_deposit(depositor);
 
-        if amount > compounded_deposit {
-            self.env().revert(CdpError::InsufficientCollateral);
+        if let Err(err) = check_withdraw_amount(amount, compounded_deposit) {
+            self.env().revert(err);
         }
 
         // Get pending gains
@@ -297,6 +378,7 @@ impl StabilityPool {
 
         // Transfer pending collateral gains to depositor
         self.transfer_gains_internal(depositor, gains);
+        self.exit_non_reentrant();
     }
 
     /// Claim collateral gains without modifying deposit
@@ -305,20 +387,82 @@ impl StabilityPool {
         self.require_not_safe_mode();
 
         let depositor = self.env().caller();
-        let gains = self.get_depositor_gains(depositor);
+        self.require_deposit_unlocked(depositor);
+        self.enter_non_reentrant();
+        let gains = self.checkpoint_gains(depositor);
 
-        if gains.cspr_gain.is_zero() && gains.scspr_gain.is_zero() {
-            self.env().revert(CdpError::SpNoGains);
+        // Transfer collateral gains to depositor
+        self.transfer_gains_internal(depositor, gains);
+        self.exit_non_reentrant();
+    }
+
+    /// Claim collateral gains, staking the CSPR portion into stCSPR
+    ///
+    /// CSPR gains are deposited into the compound target ybToken and the
+    /// minted stCSPR is sent to the depositor; stCSPR gains are paid out as in
+    /// `claim_gains`. Returns the stCSPR minted from CSPR gains.
+    pub fn claim_and_compound(&mut self) -> U256 {
+        // Claims BLOCKED in safe mode (treated as withdrawal)
+        self.require_not_safe_mode();
+
+        let depositor = self.env().caller();
+        self.require_deposit_unlocked(depositor);
+        self.enter_non_reentrant();
+        let gains = self.checkpoint_gains(depositor);
+
+        let (payout, cspr_to_stake) = split_for_compound(gains);
+        self.transfer_gains_internal(depositor, payout);
+
+        let minted = if cspr_to_stake.is_zero() {
+            U256::zero()
+        } else {
+            self.stake_cspr_gain(depositor, cspr_to_stake)
+        };
+        self.exit_non_reentrant();
+        minted
+    }
+
+    /// Opt in or out of auto-compounding collateral gains into the deposit
+    pub fn set_auto_compound(&mut self, enabled: bool) {
+        let depositor = self.env().caller();
+        self.state.auto_compound.set(&depositor, enabled);
+    }
+
+    /// Whether a depositor has opted into auto-compounding
+    pub fn is_auto_compound(&self, depositor: Address) -> bool {
+        self.state.auto_compound.get(&depositor).unwrap_or(false)
+    }
+
+    /// Convert an opted-in depositor's collateral gains into gUSD deposit
+    ///
+    /// Callable by anyone (keepers). The gains are sold to the treasury, which
+    /// prices them itself and pays gUSD out of pending fees; that gUSD is added
+    /// to the depositor's compounded deposit. Reverts unless the oracle price
+    /// of each collateral sold is fresh and Ok. `offset` can't walk
+    /// depositors, so keepers call this after liquidations. Returns the gUSD
+    /// added.
+    pub fn compound(&mut self, depositor: Address) -> U256 {
+        // Treated as a claim: BLOCKED in safe mode
+        self.require_not_safe_mode();
+        if !self.is_auto_compound(depositor) {
+            self.env().revert(CdpError::SpAutoCompoundDisabled);
         }
+        self.enter_non_reentrant();
+        let gains = self.checkpoint_gains(depositor);
 
-        // Update snapshot to current state (resets gains)
-        let compounded_deposit = self.get_compounded_deposit(depositor);
-        if !compounded_deposit.is_zero() {
-            self.store_snapshot(depositor, compounded_deposit);
+        let gusd = self.sell_gain_to_treasury(CollateralId::Cspr, gains.cspr_gain)
+            + self.sell_gain_to_treasury(CollateralId::SCSPR, gains.scspr_gain);
+
+        let new_deposit = self.get_compounded_deposit(depositor) + gusd;
+        if let Err(err) = check_deposit_cap(new_deposit, self.get_max_deposit_per_user()) {
+            self.env().revert(err);
         }
+        self.store_snapshot(depositor, new_deposit);
+        let total = self.total_deposits.get().unwrap_or(U256::zero());
+        self.total_deposits.set(total + gusd);
 
-        // Transfer collateral gains to depositor
-        self.transfer_gains_internal(depositor, gains);
+        self.exit_non_reentrant();
+        gusd
     }
 
     // ========== Liquidation Offset Functions ==========
@@ -395,6 +539,36 @@ impl StabilityPool {
         self.offset(coll_id, debt_to_offset, collateral_to_add)
     }
 
+    /// Credit collateral to depositors as a gain without absorbing debt
+    ///
+    /// Used for the SP share of redemption fees; only the redemption engine may
+    /// call it, and it transfers the collateral right after. Returns the amount
+    /// credited: zero when the pool is empty, so the caller can route the fee
+    /// elsewhere.
+    pub fn add_collateral_gain(&mut self, collateral_id: CollateralId, amount: U256) -> U256 {
+        self.require_redemption_engine();
+
+        let total = self.total_deposits.get().unwrap_or(U256::zero());
+        if total.is_zero() || amount.is_zero() {
+            return U256::zero();
+        }
+
+        // Zero debt leaves P unchanged; only the collateral sum moves
+        self.update_product_sum(collateral_id, U256::zero(), amount, total);
+
+        match collateral_id {
+            CollateralId::Cspr => {
+                let current = self.total_cspr_collateral.get().unwrap_or(U256::zero());
+                self.total_cspr_collateral.set(current + amount);
+            }
+            CollateralId::SCSPR => {
+                let current = self.total_scspr_collateral.get().unwrap_or(U256::zero());
+                self.total_scspr_collateral.set(current + amount);
+            }
+        }
+        amount
+    }
+
     /// Receive CSPR collateral during liquidation offset (payable)
     #[odra(payable)]
     pub fn receive_cspr_collateral(&mut self) {
@@ -466,15 +640,7 @@ impl StabilityPool {
 
         // Handle scale changes
         let scale_diff = state.scale.saturating_sub(snapshot.scale);
-
-        if scale_diff == 0 {
-            snapshot.deposit * state.p / snapshot_p
-        } else if scale_diff == 1 {
-            snapshot.deposit * state.p / snapshot_p / U256::from(SCALE_FACTOR)
-        } else {
-            // More than 1 scale difference means deposit is effectively zero
-            U256::zero()
-        }
+        compound_deposit(snapshot.deposit, snapshot_p, state.p, scale_diff)
     }
 
     /// Get depositor's pending collateral gains
@@ -555,6 +721,36 @@ impl StabilityPool {
         self.depositor_count.get().unwrap_or(0)
     }
 
+    /// Get the pool's total value in USD (18 decimals)
+    ///
+    /// gUSD deposits count at $1; CSPR and stCSPR collateral held by the pool
+    /// are valued at the oracle adapter's current prices.
+    pub fn get_pool_value_usd(&self) -> U256 {
+        let (cspr_price, scspr_price) = self.collateral_prices();
+        position_value_usd(
+            self.get_total_deposits(),
+            self.get_total_cspr_collateral(),
+            self.get_total_scspr_collateral(),
+            cspr_price,
+            scspr_price,
+        )
+    }
+
+    /// Get a depositor's total value in USD (18 decimals)
+    ///
+    /// Compounded deposit at $1 plus pending collateral gains at current prices.
+    pub fn get_depositor_value_usd(&self, depositor: Address) -> U256 {
+        let gains = self.get_depositor_gains(depositor);
+        let (cspr_price, scspr_price) = self.collateral_prices();
+        position_value_usd(
+            self.get_compounded_deposit(depositor),
+            gains.cspr_gain,
+            gains.scspr_gain,
+            cspr_price,
+            scspr_price,
+        )
+    }
+
     // ========== Frontend-Friendly User State Access ==========
 
     /// Get user's current deposit (primitive return for frontend)
@@ -580,6 +776,41 @@ impl StabilityPool {
         (deposit, gains.cspr_gain, gains.scspr_gain)
     }
 
+    /// Get depositor's full position in a single call
+    ///
+    /// Amounts match what `withdraw` / `claim_gains` would realize at this point.
+    pub fn get_depositor_position(&self, depositor: Address) -> DepositorPosition {
+        let gains = self.get_depositor_gains(depositor);
+        DepositorPosition {
+            compounded_deposit: self.get_compounded_deposit(depositor),
+            cspr_gain: gains.cspr_gain,
+            scspr_gain: gains.scspr_gain,
+            locked_until: self.get_deposit_locked_until(depositor),
+        }
+    }
+
+    /// Get timestamp until which a depositor cannot withdraw or claim
+    pub fn get_deposit_locked_until(&self, depositor: Address) -> u64 {
+        self.state.deposit_locked_until.get(&depositor).unwrap_or(0)
+    }
+
+    /// Get lock duration applied after each deposit
+    pub fn get_min_deposit_lock_seconds(&self) -> u64 {
+        self.state.min_deposit_lock_seconds.get().unwrap_or(DEFAULT_MIN_DEPOSIT_LOCK_SECONDS)
+    }
+
+    /// Get the per-user deposit cap in gUSD (0 = unlimited)
+    pub fn get_max_deposit_per_user(&self) -> U256 {
+        self.state.max_deposit_per_user.get().unwrap_or(U256::zero())
+    }
+
+    /// Get the product threshold that triggers a scale change
+    pub fn get_scale_reset_threshold(&self) -> U256 {
+        self.state.scale_reset_threshold
+            .get()
+            .unwrap_or(U256::from(DEFAULT_SCALE_RESET_THRESHOLD))
+    }
+
     /// Get registry address
     pub fn get_registry(&self) -> Option<Address> {
         self.registry.get()
@@ -594,17 +825,18 @@ impl StabilityPool {
 
     /// Trigger safe mode
     pub fn trigger_safe_mode(&mut self, reason: OracleStatus) {
-        self.safe_mode.set(SafeModeState {
+        let safe_mode = SafeModeState {
             is_active: true,
             triggered_at: self.env().get_block_time(),
             reason,
-        });
+        };
+        self.state.safe_mode.set(safe_mode);
     }
 
     /// Clear safe mode (admin only)
     pub fn clear_safe_mode(&mut self) {
         // TODO: Add admin access control
-        self.safe_mode.set(SafeModeState {
+        self.state.safe_mode.set(SafeModeState {
             is_active: false,
             triggered_at: 0,
             reason: OracleStatus::Ok,
@@ -613,7 +845,26 @@ impl StabilityPool {
 
     /// Check if safe mode is active
     pub fn is_safe_mode_active(&self) -> bool {
-        self.safe_mode.get().map(|s| s.is_active).unwrap_or(false)
+        self.state.safe_mode.get().map(|s| s.is_active).unwrap_or(false)
+    }
+
+    /// Halt new deposits, e.g. while responding to a critical bug (admin only)
+    ///
+    /// Withdrawals are unaffected and stay governed by safe mode.
+    pub fn pause_deposits(&mut self) {
+        // TODO: Add admin access control
+        self.state.deposits_paused.set(true);
+    }
+
+    /// Resume deposits (admin only)
+    pub fn unpause_deposits(&mut self) {
+        // TODO: Add admin access control
+        self.state.deposits_paused.set(false);
+    }
+
+    /// Check if deposits are paused
+    pub fn is_deposits_paused(&self) -> bool {
+        self.state.deposits_paused.get().unwrap_or(false)
     }
 
     // ========== Admin Functions ==========
@@ -624,11 +875,78 @@ impl StabilityPool {
         self.scspr_token.set(scspr_token);
     }
 
+    /// Pull all dependency addresses from the registry in one call (admin only)
+    ///
+    /// Components the registry doesn't know yet keep their current address;
+    /// the individual setters remain available as overrides.
+    pub fn refresh_addresses(&mut self) {
+        self.require_registry_admin();
+        let registry_addr = match self.registry.get() {
+            Some(addr) => addr,
+            None => self.env().revert(CdpError::InvalidConfig),
+        };
+        let env = self.env();
+        let resolved = resolve_all(&REGISTRY_DEPENDENCIES, |component| {
+            registry::try_resolve(&env, registry_addr, component)
+        });
+        for (component, address) in resolved {
+            match component {
+                ComponentId::Router => self.router.set(address),
+                ComponentId::Oracle => self.state.oracle_adapter.set(address),
+                ComponentId::Stablecoin => self.stablecoin.set(address),
+                ComponentId::LiquidationEngine => self.liquidation_engine.set(address),
+                ComponentId::RedemptionEngine => self.state.redemption_engine.set(address),
+                ComponentId::ScsprToken => self.scspr_token.set(address),
+                ComponentId::ScsprYbToken => self.state.compound_target.set(address),
+                _ => {}
+            }
+        }
+    }
+
+    /// Set lock duration applied after each deposit (admin only)
+    pub fn set_min_deposit_lock_seconds(&mut self, seconds: u64) {
+        self.require_registry_admin();
+        if seconds > MAX_DEPOSIT_LOCK_SECONDS {
+            self.env().revert(CdpError::InvalidConfig);
+        }
+        self.state.min_deposit_lock_seconds.set(seconds);
+    }
+
+    /// Set the per-user deposit cap for the guarded launch (admin only)
+    ///
+    /// Applies to the compounded deposit plus the new amount; zero lifts it.
+    pub fn set_max_deposit_per_user(&mut self, cap: U256) {
+        // TODO: Add admin access control
+        self.state.max_deposit_per_user.set(cap);
+    }
+
+    /// Set the product threshold that triggers a scale change (admin only)
+    ///
+    /// Must be in `1..=SCALE / SCALE_FACTOR` so a rescaled P stays below SCALE.
+    pub fn set_scale_reset_threshold(&mut self, threshold: U256) {
+        // TODO: Add admin access control
+        if threshold.is_zero() || threshold > U256::from(SCALE / SCALE_FACTOR) {
+            self.env().revert(CdpError::InvalidConfig);
+        }
+        self.state.scale_reset_threshold.set(threshold);
+    }
+
     /// Get stCSPR token address
     pub fn get_scspr_token(&self) -> Option<Address> {
         self.scspr_token.get()
     }
 
+    /// Get the ybToken CSPR gains are staked into (defaults to the stCSPR token)
+    pub fn get_compound_target(&self) -> Option<Address> {
+        self.state.compound_target.get().or_else(|| self.scspr_token.get())
+    }
+
+    /// Set the ybToken CSPR gains are staked into (admin only)
+    pub fn set_compound_target(&mut self, target: Address) {
+        // TODO: Add admin access control
+        self.state.compound_target.set(target);
+    }
+
     /// Get liquidation engine address
     pub fn get_liquidation_engine(&self) -> Option<Address> {
         self.liquidation_engine.get()
@@ -639,16 +957,82 @@ impl StabilityPool {
         self.stablecoin.get()
     }
 
+    /// Get redemption engine address
+    pub fn get_redemption_engine(&self) -> Option<Address> {
+        self.state.redemption_engine.get()
+    }
+
+    /// Set redemption engine address (post-deploy wiring)
+    pub fn set_redemption_engine(&mut self, redemption_engine: Address) {
+        // TODO: Add admin access control
+        self.state.redemption_engine.set(redemption_engine);
+    }
+
+    /// Get oracle adapter address
+    pub fn get_oracle_adapter(&self) -> Option<Address> {
+        self.state.oracle_adapter.get()
+    }
+
+    /// Set oracle adapter address (post-deploy wiring)
+    pub fn set_oracle_adapter(&mut self, oracle_adapter: Address) {
+        // TODO: Add admin access control
+        self.state.oracle_adapter.set(oracle_adapter);
+    }
+
     // ========== Internal Functions ==========
 
+    /// Current (CSPR, stCSPR) USD prices from the oracle adapter
+    fn collateral_prices(&self) -> (U256, U256) {
+        let adapter_addr = match self.state.oracle_adapter.get() {
+            Some(addr) => addr,
+            None => self.env().revert(CdpError::OraclePriceUnavailable),
+        };
+        let price_of = |collateral_id: CollateralId| {
+            let args = runtime_args! {
+                "collateral_id" => collateral_id
+            };
+            let call_def = CallDef::new("get_price", false, args);
+            let price_data: PriceData = self.env().call_contract(adapter_addr, call_def);
+            price_data.price_int
+        };
+        (price_of(CollateralId::Cspr), price_of(CollateralId::SCSPR))
+    }
+
+    fn enter_non_reentrant(&mut self) {
+        if let Err(err) = check_not_entered(self.state.reentrancy_lock.get().unwrap_or(false)) {
+            self.env().revert(err);
+        }
+        self.state.reentrancy_lock.set(true);
+    }
+
+    fn exit_non_reentrant(&mut self) {
+        self.state.reentrancy_lock.set(false);
+    }
+
     fn require_not_safe_mode(&self) {
-        let state = self.safe_mode.get().unwrap_or(SafeModeState {
-            is_active: false,
-            triggered_at: 0,
-            reason: OracleStatus::Ok,
-        });
-        if state.is_active {
-            self.env().revert(CdpError::SafeModeActive);
+        if let Err(err) = check_withdrawals_open(self.is_safe_mode_active()) {
+            self.env().revert(err);
+        }
+    }
+
+    fn require_registry_admin(&self) {
+        let registry_addr = match self.registry.get() {
+            Some(addr) => addr,
+            None => self.env().revert(CdpError::InvalidConfig),
+        };
+        let args = runtime_args! {
+            "caller" => self.env().caller()
+        };
+        let is_admin: bool = self.env().call_contract(registry_addr, CallDef::new("is_admin", false, args));
+        if !is_admin {
+            self.env().revert(CdpError::Unauthorized);
+        }
+    }
+
+    fn require_deposit_unlocked(&self, depositor: Address) {
+        let locked_until = self.state.deposit_locked_until.get(&depositor).unwrap_or(0);
+        if is_deposit_locked(locked_until, self.env().get_block_time()) {
+            self.env().revert(CdpError::DepositLocked);
         }
     }
 
@@ -662,6 +1046,105 @@ impl StabilityPool {
         }
     }
 
+    fn require_redemption_engine(&self) {
+        match self.state.redemption_engine.get() {
+            Some(engine) if self.env().caller() == engine => {}
+            _ => self.env().revert(CdpError::UnauthorizedProtocol),
+        }
+    }
+
+    /// Read pending gains and roll the depositor's snapshot forward
+    ///
+    /// Reverts with `SpNoGains` if there is nothing to claim.
+    fn checkpoint_gains(&mut self, depositor: Address) -> CollateralGains {
+        let gains = self.get_depositor_gains(depositor);
+
+        if gains.cspr_gain.is_zero() && gains.scspr_gain.is_zero() {
+            self.env().revert(CdpError::SpNoGains);
+        }
+
+        // Update snapshot to current state (resets gains)
+        let compounded_deposit = self.get_compounded_deposit(depositor);
+        if !compounded_deposit.is_zero() {
+            self.store_snapshot(depositor, compounded_deposit);
+        }
+        gains
+    }
+
+    /// Sell `amount` of pooled collateral to the treasury; returns the gUSD received
+    fn sell_gain_to_treasury(&mut self, collateral_id: CollateralId, amount: U256) -> U256 {
+        if amount.is_zero() {
+            return U256::zero();
+        }
+        let registry_addr = match self.registry.get() {
+            Some(addr) => addr,
+            None => self.env().revert(CdpError::InvalidConfig),
+        };
+        let treasury = match registry::try_resolve(&self.env(), registry_addr, ComponentId::Treasury) {
+            Some(addr) => addr,
+            None => self.env().revert(CdpError::InvalidConfig),
+        };
+
+        let args = runtime_args! {
+            "collateral_id" => collateral_id,
+            "collateral_amount" => amount
+        };
+        let call_def = match collateral_id {
+            CollateralId::Cspr => {
+                let current = self.total_cspr_collateral.get().unwrap_or(U256::zero());
+                if amount > current {
+                    self.env().revert(CdpError::InsufficientCollateral);
+                }
+                self.total_cspr_collateral.set(current - amount);
+                CallDef::new("buy_collateral", true, args).with_amount(u256_to_u512(amount))
+            }
+            CollateralId::SCSPR => {
+                let current = self.total_scspr_collateral.get().unwrap_or(U256::zero());
+                if amount > current {
+                    self.env().revert(CdpError::InsufficientCollateral);
+                }
+                self.total_scspr_collateral.set(current - amount);
+                let scspr_addr = self.scspr_token.get().expect("scspr token not set");
+                let transfer_args = runtime_args! {
+                    "recipient" => treasury,
+                    "amount" => amount
+                };
+                let success: bool =
+                    self.env().call_contract(scspr_addr, CallDef::new("transfer", true, transfer_args));
+                if !success {
+                    self.env().revert(CdpError::InsufficientTokenBalance);
+                }
+                CallDef::new("buy_collateral", true, args)
+            }
+        };
+        self.env().call_contract(treasury, call_def)
+    }
+
+    /// Deposit `amount` of pooled CSPR into the compound target and forward the
+    /// minted stCSPR to `recipient`
+    fn stake_cspr_gain(&mut self, recipient: Address, amount: U256) -> U256 {
+        let current_cspr = self.total_cspr_collateral.get().unwrap_or(U256::zero());
+        if amount > current_cspr {
+            self.env().revert(CdpError::InsufficientCollateral);
+        }
+        self.total_cspr_collateral.set(current_cspr - amount);
+
+        let target = self.get_compound_target().expect("compound target not set");
+        let deposit_call = CallDef::new("deposit", true, runtime_args! {}).with_amount(u256_to_u512(amount));
+        let minted: U256 = self.env().call_contract(target, deposit_call);
+
+        let args = runtime_args! {
+            "recipient" => recipient,
+            "amount" => minted
+        };
+        let transfer_call = CallDef::new("transfer", true, args);
+        let success: bool = self.env().call_contract(target, transfer_call);
+        if !success {
+            self.env().revert(CdpError::InsufficientTokenBalance);
+        }
+        minted
+    }
+
     fn transfer_gains_internal(&mut self, recipient: Address, gains: CollateralGains) {
         // Transfer CSPR gains (native transfer)
         if !gains.cspr_gain.is_zero() {
@@ -720,53 +1203,23 @@ impl StabilityPool {
         collateral_gain: U256,
         total_deposits: U256,
     ) {
-        let scale = U256::from(SCALE);
         let mut state = self.ps_state.get().unwrap_or(ProductSumState {
-            p: scale,
+            p: U256::from(SCALE),
             s_cspr: U256::zero(),
             s_scspr: U256::zero(),
             epoch: 0,
             scale: 0,
         });
 
-        // Product decrease factor = (total - debt) / total = 1 - debt/total
-        let numerator = if total_deposits > debt_offset {
-            total_deposits - debt_offset
-        } else {
-            U256::zero()
-        };
-
-        // Update sum: S += collateral * P / totalDeposits
-        let sum_increment = collateral_gain * state.p / total_deposits;
+        let threshold = self.get_scale_reset_threshold();
+        let (epoch_scale, sum) =
+            book_offset(&mut state, collateral_id, debt_offset, collateral_gain, total_deposits, threshold);
 
+        // Store the sum at the epoch and scale it was booked in; only the
+        // offset's own collateral is touched
         match collateral_id {
-            CollateralId::Cspr => {
-                state.s_cspr = state.s_cspr + sum_increment;
-                // Store sum at current epoch and scale
-                self.epoch_scale_sum_cspr.set(&(state.epoch, state.scale), state.s_cspr);
-            }
-            CollateralId::SCSPR => {
-                state.s_scspr = state.s_scspr + sum_increment;
-                self.epoch_scale_sum_scspr.set(&(state.epoch, state.scale), state.s_scspr);
-            }
-        }
-
-        // Update product: P *= (1 - debtLoss/totalDeposits)
-        if numerator.is_zero() {
-            // Full depletion - reset to new epoch
-            state.epoch += 1;
-            state.scale = 0;
-            state.p = scale;
-        } else {
-            let new_p = state.p * numerator / total_deposits;
-
-            // Check for scale change (product becomes too small)
-            if new_p < scale / U256::from(SCALE_FACTOR) {
-                state.p = new_p * U256::from(SCALE_FACTOR);
-                state.scale += 1;
-            } else {
-                state.p = new_p;
-            }
+            CollateralId::Cspr => self.epoch_scale_sum_cspr.set(&epoch_scale, sum),
+            CollateralId::SCSPR => self.epoch_scale_sum_scspr.set(&epoch_scale, sum),
         }
 
         self.ps_state.set(state);
@@ -785,43 +1238,200 @@ impl StabilityPool {
         current_scale: u64,
         collateral_id: CollateralId,
     ) -> U256 {
-        if snapshot_p.is_zero() {
-            return U256::zero();
-        }
-
         // If epoch changed, depositor's gain is from last epoch
-        if current_epoch != snapshot_epoch {
+        if snapshot_p.is_zero() || current_epoch != snapshot_epoch {
             return U256::zero(); // Simplified: would need epoch boundary sums
         }
 
-        // Calculate sum difference (accounting for scale changes)
-        let scale_diff = current_scale.saturating_sub(snapshot_scale);
-
-        let sum_diff = if scale_diff == 0 {
-            current_s.saturating_sub(snapshot_s)
-        } else if scale_diff == 1 {
-            // Get sum at next scale
-            let sum_at_next = match collateral_id {
-                CollateralId::Cspr => {
-                    self.epoch_scale_sum_cspr.get(&(snapshot_epoch, snapshot_scale + 1))
-                        .unwrap_or(U256::zero())
-                }
-                CollateralId::SCSPR => {
-                    self.epoch_scale_sum_scspr.get(&(snapshot_epoch, snapshot_scale + 1))
-                        .unwrap_or(U256::zero())
-                }
+        // Sum recorded at the end of each scale since the snapshot; the
+        // current scale ends at the live sum
+        let mut sums_by_scale = Vec::new();
+        for scale in snapshot_scale..current_scale {
+            let recorded = match collateral_id {
+                CollateralId::Cspr => self.epoch_scale_sum_cspr.get(&(snapshot_epoch, scale)),
+                CollateralId::SCSPR => self.epoch_scale_sum_scspr.get(&(snapshot_epoch, scale)),
             };
-            sum_at_next / U256::from(SCALE_FACTOR) + current_s.saturating_sub(snapshot_s)
-        } else {
-            U256::zero()
+            sums_by_scale.push(recorded);
+        }
+        sums_by_scale.push(Some(current_s));
+        gain_since_snapshot(deposit, snapshot_s, snapshot_p, &sums_by_scale)
+    }
+}
+
+// ===== Helper Functions =====
+
+/// Compound a deposit snapshot against the current product
+///
+/// `scale_diff` is the number of scale changes since the snapshot; each one
+/// divides by another SCALE_FACTOR, so a few changes round the deposit to zero.
+fn compound_deposit(deposit: U256, snapshot_p: U256, current_p: U256, scale_diff: u64) -> U256 {
+    if snapshot_p.is_zero() {
+        return deposit;
+    }
+    match scale_divisor(scale_diff) {
+        Some(divisor) => mul_div_floor(deposit, current_p, snapshot_p) / divisor,
+        None => U256::zero(),
+    }
+}
+
+/// SCALE_FACTOR^steps, or None once it no longer fits in U256
+fn scale_divisor(steps: u64) -> Option<U256> {
+    let mut divisor = U256::one();
+    for _ in 0..steps {
+        divisor = divisor.checked_mul(U256::from(SCALE_FACTOR))?;
+    }
+    Some(divisor)
+}
+
+/// Multiply `p` by SCALE_FACTOR until it reaches `threshold`: (new P, scale steps)
+fn rescale_product(mut p: U256, threshold: U256) -> (U256, u64) {
+    let mut steps = 0u64;
+    while !p.is_zero() && p < threshold {
+        p *= U256::from(SCALE_FACTOR);
+        steps += 1;
+    }
+    (p, steps)
+}
+
+/// Collateral sum accrued since a snapshot, walking every scale change
+///
+/// `sums_by_scale[i]` is the cumulative sum at the end of scale
+/// `snapshot_scale + i` (`None` if nothing was booked in it); the last entry is
+/// the live sum. P was multiplied by SCALE_FACTOR at each change, so each later
+/// scale's increment is divided by one more SCALE_FACTOR.
+fn accrued_sum_across_scales(snapshot_s: U256, sums_by_scale: &[Option<U256>]) -> U256 {
+    let mut total = U256::zero();
+    let mut start = snapshot_s;
+    for (steps, recorded) in sums_by_scale.iter().enumerate() {
+        let divisor = match scale_divisor(steps as u64) {
+            Some(divisor) => divisor,
+            None => break,
         };
+        let end = recorded.unwrap_or(start).max(start);
+        total += (end - start) / divisor;
+        start = end;
+    }
+    total
+}
 
-        // Gain = deposit * (S_current - S_snapshot) / P_snapshot
-        deposit * sum_diff / snapshot_p
+/// Book one offset into the product-sum state
+///
+/// Raises only `collateral_id`'s sum, so gains from one collateral never leak
+/// into the other's, then shrinks P (rescaling or starting a new epoch as
+/// needed). Returns the `(epoch, scale)` the sum was booked in and its new
+/// value, for the per-collateral sum history.
+fn book_offset(
+    state: &mut ProductSumState,
+    collateral_id: CollateralId,
+    debt_offset: U256,
+    collateral_gain: U256,
+    total_deposits: U256,
+    threshold: U256,
+) -> ((u64, u64), U256) {
+    let epoch_scale = (state.epoch, state.scale);
+
+    // Update sum: S += collateral * P / totalDeposits
+    let sum_increment = mul_div_floor(collateral_gain, state.p, total_deposits);
+    let sum = match collateral_id {
+        CollateralId::Cspr => {
+            state.s_cspr += sum_increment;
+            state.s_cspr
+        }
+        CollateralId::SCSPR => {
+            state.s_scspr += sum_increment;
+            state.s_scspr
+        }
+    };
+
+    // Update product: P *= (1 - debtLoss/totalDeposits)
+    let numerator = total_deposits.saturating_sub(debt_offset);
+    let new_p = mul_div_floor(state.p, numerator, total_deposits);
+    if new_p.is_zero() {
+        // Full depletion (or P rounded away) - reset to new epoch
+        state.epoch += 1;
+        state.scale = 0;
+        state.p = U256::from(SCALE);
+    } else {
+        // Rescale as many times as needed to bring P back above the threshold
+        let (p, scale_steps) = rescale_product(new_p, threshold);
+        state.p = p;
+        state.scale += scale_steps;
     }
+
+    (epoch_scale, sum)
 }
 
-// ===== Helper Functions =====
+/// One collateral's gain for a deposit within the snapshot's epoch
+///
+/// `sums_by_scale` is that collateral's sum history from the snapshot scale
+/// on (see `accrued_sum_across_scales`). Gain = deposit * ΔS / P_snapshot.
+fn gain_since_snapshot(deposit: U256, snapshot_s: U256, snapshot_p: U256, sums_by_scale: &[Option<U256>]) -> U256 {
+    if snapshot_p.is_zero() {
+        return U256::zero();
+    }
+    let sum_diff = accrued_sum_across_scales(snapshot_s, sums_by_scale);
+    mul_div_floor(deposit, sum_diff, snapshot_p)
+}
+
+/// Deposits are blocked only by the operator pause
+fn check_deposits_open(deposits_paused: bool) -> Result<(), CdpError> {
+    if deposits_paused {
+        Err(CdpError::SpDepositsPaused)
+    } else {
+        Ok(())
+    }
+}
+
+/// Withdrawals and gain claims are blocked only by safe mode
+fn check_withdrawals_open(safe_mode_active: bool) -> Result<(), CdpError> {
+    if safe_mode_active {
+        Err(CdpError::SafeModeActive)
+    } else {
+        Ok(())
+    }
+}
+
+/// Validate a withdrawal against the depositor's compounded deposit
+fn check_withdraw_amount(amount: U256, compounded_deposit: U256) -> Result<(), CdpError> {
+    if amount > compounded_deposit {
+        Err(CdpError::SpInsufficientDeposit)
+    } else {
+        Ok(())
+    }
+}
+
+/// Validate a depositor's balance after a deposit against the per-user cap (0 = unlimited)
+fn check_deposit_cap(new_deposit: U256, cap: U256) -> Result<(), CdpError> {
+    if !cap.is_zero() && new_deposit > cap {
+        Err(CdpError::DepositCapReached)
+    } else {
+        Ok(())
+    }
+}
+
+/// Split gains for `claim_and_compound`: (paid out directly, CSPR to stake)
+fn split_for_compound(gains: CollateralGains) -> (CollateralGains, U256) {
+    let payout = CollateralGains {
+        cspr_gain: U256::zero(),
+        scspr_gain: gains.scspr_gain,
+    };
+    (payout, gains.cspr_gain)
+}
+
+/// Timestamp until which a deposit made at `now` stays locked
+fn deposit_lock_expiry(now: u64, lock_seconds: u64) -> u64 {
+    now.saturating_add(lock_seconds)
+}
+
+/// Whether a deposit locked until `locked_until` is still locked at `now`
+fn is_deposit_locked(locked_until: u64, now: u64) -> bool {
+    now < locked_until
+}
+
+/// USD value of gUSD plus CSPR and stCSPR amounts at the given prices
+fn position_value_usd(gusd: U256, cspr: U256, scspr: U256, cspr_price: U256, scspr_price: U256) -> U256 {
+    gusd + collateral_to_value(cspr, cspr_price) + collateral_to_value(scspr, scspr_price)
+}
 
 /// Convert U512 to U256 (safe for CSPR amounts which fit in U256)
 fn u512_to_u256(value: U512) -> U256 {
@@ -900,6 +1510,63 @@ mod tests {
         assert_eq!(sum_increment, expected);
     }
 
+    #[test]
+    fn test_redemption_fee_gain_raises_depositor_gains() {
+        // 40 collateral of redemption fees credited to 1000 gUSD of deposits
+        let scale = U256::from(SCALE);
+        let total = U256::from(1000u64);
+        let fee_gain = U256::from(40u64);
+
+        let p = scale;
+        let s_before = U256::zero();
+        let s_after = s_before + mul_div_floor(fee_gain, p, total);
+
+        // No debt absorbed: P and deposits are unchanged
+        assert_eq!(mul_div_floor(p, total - U256::zero(), total), p);
+        assert_eq!(compound_deposit(U256::from(600u64), p, p, 0), U256::from(600u64));
+
+        // Gains rise pro rata: 60% / 40% of the fee
+        let gain = |deposit: u64| U256::from(deposit) * (s_after - s_before) / p;
+        assert_eq!(gain(600), U256::from(24u64));
+        assert_eq!(gain(400), U256::from(16u64));
+    }
+
+    #[test]
+    fn test_compound_deposit_scale_handling() {
+        let deposit = U256::from(1000u64);
+        let p = U256::from(SCALE);
+        assert_eq!(compound_deposit(deposit, U256::zero(), p, 0), deposit);
+        assert_eq!(compound_deposit(deposit, p, p, 2), U256::zero());
+    }
+
+    #[test]
+    fn test_withdraw_before_lock_elapses_is_locked() {
+        let deposited_at = 1_000u64;
+        let locked_until = deposit_lock_expiry(deposited_at, 3600);
+
+        assert!(is_deposit_locked(locked_until, deposited_at));
+        assert!(is_deposit_locked(locked_until, locked_until - 1));
+    }
+
+    #[test]
+    fn test_withdraw_after_lock_elapses_is_unlocked() {
+        let deposited_at = 1_000u64;
+        let locked_until = deposit_lock_expiry(deposited_at, 3600);
+
+        assert!(!is_deposit_locked(locked_until, locked_until));
+        assert!(!is_deposit_locked(locked_until, locked_until + 1));
+        // Never-deposited addresses are not locked
+        assert!(!is_deposit_locked(0, deposited_at));
+    }
+
+    #[test]
+    fn test_deposit_lock_bounds() {
+        // Off by default; governance opts in up to the maximum
+        assert_eq!(DEFAULT_MIN_DEPOSIT_LOCK_SECONDS, 0);
+        assert_eq!(deposit_lock_expiry(u64::MAX, 10), u64::MAX);
+        assert_eq!(deposit_lock_expiry(500, 0), 500);
+    }
+
     #[test]
     fn test_product_sum_state_default() {
         let state = ProductSumState::default();
@@ -909,4 +1576,239 @@ mod tests {
         assert_eq!(state.epoch, 0);
         assert_eq!(state.scale, 0);
     }
+
+    #[test]
+    fn test_gains_across_multiple_scale_changes() {
+        let threshold = U256::from(DEFAULT_SCALE_RESET_THRESHOLD);
+        let coll = |n: u64| U256::from(n) * U256::from(1_000_000_000u64);
+
+        // One depositor with 1000 gUSD; three offsets, the first two wiping all
+        // but 1e-10 of the pool and each forcing a scale change
+        let deposit = U256::from(1_000u64) * U256::from(SCALE);
+        let mut total = deposit;
+        let mut p = U256::from(SCALE);
+        let mut s = U256::zero();
+        let mut sums_by_scale: Vec<Option<U256>> = Vec::new();
+        let offsets = [
+            (total - total / U256::from(10_000_000_000u64), coll(500)),
+            (U256::from(100_000_000_000u64) - U256::from(10u64), coll(300)),
+            (U256::from(5u64), coll(100)),
+        ];
+        for (debt, gain) in offsets {
+            s += mul_div_floor(gain, p, total);
+            let remaining = total - debt;
+            let (new_p, steps) = rescale_product(mul_div_floor(p, remaining, total), threshold);
+            // Close the current scale with its final sum when P was rescaled
+            if steps > 0 {
+                sums_by_scale.push(Some(s));
+            }
+            p = new_p;
+            total = remaining;
+        }
+        sums_by_scale.push(Some(s));
+        let scale_diff = (sums_by_scale.len() - 1) as u64;
+        assert_eq!(scale_diff, 2);
+
+        // All 900 CSPR of gains reach the sole depositor
+        let sum_diff = accrued_sum_across_scales(U256::zero(), &sums_by_scale);
+        assert_eq!(deposit * sum_diff / U256::from(SCALE), coll(900));
+
+        // And the compounded deposit matches what is left in the pool
+        assert_eq!(compound_deposit(deposit, U256::from(SCALE), p, scale_diff), total);
+    }
+
+    #[test]
+    fn test_rescale_product_threshold() {
+        let threshold = U256::from(DEFAULT_SCALE_RESET_THRESHOLD);
+        assert_eq!(rescale_product(U256::from(SCALE), threshold), (U256::from(SCALE), 0));
+        assert_eq!(rescale_product(U256::from(5u64), threshold), (U256::from(5_000_000_000u64), 1));
+        assert_eq!(rescale_product(U256::zero(), threshold), (U256::zero(), 0));
+
+        // A lower threshold rescales less eagerly
+        let (p, steps) = rescale_product(U256::from(1_000u64), U256::from(100u64));
+        assert_eq!((p, steps), (U256::from(1_000u64), 0));
+    }
+
+    #[test]
+    fn test_accrued_sum_skips_scales_without_gains() {
+        // Nothing booked in the middle scale; its start carries over
+        let sums = [Some(U256::from(500u64)), None, Some(U256::from(500u64) + U256::from(SCALE))];
+        let expected = U256::from(500u64) + U256::from(SCALE) / U256::from(SCALE_FACTOR) / U256::from(SCALE_FACTOR);
+        assert_eq!(accrued_sum_across_scales(U256::zero(), &sums), expected);
+    }
+
+    #[test]
+    fn test_mul_div_overflow_path() {
+        let big = U256::MAX / U256::from(2u64);
+        assert_eq!(mul_div_floor(big, U256::from(4u64), U256::from(8u64)), big / U256::from(2u64));
+        assert_eq!(mul_div_floor(U256::MAX, U256::MAX, U256::one()), U256::MAX);
+        assert_eq!(mul_div_floor(U256::from(6u64), U256::from(7u64), U256::from(3u64)), U256::from(14u64));
+    }
+
+    #[test]
+    fn test_claim_and_compound_stakes_cspr_gains() {
+        let gains = CollateralGains {
+            cspr_gain: U256::from(1_100u64),
+            scspr_gain: U256::from(40u64),
+        };
+        let (payout, to_stake) = split_for_compound(gains);
+
+        // stCSPR gains are paid as-is, CSPR gains are all staked
+        assert!(payout.cspr_gain.is_zero());
+        assert_eq!(payout.scspr_gain, U256::from(40u64));
+        assert_eq!(to_stake, U256::from(1_100u64));
+
+        // At R = 1.1 (1100 assets / 1000 shares) the depositor ends up with
+        // 1000 stCSPR from the stake plus the 40 stCSPR gain
+        let minted = to_stake * U256::from(1_000u64) / U256::from(1_100u64);
+        assert_eq!(minted + payout.scspr_gain, U256::from(1_040u64));
+    }
+
+    #[test]
+    fn test_over_withdraw_reports_insufficient_deposit() {
+        let deposit = U256::from(1_000u64);
+        assert_eq!(
+            check_withdraw_amount(deposit + U256::one(), deposit),
+            Err(CdpError::SpInsufficientDeposit)
+        );
+        assert_eq!(check_withdraw_amount(deposit, deposit), Ok(()));
+    }
+
+    #[test]
+    fn test_deposit_cap_boundary() {
+        let gusd = |n: u64| U256::from(n) * U256::from(SCALE);
+        let cap = gusd(10_000);
+
+        // Topping up to exactly the cap is allowed; one unit more is not
+        assert_eq!(check_deposit_cap(cap, cap), Ok(()));
+        assert_eq!(check_deposit_cap(cap + U256::one(), cap), Err(CdpError::DepositCapReached));
+        assert_eq!(check_deposit_cap(gusd(9_999) + gusd(2), cap), Err(CdpError::DepositCapReached));
+
+        // Zero cap means unlimited
+        assert_eq!(check_deposit_cap(gusd(1_000_000), U256::zero()), Ok(()));
+    }
+
+    #[test]
+    fn test_deposit_pause_is_independent_of_safe_mode() {
+        // Paused deposits block inflows; withdrawals still follow safe mode only
+        assert_eq!(check_deposits_open(true), Err(CdpError::SpDepositsPaused));
+        assert_eq!(check_withdrawals_open(false), Ok(()));
+
+        // Safe mode blocks withdrawals but deposits stay open unless paused
+        assert_eq!(check_deposits_open(false), Ok(()));
+        assert_eq!(check_withdrawals_open(true), Err(CdpError::SafeModeActive));
+    }
+
+    #[test]
+    fn test_late_depositor_gets_no_gain_from_earlier_collateral() {
+        use std::collections::BTreeMap;
+
+        let gusd = |n: u64| U256::from(n) * U256::from(SCALE);
+        let coll = |n: u64| U256::from(n) * U256::from(1_000_000_000u64);
+        // Thresholds above P force scale changes on cue: one before Bob joins,
+        // one after
+        let low = U256::from(SCALE);
+        let high = low * U256::from(SCALE_FACTOR);
+
+        let mut state = ProductSumState {
+            p: U256::from(SCALE),
+            ..ProductSumState::default()
+        };
+        let mut sums: BTreeMap<(CollateralId, u64, u64), U256> = BTreeMap::new();
+        let mut book = |state: &mut ProductSumState, id: CollateralId, debt: U256, gain: U256, total: U256, threshold| {
+            let ((epoch, scale), sum) = book_offset(state, id, debt, gain, total, threshold);
+            sums.insert((id, epoch, scale), sum);
+        };
+
+        // Alice deposits 1000; a CSPR liquidation offsets 100 debt for 50 CSPR
+        let alice = DepositSnapshot {
+            deposit: gusd(1000),
+            p: state.p,
+            ..DepositSnapshot::default()
+        };
+        book(&mut state, CollateralId::Cspr, gusd(100), coll(50), gusd(1000), low);
+        assert_eq!(state.scale, 1);
+
+        // Bob joins after every CSPR liquidation, matching Alice's remaining 900
+        let bob = DepositSnapshot {
+            deposit: gusd(900),
+            p: state.p,
+            s_cspr: state.s_cspr,
+            s_scspr: state.s_scspr,
+            epoch: state.epoch,
+            scale: state.scale,
+        };
+
+        // Two stCSPR liquidations follow on the 1800 pool
+        book(&mut state, CollateralId::SCSPR, gusd(180), coll(90), gusd(1800), high);
+        book(&mut state, CollateralId::SCSPR, gusd(162), coll(81), gusd(1620), high);
+        assert_eq!(state.scale, 2);
+
+        let gain = |snapshot: &DepositSnapshot, id: CollateralId| {
+            let (snapshot_s, current_s) = match id {
+                CollateralId::Cspr => (snapshot.s_cspr, state.s_cspr),
+                CollateralId::SCSPR => (snapshot.s_scspr, state.s_scspr),
+            };
+            let mut history: Vec<Option<U256>> =
+                (snapshot.scale..state.scale).map(|scale| sums.get(&(id, snapshot.epoch, scale)).copied()).collect();
+            history.push(Some(current_s));
+            gain_since_snapshot(snapshot.deposit, snapshot_s, snapshot.p, &history)
+        };
+
+        // Bob: no CSPR, half of the 171 stCSPR that followed his deposit
+        assert_eq!(gain(&bob, CollateralId::Cspr), U256::zero());
+        assert_eq!(gain(&bob, CollateralId::SCSPR), coll(171) / U256::from(2u64));
+
+        // Alice: all the CSPR, the other half of the stCSPR
+        assert_eq!(gain(&alice, CollateralId::Cspr), coll(50));
+        assert_eq!(gain(&alice, CollateralId::SCSPR), coll(171) / U256::from(2u64));
+    }
+
+    #[test]
+    fn test_position_value_at_known_prices() {
+        use crate::math::{COLLATERAL_SCALE, PRICE_SCALE};
+
+        let gusd = |n: u64| U256::from(n) * U256::from(PRICE_SCALE);
+        let coll = |n: u64| U256::from(n) * U256::from(COLLATERAL_SCALE);
+        let cspr_price = U256::from(PRICE_SCALE / 20); // $0.05
+        let scspr_price = U256::from(PRICE_SCALE / 100) * U256::from(6u64); // $0.06 (R = 1.2)
+
+        // 1000 gUSD + 2000 CSPR ($100) + 500 stCSPR ($30)
+        assert_eq!(position_value_usd(gusd(1000), coll(2000), coll(500), cspr_price, scspr_price), gusd(1130));
+
+        // Deposits only: valued at $1 regardless of prices
+        assert_eq!(position_value_usd(gusd(250), U256::zero(), U256::zero(), U256::zero(), U256::zero()), gusd(250));
+
+        // A fully absorbed deposit is worth exactly its gains
+        assert_eq!(position_value_usd(U256::zero(), coll(20), U256::zero(), cspr_price, scspr_price), gusd(1));
+    }
+
+    #[test]
+    fn test_auto_compound_grows_deposit_after_liquidation() {
+        use crate::math::{COLLATERAL_SCALE, PRICE_SCALE};
+
+        let gusd = |n: u64| U256::from(n) * U256::from(PRICE_SCALE);
+        let coll = |n: u64| U256::from(n) * U256::from(COLLATERAL_SCALE);
+        let cspr_price = U256::from(PRICE_SCALE / 20) * U256::from(3u64); // $0.15
+
+        let mut state = ProductSumState {
+            p: U256::from(SCALE),
+            ..ProductSumState::default()
+        };
+        let snapshot_p = state.p;
+
+        // Sole depositor with 1000 gUSD; a liquidation offsets 100 debt for 1000 CSPR
+        let (_, sum) = book_offset(&mut state, CollateralId::Cspr, gusd(100), coll(1000), gusd(1000), U256::one());
+        let compounded = compound_deposit(gusd(1000), snapshot_p, state.p, 0);
+        let gain = gain_since_snapshot(gusd(1000), U256::zero(), snapshot_p, &[Some(sum)]);
+        assert_eq!(compounded, gusd(900));
+        assert_eq!(gain, coll(1000));
+
+        // Selling the 1000 CSPR ($150) back into the deposit leaves it above where it started
+        let gusd_added = collateral_to_value(gain, cspr_price);
+        let new_deposit = compounded + gusd_added;
+        assert_eq!(gusd_added, gusd(150));
+        assert_eq!(new_deposit, position_value_usd(compounded, gain, U256::zero(), cspr_price, U256::zero()));
+        assert!(new_deposit > gusd(1000));
+    }
 }
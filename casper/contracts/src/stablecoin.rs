@@ -30,6 +30,19 @@ const SECURITY_ADMIN: u8 = 1;
 const SECURITY_MINT_AND_BURN: u8 = 2;
 const SECURITY_BURNER: u8 = 3;
 const SECURITY_MINTER: u8 = 4;
+/// Maximum token name / symbol length in bytes
+pub const MAX_METADATA_LEN: usize = 64;
+
+/// Validate a new token name and symbol: both non-empty and at most
+/// `MAX_METADATA_LEN` bytes
+pub fn check_metadata(name: &str, symbol: &str) -> Result<(), CdpError> {
+    let valid = |value: &str| !value.trim().is_empty() && value.len() <= MAX_METADATA_LEN;
+    if valid(name) && valid(symbol) {
+        Ok(())
+    } else {
+        Err(CdpError::InvalidConfig)
+    }
+}
 
 #[odra::event]
 pub struct Transfer {
@@ -323,6 +336,20 @@ impl CsprUsd {
         self.supply_cap.set(cap);
     }
 
+    /// Rename the token (admin only via registry)
+    ///
+    /// Updates the stored name and symbol and the CEP-18 named keys explorers
+    /// read. Decimals are fixed at deploy and cannot be changed.
+    pub fn set_metadata(&mut self, name: String, symbol: String) {
+        self.require_registry_admin();
+        if let Err(err) = check_metadata(&name, &symbol) {
+            self.env().revert(err);
+        }
+        self.name.set(name);
+        self.symbol.set(symbol);
+        self.set_metadata_cep18();
+    }
+
     /// Change security roles (registry admin only)
     ///
     /// Lists are comma-separated account-hash strings. Empty string = no-op.
@@ -448,6 +475,20 @@ impl CsprUsd {
         }
     }
 
+    fn set_metadata_cep18(&self) {
+        self.env().set_named_value(CEP18_NAME_KEY, self.name());
+        self.env().set_named_value(CEP18_SYMBOL_KEY, self.symbol());
+
+        // Native Casper named key update for Explorer
+        #[cfg(target_arch = "wasm32")]
+        {
+            let name_uref = storage::new_uref(self.name());
+            runtime::put_key(CEP18_NAME_KEY, Key::URef(name_uref));
+            let symbol_uref = storage::new_uref(self.symbol());
+            runtime::put_key(CEP18_SYMBOL_KEY, Key::URef(symbol_uref));
+        }
+    }
+
     fn cep18_balance_key(owner: Address) -> String {
         let key = Key::from(owner);
         let bytes = key.to_bytes().unwrap_or_default();
@@ -50,16 +50,20 @@ impl StyksOracle {
     /// Get CSPR/USD price from Styks
     /// Returns price scaled by 1e18, or default if unavailable
     pub fn get_cspr_price(env: &odra::ContractEnv, styks_address: Address) -> U256 {
+        match Self::get_cspr_twap(env, styks_address) {
+            Some(price_data) => price_data.price,
+            None => U256::from(DEFAULT_CSPR_PRICE),
+        }
+    }
+
+    /// Get the raw CSPR/USD TWAP reading (with its timestamp) from a feed
+    pub fn get_cspr_twap(env: &odra::ContractEnv, feed_address: Address) -> Option<StyksTwapPrice> {
         let args = runtime_args! {
             "price_feed_id" => CSPR_USD_FEED_ID.to_string()
         };
 
         let call_def = odra::CallDef::new("get_twap_price", false, args);
-
-        match env.call_contract::<Option<StyksTwapPrice>>(styks_address, call_def) {
-            Some(price_data) => price_data.price,
-            None => U256::from(DEFAULT_CSPR_PRICE),
-        }
+        env.call_contract::<Option<StyksTwapPrice>>(feed_address, call_def)
     }
 
     /// Get stCSPR/USD price using composite formula
@@ -63,6 +63,14 @@ pub struct BalanceSnapshot {
     pub after: U256,
 }
 
+/// Net amount a transfer delivered, from balance snapshots taken around it
+///
+/// Less than `requested` for fee-on-transfer tokens; never credited above
+/// `requested`, so an unrelated balance increase cannot inflate a deposit.
+pub fn net_received(snapshot: &BalanceSnapshot, requested: U256) -> U256 {
+    snapshot.after.saturating_sub(snapshot.before).min(requested)
+}
+
 /// Token Adapter Contract
 #[odra::module]
 pub struct TokenAdapter {
@@ -537,6 +545,21 @@ mod tests {
         assert_eq!(received, U256::from(99_900u64));
     }
 
+    #[test]
+    fn test_net_received_credits_only_the_transfer() {
+        let amount = U256::from(100_000u64);
+
+        // A 1% fee is credited net
+        let snapshot = BalanceSnapshot { before: U256::zero(), after: U256::from(99_000u64) };
+        assert_eq!(net_received(&snapshot, amount), U256::from(99_000u64));
+
+        // Balance growth beyond the transfer is never credited
+        let snapshot = BalanceSnapshot { before: U256::from(5u64), after: U256::from(200_000u64) };
+        assert_eq!(net_received(&snapshot, amount), amount);
+        let snapshot = BalanceSnapshot { before: U256::from(5u64), after: U256::from(5u64) };
+        assert_eq!(net_received(&snapshot, amount), U256::zero());
+    }
+
     #[test]
     fn test_exchange_rate_scaling() {
         // Exchange rate is scaled by 1000
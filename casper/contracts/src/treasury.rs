@@ -8,11 +8,16 @@
 //! Fees are collected in gUSD and can be distributed to stakeholders.
 
 use odra::prelude::*;
-use odra::casper_types::U256;
+use odra::casper_types::{U256, U512, runtime_args};
+use odra::CallDef;
 use crate::errors::CdpError;
+use crate::recovery::{self, TokenSwept};
+use crate::registry;
+use crate::types::{CollateralId, ComponentId, OracleStatus, PriceData};
+use crate::math::collateral_to_value;
 
 /// Treasury Contract for protocol fee collection and distribution
-#[odra::module]
+#[odra::module(events = [TokenSwept])]
 pub struct Treasury {
     /// Registry contract address
     registry: Var<Address>,
@@ -32,6 +37,8 @@ pub struct Treasury {
     authorized_depositors: Mapping<Address, bool>,
     /// Fee recipient address
     fee_recipient: Var<Option<Address>>,
+    /// Collateral bought from the stability pool and not yet withdrawn, per collateral type
+    collateral_bought: Mapping<CollateralId, U256>,
 }
 
 #[odra::module]
@@ -89,6 +96,58 @@ impl Treasury {
         // This requires the stablecoin contract interaction
     }
 
+    /// Pay a keeper bounty in gUSD out of pending fees (protocol only)
+    ///
+    /// Pays at most the pending fees so an empty treasury never blocks the
+    /// keeper action itself. Returns the amount actually paid.
+    pub fn pay_keeper_bounty(&mut self, keeper: Address, amount: U256) -> U256 {
+        self.require_authorized_depositor();
+
+        let pending = self.pending_fees.get().unwrap_or(U256::zero());
+        let paid = amount.min(pending);
+        if paid.is_zero() {
+            return paid;
+        }
+
+        self.pay_from_fees(keeper, paid);
+        paid
+    }
+
+    /// Buy collateral for gUSD out of pending fees (stability pool only)
+    ///
+    /// Used by the stability pool to convert auto-compounding depositors'
+    /// gains. CSPR must be attached to the call; stCSPR is transferred to the
+    /// treasury beforehand. The gUSD paid is priced here at the oracle price,
+    /// which must be fresh and Ok. Reverts if pending fees cannot cover it.
+    /// Returns the gUSD paid.
+    #[odra(payable)]
+    pub fn buy_collateral(&mut self, collateral_id: CollateralId, collateral_amount: U256) -> U256 {
+        self.require_stability_pool();
+
+        if collateral_id == CollateralId::Cspr && self.env().attached_value() != u256_to_u512(collateral_amount) {
+            self.env().revert(CdpError::InvalidConfig);
+        }
+        let price = match purchase_price(&self.oracle_price(collateral_id)) {
+            Ok(price) => price,
+            Err(err) => self.env().revert(err),
+        };
+        let gusd_amount = collateral_to_value(collateral_amount, price);
+        let pending = self.pending_fees.get().unwrap_or(U256::zero());
+        if gusd_amount > pending {
+            self.env().revert(CdpError::InsufficientTokenBalance);
+        }
+
+        let bought = self.collateral_bought.get(&collateral_id).unwrap_or(U256::zero());
+        self.collateral_bought.set(&collateral_id, bought + collateral_amount);
+        self.pay_from_fees(self.env().caller(), gusd_amount);
+        gusd_amount
+    }
+
+    /// Get collateral bought from the stability pool and still held
+    pub fn get_collateral_bought(&self, collateral_id: CollateralId) -> U256 {
+        self.collateral_bought.get(&collateral_id).unwrap_or(U256::zero())
+    }
+
     // ========== View Functions ==========
 
     /// Get total fees collected
@@ -155,8 +214,118 @@ impl Treasury {
         self.fee_recipient.set(Some(recipient));
     }
 
+    /// Withdraw collateral bought from the stability pool (admin only)
+    pub fn withdraw_collateral(&mut self, collateral_id: CollateralId, to: Address, amount: U256) {
+        self.require_registry_admin();
+
+        let held = self.get_collateral_bought(collateral_id);
+        if amount > held {
+            self.env().revert(CdpError::InsufficientCollateral);
+        }
+        self.collateral_bought.set(&collateral_id, held - amount);
+
+        match collateral_id {
+            CollateralId::Cspr => self.env().transfer_tokens(&to, &u256_to_u512(amount)),
+            CollateralId::SCSPR => {
+                let scspr_addr = self.resolve(ComponentId::ScsprToken);
+                let args = runtime_args! {
+                    "recipient" => to,
+                    "amount" => amount
+                };
+                let success: bool = self.env().call_contract(scspr_addr, CallDef::new("transfer", true, args));
+                if !success {
+                    self.env().revert(CdpError::TokenTransferFailed);
+                }
+            }
+        }
+    }
+
+    /// Sweep a token accidentally sent to the treasury (admin only)
+    ///
+    /// gUSD can only be swept above pending (undistributed) fees.
+    pub fn sweep_token(&mut self, token: Address, to: Address, amount: U256) {
+        self.require_registry_admin();
+
+        let accounted = if self.stablecoin.get() == Some(token) {
+            self.pending_fees.get().unwrap_or(U256::zero())
+        } else {
+            U256::zero()
+        };
+        recovery::sweep_token(&self.env(), token, to, amount, accounted);
+    }
+
     // ========== Internal Functions ==========
 
+    fn require_registry_admin(&self) {
+        let caller = self.env().caller();
+        let registry_addr = match self.registry.get() {
+            Some(addr) => addr,
+            None => self.env().revert(CdpError::InvalidConfig),
+        };
+
+        let args = runtime_args! {
+            "caller" => caller
+        };
+        let call_def = CallDef::new("is_admin", false, args);
+        let is_admin: bool = self.env().call_contract(registry_addr, call_def);
+
+        if !is_admin {
+            self.env().revert(CdpError::Unauthorized);
+        }
+    }
+
+    /// Resolve a protocol component through the registry
+    fn resolve(&self, component: ComponentId) -> Address {
+        let registry_addr = match self.registry.get() {
+            Some(addr) => addr,
+            None => self.env().revert(CdpError::InvalidConfig),
+        };
+        match registry::try_resolve(&self.env(), registry_addr, component) {
+            Some(addr) => addr,
+            None => self.env().revert(CdpError::InvalidConfig),
+        }
+    }
+
+    fn require_stability_pool(&self) {
+        if self.env().caller() != self.resolve(ComponentId::StabilityPool) {
+            self.env().revert(CdpError::UnauthorizedProtocol);
+        }
+    }
+
+    /// Current adapter reading for a collateral
+    fn oracle_price(&self, collateral_id: CollateralId) -> PriceData {
+        let oracle = self.resolve(ComponentId::Oracle);
+        let args = runtime_args! {
+            "collateral_id" => collateral_id
+        };
+        self.env().call_contract(oracle, CallDef::new("get_price", false, args))
+    }
+
+    /// Send `amount` gUSD out of pending fees, counting it as distributed
+    fn pay_from_fees(&mut self, recipient: Address, amount: U256) {
+        if amount.is_zero() {
+            return;
+        }
+        let pending = self.pending_fees.get().unwrap_or(U256::zero());
+        self.pending_fees.set(pending - amount);
+        let total_distributed = self.total_fees_distributed.get().unwrap_or(U256::zero());
+        self.total_fees_distributed.set(total_distributed + amount);
+
+        let stablecoin_addr = match self.stablecoin.get() {
+            Some(addr) => addr,
+            None => self.env().revert(CdpError::InvalidConfig),
+        };
+        let args = runtime_args! {
+            "recipient" => recipient,
+            "amount" => amount
+        };
+        let call_def = CallDef::new("transfer", true, args);
+        let success: bool = self.env().call_contract(stablecoin_addr, call_def);
+        if !success {
+            self.env().revert(CdpError::TokenTransferFailed);
+        }
+    }
+
     fn add_fee(&mut self, amount: U256, fee_type: FeeType) {
         if amount.is_zero() {
             return;
@@ -212,3 +381,41 @@ pub struct FeeBreakdown {
     /// Total interest fees collected
     pub interest: U256,
 }
+
+/// Price to buy collateral at: the adapter reading, only if fresh and Ok
+fn purchase_price(price_data: &PriceData) -> Result<U256, CdpError> {
+    match price_data.status {
+        OracleStatus::Ok if !price_data.price_int.is_zero() => Ok(price_data.price_int),
+        OracleStatus::Stale => Err(CdpError::OraclePriceStale),
+        _ => Err(CdpError::OraclePriceUnavailable),
+    }
+}
+
+/// Convert U256 to U512
+fn u256_to_u512(value: U256) -> U512 {
+    let mut bytes = [0u8; 32];
+    value.to_little_endian(&mut bytes);
+    U512::from_little_endian(&bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn reading(price: u64, status: OracleStatus) -> PriceData {
+        PriceData {
+            price_int: U256::from(price),
+            price_decimals: 18,
+            timestamp_sec: 0,
+            status,
+        }
+    }
+
+    #[test]
+    fn test_purchase_price_requires_fresh_ok_reading() {
+        assert_eq!(purchase_price(&reading(150, OracleStatus::Ok)), Ok(U256::from(150u64)));
+        assert_eq!(purchase_price(&reading(150, OracleStatus::Stale)), Err(CdpError::OraclePriceStale));
+        assert_eq!(purchase_price(&reading(150, OracleStatus::Deviation)), Err(CdpError::OraclePriceUnavailable));
+        assert_eq!(purchase_price(&reading(0, OracleStatus::Ok)), Err(CdpError::OraclePriceUnavailable));
+    }
+}
@@ -13,6 +13,36 @@ pub enum CollateralId {
     SCSPR,
 }
 
+/// Protocol component resolvable through the registry
+#[odra::odra_type]
+#[derive(Copy)]
+pub enum ComponentId {
+    /// Router contract
+    Router,
+    /// gUSD stablecoin
+    Stablecoin,
+    /// Treasury
+    Treasury,
+    /// Oracle adapter
+    Oracle,
+    /// Stability pool
+    StabilityPool,
+    /// Liquidation engine
+    LiquidationEngine,
+    /// Redemption engine
+    RedemptionEngine,
+    /// CSPR branch
+    BranchCspr,
+    /// stCSPR branch
+    BranchScspr,
+    /// stCSPR token (CEP-18)
+    ScsprToken,
+    /// stCSPR ybToken (exchange rate source)
+    ScsprYbToken,
+    /// Styks price feed
+    StyksOracle,
+}
+
 /// Oracle price status
 #[odra::odra_type]
 #[derive(Copy)]
@@ -119,3 +149,65 @@ pub struct SafeModeState {
     /// Reason for safe mode activation
     pub reason: OracleStatus,
 }
+
+/// Branch operation that moved the aggregate totals
+#[odra::odra_type]
+#[derive(Copy)]
+pub enum TotalsChangeReason {
+    /// Vault opened
+    Open,
+    /// Vault collateral or debt adjusted (including third-party repayment)
+    Adjust,
+    /// Vault closed by its owner
+    Close,
+    /// Vault redeemed against
+    Redeem,
+    /// Vault liquidated or debt redistributed
+    Liquidate,
+    /// Interest rate changed (accrual and rate-adjust fee)
+    Interest,
+    /// Vault moved to or from another branch version
+    Migrate,
+}
+
+/// Branch aggregate totals
+#[odra::odra_type]
+#[derive(Copy, Default)]
+pub struct BranchTotals {
+    /// Branch total collateral
+    pub total_collateral: U256,
+    /// Branch total debt
+    pub total_debt: U256,
+    /// Open vault count
+    pub vault_count: u64,
+}
+
+/// Emitted after every mutating branch operation with the post-operation totals
+///
+/// Lets indexers reconcile branch aggregates without replaying every vault.
+#[odra::event]
+pub struct TotalsChanged {
+    /// Branch event sequence number
+    pub seq: u64,
+    /// Branch total collateral
+    pub total_collateral: U256,
+    /// Branch total debt
+    pub total_debt: U256,
+    /// Open vault count
+    pub vault_count: u64,
+    /// Operation that caused the change
+    pub reason: TotalsChangeReason,
+}
+
+/// Reconciliation of a branch's accounted collateral against its actual balance
+#[odra::odra_type]
+pub struct CollateralBalanceDelta {
+    /// Vault collateral plus unclaimed liquidation surplus
+    pub accounted: U256,
+    /// Collateral actually held by the branch
+    pub actual: U256,
+    /// Magnitude of the difference
+    pub delta: U256,
+    /// Whether the branch holds at least what it accounts for
+    pub is_surplus: bool,
+}
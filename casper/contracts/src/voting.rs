@@ -0,0 +1,81 @@
+//! Governance voting power from stCSPR holdings and SP deposits.
+//!
+//! Voting power is read-only and composable: the registry combines a holder's
+//! stCSPR balance (valued in CSPR at the current exchange rate R) and their
+//! compounded stability pool deposit, each scaled by a configurable weight.
+//! Both legs are normalized to 18 decimals so weights apply per whole token.
+//! Snapshotting (e.g. at proposal creation) is left to the governance caller.
+
+use odra::casper_types::U256;
+use crate::math::{apply_rate, COLLATERAL_SCALE, PRICE_SCALE};
+
+/// Basis points scale (100% = 10000 bps)
+const BPS_SCALE: u32 = 10_000;
+
+/// Maximum weight per source (10x)
+pub const MAX_VOTING_WEIGHT_BPS: u32 = 100_000;
+
+/// Per-source voting weights in bps (10000 = 1 vote per whole token)
+#[odra::odra_type]
+#[derive(Copy)]
+pub struct VotingWeights {
+    /// Weight of stCSPR holdings, valued in CSPR at R
+    pub scspr_weight_bps: u32,
+    /// Weight of the compounded SP deposit (gUSD)
+    pub sp_weight_bps: u32,
+}
+
+impl Default for VotingWeights {
+    fn default() -> Self {
+        Self {
+            scspr_weight_bps: BPS_SCALE,
+            sp_weight_bps: BPS_SCALE,
+        }
+    }
+}
+
+/// Weighted voting power (18 decimals)
+///
+/// # Arguments
+/// * `scspr_shares` - stCSPR balance (9 decimals)
+/// * `rate` - CSPR per stCSPR, scaled by 1e18
+/// * `sp_deposit` - Compounded SP deposit (gUSD, 18 decimals)
+/// * `weights` - Per-source weights
+pub fn voting_power(scspr_shares: U256, rate: U256, sp_deposit: U256, weights: &VotingWeights) -> U256 {
+    // stCSPR -> CSPR (9 dec) -> 18 decimals
+    let cspr_equivalent = apply_rate(scspr_shares, rate);
+    let scspr_votes = cspr_equivalent * U256::from(PRICE_SCALE) / U256::from(COLLATERAL_SCALE);
+
+    let weighted = |amount: U256, weight_bps: u32| amount * U256::from(weight_bps) / U256::from(BPS_SCALE);
+    weighted(scspr_votes, weights.scspr_weight_bps) + weighted(sp_deposit, weights.sp_weight_bps)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_voting_power_sums_weighted_balances() {
+        let scspr = |n: u64| U256::from(n) * U256::from(COLLATERAL_SCALE);
+        let votes = |n: u64| U256::from(n) * U256::from(PRICE_SCALE);
+        let rate = votes(11) / U256::from(10u64); // R = 1.1
+
+        // 1000 stCSPR at R = 1.1 is 1100 CSPR; plus a 500 gUSD SP deposit
+        let equal = VotingWeights::default();
+        assert_eq!(voting_power(scspr(1000), rate, votes(500), &equal), votes(1600));
+
+        // SP deposits at half weight, stCSPR at double weight
+        let weights = VotingWeights {
+            scspr_weight_bps: 20_000,
+            sp_weight_bps: 5_000,
+        };
+        assert_eq!(voting_power(scspr(1000), rate, votes(500), &weights), votes(2200 + 250));
+
+        // A zero weight drops that source entirely
+        let sp_only = VotingWeights {
+            scspr_weight_bps: 0,
+            sp_weight_bps: BPS_SCALE,
+        };
+        assert_eq!(voting_power(scspr(1000), rate, votes(500), &sp_only), votes(500));
+    }
+}
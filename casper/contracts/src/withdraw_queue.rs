@@ -21,6 +21,8 @@ use odra::prelude::*;
 use odra::casper_types::{U256, runtime_args, RuntimeArgs};
 use odra::CallDef;
 use crate::errors::CdpError;
+use crate::math::mul_div_floor;
+use crate::scspr_ybtoken::AssetBreakdown;
 
 /// Scale for rate calculations (1e18)
 const SCALE: u128 = 1_000_000_000_000_000_000;
@@ -29,6 +31,8 @@ const SCALE: u128 = 1_000_000_000_000_000_000;
 const DEFAULT_UNBONDING_PERIOD: u64 = 25200;
 /// Maximum requests per user (to limit storage)
 const MAX_REQUESTS_PER_USER: u32 = 100;
+/// Maximum requests returned by `get_pending_requests_page`
+const MAX_PAGE_SIZE: u32 = 100;
 
 /// Withdrawal request status
 #[odra::odra_type]
@@ -94,8 +98,23 @@ pub struct QueueConfig {
     pub claims_paused: bool,
 }
 
+/// Emitted when a claim is paid below its quote because R dropped
+#[odra::event]
+pub struct ClaimHaircut {
+    /// Request claimed
+    pub request_id: u64,
+    /// Request owner
+    pub owner: Address,
+    /// Assets quoted at request time
+    pub quoted_assets: U256,
+    /// Assets actually paid
+    pub paid_assets: U256,
+    /// Exchange rate at claim time (scaled by 1e18)
+    pub current_rate: U256,
+}
+
 /// Withdraw Queue Contract
-#[odra::module]
+#[odra::module(events = [ClaimHaircut])]
 pub struct WithdrawQueue {
     /// ybToken (stCSPR) contract address
     ybtoken: Var<Address>,
@@ -116,6 +135,10 @@ pub struct WithdrawQueue {
     /// Cached exchange rate (updated externally to avoid cross-contract call issues)
     /// Scaled by 1e18 (1e18 = 1.0)
     cached_rate: Var<U256>,
+    /// Wind-down switch allowing `emergency_claim` (default off)
+    emergency_mode: Var<bool>,
+    /// Pay `min(quoted, shares * current R)` when R fell since the request (default off)
+    haircut_on_loss: Var<bool>,
 }
 
 #[odra::module]
@@ -129,12 +152,29 @@ impl WithdrawQueue {
         // Initialize cached rate to 1:1 (1e18)
         self.cached_rate.set(U256::from(SCALE));
 
-        self.config.set(QueueConfig {
-            unbonding_period: DEFAULT_UNBONDING_PERIOD,
-            min_withdrawal: U256::zero(),
-            requests_paused: false,
-            claims_paused: false,
-        });
+        self.config.set(default_queue_config());
+    }
+
+    /// Upgrade hook (called automatically by Odra during contract upgrade).
+    ///
+    /// Defaults any storage left unset by an older version; existing requests,
+    /// stats, config and cached rate are left untouched. Stored structs keep
+    /// their original layout, so older entries decode as-is; settings added
+    /// since (emergency mode, haircut on loss) live in their own vars and read
+    /// as off until set.
+    pub fn upgrade(&mut self) {
+        if let Some(next_id) = backfill(self.next_request_id.get(), 1) {
+            self.next_request_id.set(next_id);
+        }
+        if let Some(stats) = backfill(self.stats.get(), QueueStats::default()) {
+            self.stats.set(stats);
+        }
+        if let Some(rate) = backfill(self.cached_rate.get(), U256::from(SCALE)) {
+            self.cached_rate.set(rate);
+        }
+        if let Some(config) = backfill(self.config.get(), default_queue_config()) {
+            self.config.set(config);
+        }
     }
 
     // ===== User Functions =====
@@ -154,24 +194,20 @@ impl WithdrawQueue {
     pub fn request_withdraw(&mut self, shares: U256) -> u64 {
         let config = self.config.get().unwrap();
         if config.requests_paused {
-            self.env().revert(CdpError::SafeModeActive);
+            self.env().revert(CdpError::LstWithdrawalsPaused);
         }
 
         let caller = self.env().caller();
 
         // Validate minimum
-        if shares < config.min_withdrawal {
-            self.env().revert(CdpError::BelowMinDebt);
-        }
-
-        if shares.is_zero() {
-            self.env().revert(CdpError::BelowMinDebt);
+        if let Err(err) = check_request_amount(shares, config.min_withdrawal) {
+            self.env().revert(err);
         }
 
         // Check user hasn't exceeded max requests
         let user_count = self.user_request_count.get(&caller).unwrap_or(0);
         if user_count >= MAX_REQUESTS_PER_USER {
-            self.env().revert(CdpError::InvalidConfig);
+            self.env().revert(CdpError::LstMaxRequestsExceeded);
         }
 
         // Get current exchange rate from ybToken
@@ -179,28 +215,21 @@ impl WithdrawQueue {
         // For MVP, we'll store the rate calculation here
         let quoted_rate = self.get_current_rate();
 
-        // Calculate quoted assets: assets = shares * R / SCALE
-        let quoted_assets = shares * quoted_rate / U256::from(SCALE);
-
         // Generate request ID
         let request_id = self.next_request_id.get().unwrap_or(1);
         self.next_request_id.set(request_id + 1);
 
-        // Calculate claimable timestamp
+        // Create request; its cooldown end is fixed by the current unbonding period
         let now = self.env().get_block_time();
-        let claimable_at = now + config.unbonding_period;
-
-        // Create request
-        let request = WithdrawRequest {
+        let request = new_request(
             request_id,
-            owner: caller,
-            shares_locked: shares,
-            quoted_assets,
+            caller,
+            shares,
             quoted_rate,
-            request_timestamp: now,
-            claimable_at,
-            status: WithdrawStatus::Pending,
-        };
+            now,
+            config.unbonding_period,
+        );
+        let quoted_assets = request.quoted_assets;
 
         // Store request
         self.requests.set(&request_id, request);
@@ -236,7 +265,7 @@ impl WithdrawQueue {
     pub fn claim(&mut self, request_id: u64) {
         let config = self.config.get().unwrap();
         if config.claims_paused {
-            self.env().revert(CdpError::SafeModeActive);
+            self.env().revert(CdpError::LstWithdrawalsPaused);
         }
 
         let caller = self.env().caller();
@@ -244,52 +273,50 @@ impl WithdrawQueue {
         // Get request
         let mut request = match self.requests.get(&request_id) {
             Some(r) => r,
-            None => self.env().revert(CdpError::VaultNotFound),
+            None => self.env().revert(CdpError::LstRequestNotFound),
         };
 
-        // Verify ownership
-        if request.owner != caller {
-            self.env().revert(CdpError::Unauthorized);
+        // Verify ownership, status and cooldown
+        let now = self.env().get_block_time();
+        if let Err(err) = check_claim(&request, caller, now) {
+            self.env().revert(err);
         }
 
-        // Verify status
-        match request.status {
-            WithdrawStatus::Pending => {}
-            WithdrawStatus::Claimable => {}
-            WithdrawStatus::Claimed => self.env().revert(CdpError::VaultNotFound),
-            WithdrawStatus::Cancelled => self.env().revert(CdpError::VaultNotFound),
-        }
+        // Price the claim before its shares are burned
+        let payout = self.payout_for(&request);
+        self.settle_request(request_id, &mut request);
 
-        // Check cooldown
-        let now = self.env().get_block_time();
-        if now < request.claimable_at {
-            self.env().revert(CdpError::SafeModeActive); // Still in cooldown
+        // Transfer CSPR to user via ybToken
+        // Note: In real implementation, call ybtoken.transfer_cspr_to_user(caller, payout)
+        self.transfer_cspr_to_user(caller, payout);
+    }
+
+    /// Claim a request immediately from the idle buffer during a wind-down
+    ///
+    /// Only available while emergency mode is on. Pays what `claim` would
+    /// (the quote, or the haircut amount in haircut mode) whether or not the
+    /// cooldown has elapsed, and only if the ybToken holds enough idle CSPR;
+    /// claimable CSPR backing matured requests is untouched.
+    pub fn emergency_claim(&mut self, request_id: u64) {
+        if !self.is_emergency_mode() {
+            self.env().revert(CdpError::LstEmergencyModeInactive);
         }
 
-        // Update request status
-        request.status = WithdrawStatus::Claimed;
-        self.requests.set(&request_id, request.clone());
+        let caller = self.env().caller();
+        let mut request = match self.requests.get(&request_id) {
+            Some(r) => r,
+            None => self.env().revert(CdpError::LstRequestNotFound),
+        };
 
-        // Update stats
-        let mut stats = self.stats.get().unwrap_or_default();
-        if stats.total_pending_shares >= request.shares_locked {
-            stats.total_pending_shares = stats.total_pending_shares - request.shares_locked;
-        }
-        if stats.total_pending_assets >= request.quoted_assets {
-            stats.total_pending_assets = stats.total_pending_assets - request.quoted_assets;
-        }
-        if stats.pending_count > 0 {
-            stats.pending_count -= 1;
+        // Price the claim before its shares are burned
+        let payout = self.payout_for(&request);
+        let idle_cspr = self.get_ybtoken_idle_cspr();
+        if let Err(err) = check_emergency_claim(&request, caller, payout, idle_cspr) {
+            self.env().revert(err);
         }
-        self.stats.set(stats);
-
-        // Burn locked stCSPR via ybToken
-        // Note: In real implementation, call ybtoken.burn_from_queue(self, shares)
-        self.burn_locked_shares(request.shares_locked);
 
-        // Transfer CSPR to user via ybToken
-        // Note: In real implementation, call ybtoken.transfer_cspr_to_user(caller, quoted_assets)
-        self.transfer_cspr_to_user(caller, request.quoted_assets);
+        self.settle_request(request_id, &mut request);
+        self.transfer_idle_cspr_to_user(caller, payout);
     }
 
     /// Get request details
@@ -343,6 +370,28 @@ impl WithdrawQueue {
         }
     }
 
+    /// Get outstanding claims due by `before_timestamp`
+    ///
+    /// Returns (count, total quoted assets) over pending requests whose
+    /// cooldown ends at or before `before_timestamp`, so the operator can
+    /// plan undelegation amounts.
+    pub fn get_claims_due(&self, before_timestamp: u64) -> (u64, U256) {
+        let next_id = self.next_request_id.get().unwrap_or(1);
+        claims_due((1..next_id).filter_map(|id| self.requests.get(&id)), before_timestamp)
+    }
+
+    /// Get a page of outstanding requests in FIFO order
+    ///
+    /// Scans request IDs from `start` and returns up to `limit` (capped at
+    /// MAX_PAGE_SIZE) pending or claimable requests.
+    pub fn get_pending_requests_page(&self, start: u64, limit: u32) -> Vec<WithdrawRequest> {
+        let next_id = self.next_request_id.get().unwrap_or(1);
+        pending_page(
+            (start.max(1)..next_id).filter_map(|id| self.requests.get(&id)),
+            limit.min(MAX_PAGE_SIZE),
+        )
+    }
+
     /// Get queue statistics
     pub fn get_stats(&self) -> QueueStats {
         self.stats.get().unwrap_or_default()
@@ -361,6 +410,9 @@ impl WithdrawQueue {
     // ===== Admin Functions =====
 
     /// Set unbonding period (admin only)
+    ///
+    /// Applies to new requests only; pending requests keep the `claimable_at`
+    /// they were created with.
     pub fn set_unbonding_period(&mut self, period: u64) {
         self.require_admin();
         let mut config = self.config.get().unwrap();
@@ -376,6 +428,17 @@ impl WithdrawQueue {
         self.config.set(config);
     }
 
+    /// Enable or disable haircut-on-loss claims (admin only)
+    pub fn set_haircut_on_loss(&mut self, enabled: bool) {
+        self.require_admin();
+        self.haircut_on_loss.set(enabled);
+    }
+
+    /// Whether claims are paid at the lower of the quote and the live rate
+    pub fn is_haircut_on_loss(&self) -> bool {
+        self.haircut_on_loss.get().unwrap_or(false)
+    }
+
     /// Pause new requests (admin only)
     pub fn pause_requests(&mut self) {
         self.require_admin();
@@ -392,6 +455,17 @@ impl WithdrawQueue {
         self.config.set(config);
     }
 
+    /// Enable or disable emergency claims (admin only)
+    pub fn set_emergency_mode(&mut self, enabled: bool) {
+        self.require_admin();
+        self.emergency_mode.set(enabled);
+    }
+
+    /// Whether emergency claims are enabled
+    pub fn is_emergency_mode(&self) -> bool {
+        self.emergency_mode.get().unwrap_or(false)
+    }
+
     /// Pause claims (admin only)
     pub fn pause_claims(&mut self) {
         self.require_admin();
@@ -453,6 +527,70 @@ impl WithdrawQueue {
         self.cached_rate.get().unwrap_or(U256::from(SCALE))
     }
 
+    /// Exchange rate read live from the ybToken (scaled by 1e18)
+    fn get_live_rate(&self) -> U256 {
+        let ybtoken_address = self.ybtoken.get().unwrap();
+        let call_def = CallDef::new("get_exchange_rate", false, runtime_args! {});
+        self.env().call_contract(ybtoken_address, call_def)
+    }
+
+    /// Assets to pay for `request`, emitting `ClaimHaircut` when below the quote
+    ///
+    /// Honors the quote unless haircut mode is on and the ybToken's live rate
+    /// dropped since the request.
+    fn payout_for(&self, request: &WithdrawRequest) -> U256 {
+        let current_rate = self.get_live_rate();
+        let payout = claim_payout(
+            request.quoted_assets,
+            request.shares_locked,
+            request.quoted_rate,
+            current_rate,
+            self.is_haircut_on_loss(),
+        );
+        if payout < request.quoted_assets {
+            self.env().emit_event(ClaimHaircut {
+                request_id: request.request_id,
+                owner: request.owner,
+                quoted_assets: request.quoted_assets,
+                paid_assets: payout,
+                current_rate,
+            });
+        }
+        payout
+    }
+
+    /// Mark a request claimed, drop it from the stats and burn its locked shares
+    fn settle_request(&mut self, request_id: u64, request: &mut WithdrawRequest) {
+        // Update request status
+        request.status = WithdrawStatus::Claimed;
+        self.requests.set(&request_id, request.clone());
+
+        // Update stats
+        let mut stats = self.stats.get().unwrap_or_default();
+        if stats.total_pending_shares >= request.shares_locked {
+            stats.total_pending_shares -= request.shares_locked;
+        }
+        if stats.total_pending_assets >= request.quoted_assets {
+            stats.total_pending_assets -= request.quoted_assets;
+        }
+        if stats.pending_count > 0 {
+            stats.pending_count -= 1;
+        }
+        self.stats.set(stats);
+
+        // Burn locked stCSPR via ybToken
+        // Note: In real implementation, call ybtoken.burn_from_queue(self, shares)
+        self.burn_locked_shares(request.shares_locked);
+    }
+
+    /// Idle CSPR currently held by the ybToken
+    fn get_ybtoken_idle_cspr(&self) -> U256 {
+        let ybtoken_address = self.ybtoken.get().unwrap();
+        let call_def = CallDef::new("get_asset_breakdown", false, runtime_args! {});
+        let assets: AssetBreakdown = self.env().call_contract(ybtoken_address, call_def);
+        assets.idle_cspr
+    }
+
     /// Lock shares from user by transferring to this contract
     ///
     /// Calls ybtoken.transfer_from(user, queue, shares) to lock the shares.
@@ -503,11 +641,167 @@ impl WithdrawQueue {
         let call_def = CallDef::new("transfer_cspr_to_user", true, args);
         self.env().call_contract::<()>(ybtoken_address, call_def);
     }
+
+    /// Transfer idle CSPR to user via ybToken (emergency claims)
+    fn transfer_idle_cspr_to_user(&mut self, to: Address, amount: U256) {
+        let ybtoken_address = self.ybtoken.get().unwrap();
+
+        let args = runtime_args! {
+            "recipient" => to,
+            "amount" => amount
+        };
+        let call_def = CallDef::new("transfer_idle_cspr_to_user", true, args);
+        self.env().call_contract::<()>(ybtoken_address, call_def);
+    }
+}
+
+// ===== Helper Functions =====
+
+/// Build a pending withdrawal request quoted at `quoted_rate`
+///
+/// `claimable_at` is fixed from `cooldown_seconds` here, so later config
+/// changes don't move it.
+pub fn new_request(
+    request_id: u64,
+    owner: Address,
+    shares: U256,
+    quoted_rate: U256,
+    now: u64,
+    cooldown_seconds: u64,
+) -> WithdrawRequest {
+    WithdrawRequest {
+        request_id,
+        owner,
+        shares_locked: shares,
+        // assets = shares * R / SCALE, paid out so rounded down
+        quoted_assets: mul_div_floor(shares, quoted_rate, U256::from(SCALE)),
+        quoted_rate,
+        request_timestamp: now,
+        claimable_at: now.saturating_add(cooldown_seconds),
+        status: WithdrawStatus::Pending,
+    }
+}
+
+/// Assets to pay for a claim
+///
+/// Pays the quote, unless `haircut_on_loss` is set and the current rate is
+/// below the quoted rate, in which case `min(quoted_assets, shares * current_rate)`.
+pub fn claim_payout(
+    quoted_assets: U256,
+    shares: U256,
+    quoted_rate: U256,
+    current_rate: U256,
+    haircut_on_loss: bool,
+) -> U256 {
+    if !haircut_on_loss || current_rate >= quoted_rate {
+        return quoted_assets;
+    }
+    let current_assets = mul_div_floor(shares, current_rate, U256::from(SCALE));
+    quoted_assets.min(current_assets)
+}
+
+/// Validate a withdrawal request size against the configured minimum
+pub fn check_request_amount(shares: U256, min_withdrawal: U256) -> Result<(), CdpError> {
+    if shares.is_zero() || shares < min_withdrawal {
+        Err(CdpError::LstBelowMinAmount)
+    } else {
+        Ok(())
+    }
+}
+
+/// Validate that `caller` may claim `request` at `now`
+pub fn check_claim(request: &WithdrawRequest, caller: Address, now: u64) -> Result<(), CdpError> {
+    check_claimant(request, caller)?;
+    if now < request.claimable_at {
+        return Err(CdpError::LstCooldownActive);
+    }
+    Ok(())
+}
+
+/// Validate an emergency claim: cooldown is ignored, but the `payout`
+/// must be covered by `idle_cspr`
+pub fn check_emergency_claim(
+    request: &WithdrawRequest,
+    caller: Address,
+    payout: U256,
+    idle_cspr: U256,
+) -> Result<(), CdpError> {
+    check_claimant(request, caller)?;
+    if payout > idle_cspr {
+        return Err(CdpError::LstInsufficientIdle);
+    }
+    Ok(())
+}
+
+/// Ownership and status checks shared by regular and emergency claims
+fn check_claimant(request: &WithdrawRequest, caller: Address) -> Result<(), CdpError> {
+    if request.owner != caller {
+        return Err(CdpError::Unauthorized);
+    }
+    match request.status {
+        WithdrawStatus::Pending | WithdrawStatus::Claimable => Ok(()),
+        WithdrawStatus::Claimed => Err(CdpError::LstAlreadyClaimed),
+        WithdrawStatus::Cancelled => Err(CdpError::LstRequestCancelled),
+    }
+}
+
+/// Whether a request still awaits its claim
+pub fn is_outstanding(status: WithdrawStatus) -> bool {
+    matches!(status, WithdrawStatus::Pending | WithdrawStatus::Claimable)
+}
+
+/// Count and total quoted assets of outstanding requests claimable by `before`
+pub fn claims_due<I>(requests: I, before: u64) -> (u64, U256)
+where
+    I: IntoIterator<Item = WithdrawRequest>,
+{
+    requests
+        .into_iter()
+        .filter(|r| is_outstanding(r.status) && r.claimable_at <= before)
+        .fold((0u64, U256::zero()), |(count, total), r| (count + 1, total + r.quoted_assets))
+}
+
+/// First `limit` outstanding requests, in the order given
+pub fn pending_page<I>(requests: I, limit: u32) -> Vec<WithdrawRequest>
+where
+    I: IntoIterator<Item = WithdrawRequest>,
+{
+    requests
+        .into_iter()
+        .filter(|r| is_outstanding(r.status))
+        .take(limit as usize)
+        .collect()
+}
+
+/// Value to write when backfilling a storage slot during an upgrade
+///
+/// Returns `Some(default)` only if the slot is unset, so existing state is
+/// never overwritten.
+pub fn backfill<T>(current: Option<T>, default: T) -> Option<T> {
+    match current {
+        Some(_) => None,
+        None => Some(default),
+    }
+}
+
+/// Queue configuration used at deployment
+fn default_queue_config() -> QueueConfig {
+    QueueConfig {
+        unbonding_period: DEFAULT_UNBONDING_PERIOD,
+        min_withdrawal: U256::zero(),
+        requests_paused: false,
+        claims_paused: false,
+    }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use odra::casper_types::account::AccountHash;
+
+    fn owner() -> Address {
+        Address::Account(AccountHash::default())
+    }
 
     #[test]
     fn test_withdraw_status_variants() {
@@ -547,4 +841,146 @@ mod tests {
         let quoted_assets = shares * rate / U256::from(SCALE);
         assert_eq!(quoted_assets, U256::from(1100u64));
     }
+
+    #[test]
+    fn test_request_fixes_claimable_at() {
+        let mut config = QueueConfig {
+            unbonding_period: DEFAULT_UNBONDING_PERIOD,
+            min_withdrawal: U256::zero(),
+            requests_paused: false,
+            claims_paused: false,
+        };
+        let request = new_request(1, owner(), U256::from(1000u64), U256::from(SCALE), 1_000, config.unbonding_period);
+        assert_eq!(request.claimable_at, 1_000 + DEFAULT_UNBONDING_PERIOD);
+
+        // Switching to a mainnet-length period later doesn't move the pending request
+        config.unbonding_period = 14 * 24 * 60 * 60;
+        assert_eq!(request.claimable_at, 1_000 + DEFAULT_UNBONDING_PERIOD);
+
+        let later = new_request(2, owner(), U256::from(1000u64), U256::from(SCALE), 1_000, config.unbonding_period);
+        assert_eq!(later.claimable_at, 1_000 + 14 * 24 * 60 * 60);
+    }
+
+    #[test]
+    fn test_claims_due_matches_paged_requests() {
+        let rate = U256::from(SCALE);
+        let mut requests: Vec<WithdrawRequest> = (1..=5)
+            .map(|id| new_request(id, owner(), U256::from(100 * id), rate, 1_000 * id, DEFAULT_UNBONDING_PERIOD))
+            .collect();
+        requests[1].status = WithdrawStatus::Claimed;
+
+        // Page through the outstanding requests two at a time
+        let first = pending_page(requests.clone(), 2);
+        let rest = pending_page(requests.iter().skip(3).cloned(), 2);
+        assert_eq!(first.len() + rest.len(), 4);
+
+        let paged_total = first.iter().chain(rest.iter()).fold(U256::zero(), |acc, r| acc + r.quoted_assets);
+        let (count, total) = claims_due(requests.clone(), u64::MAX);
+        assert_eq!(count, 4);
+        assert_eq!(total, paged_total);
+    }
+
+    #[test]
+    fn test_claims_due_respects_timestamp() {
+        let rate = U256::from(SCALE);
+        let requests: Vec<WithdrawRequest> = (1..=3)
+            .map(|id| new_request(id, owner(), U256::from(100u64), rate, 1_000 * id, DEFAULT_UNBONDING_PERIOD))
+            .collect();
+
+        // Only the first two cooldowns end by t = 2000 + period
+        let (count, total) = claims_due(requests, 2_000 + DEFAULT_UNBONDING_PERIOD);
+        assert_eq!(count, 2);
+        assert_eq!(total, U256::from(200u64));
+    }
+
+    #[test]
+    fn test_claim_payout_no_loss_pays_full_quote() {
+        let shares = U256::from(1000u64);
+        let quoted_rate = U256::from(SCALE) * U256::from(11u64) / U256::from(10u64); // 1.1
+        let higher_rate = U256::from(SCALE) * U256::from(12u64) / U256::from(10u64); // 1.2
+        let quoted = shares * quoted_rate / U256::from(SCALE);
+
+        assert_eq!(claim_payout(quoted, shares, quoted_rate, quoted_rate, true), quoted);
+        assert_eq!(claim_payout(quoted, shares, quoted_rate, higher_rate, true), quoted);
+    }
+
+    #[test]
+    fn test_claim_payout_haircut_on_loss() {
+        let shares = U256::from(1000u64);
+        let quoted_rate = U256::from(SCALE) * U256::from(11u64) / U256::from(10u64); // 1.1
+        let slashed_rate = U256::from(SCALE); // 1.0 after slashing
+        let quoted = shares * quoted_rate / U256::from(SCALE);
+
+        // Haircut mode pays the current value of the locked shares
+        assert_eq!(claim_payout(quoted, shares, quoted_rate, slashed_rate, true), U256::from(1000u64));
+        // Without the flag the quote is honored
+        assert_eq!(claim_payout(quoted, shares, quoted_rate, slashed_rate, false), quoted);
+    }
+
+    #[test]
+    fn test_upgrade_backfill_preserves_existing_state() {
+        // Pre-upgrade storage: requests and stats exist, cached rate and config
+        // predate this version and are unset
+        let stats = QueueStats {
+            total_pending_shares: U256::from(500u64),
+            ..QueueStats::default()
+        };
+        assert!(backfill(Some(42u64), 1).is_none());
+        assert!(backfill(Some(stats), QueueStats::default()).is_none());
+
+        // New fields get their deployment defaults
+        assert_eq!(backfill(None, U256::from(SCALE)), Some(U256::from(SCALE)));
+        let config = backfill(None, default_queue_config()).unwrap();
+        assert_eq!(config.unbonding_period, DEFAULT_UNBONDING_PERIOD);
+    }
+
+    #[test]
+    fn test_claim_failures_report_specific_errors() {
+        use odra::casper_types::account::AccountHash;
+
+        let request = new_request(1, owner(), U256::from(100u64), U256::from(SCALE), 1_000, 3_600);
+        let stranger = Address::Account(AccountHash::new([7u8; 32]));
+
+        assert_eq!(check_claim(&request, stranger, 10_000), Err(CdpError::Unauthorized));
+        assert_eq!(check_claim(&request, owner(), 4_599), Err(CdpError::LstCooldownActive));
+        assert_eq!(check_claim(&request, owner(), 4_600), Ok(()));
+
+        let mut claimed = request.clone();
+        claimed.status = WithdrawStatus::Claimed;
+        assert_eq!(check_claim(&claimed, owner(), 10_000), Err(CdpError::LstAlreadyClaimed));
+
+        let mut cancelled = request.clone();
+        cancelled.status = WithdrawStatus::Cancelled;
+        assert_eq!(check_claim(&cancelled, owner(), 10_000), Err(CdpError::LstRequestCancelled));
+
+        // Request size
+        assert_eq!(check_request_amount(U256::zero(), U256::zero()), Err(CdpError::LstBelowMinAmount));
+        assert_eq!(check_request_amount(U256::from(9u64), U256::from(10u64)), Err(CdpError::LstBelowMinAmount));
+        assert_eq!(check_request_amount(U256::from(10u64), U256::from(10u64)), Ok(()));
+    }
+
+    #[test]
+    fn test_emergency_claim_ignores_cooldown_but_needs_idle() {
+        let request = new_request(1, owner(), U256::from(1_000u64), U256::from(SCALE), 1_000, DEFAULT_UNBONDING_PERIOD);
+
+        // Still deep in its cooldown: a regular claim fails, an emergency claim passes
+        assert_eq!(check_claim(&request, owner(), 1_001), Err(CdpError::LstCooldownActive));
+        let quote = request.quoted_assets;
+        assert_eq!(check_emergency_claim(&request, owner(), quote, U256::from(1_000u64)), Ok(()));
+
+        // Not enough idle CSPR to cover the quote
+        assert_eq!(
+            check_emergency_claim(&request, owner(), quote, U256::from(999u64)),
+            Err(CdpError::LstInsufficientIdle)
+        );
+
+        // A haircut payout only needs idle CSPR for the reduced amount
+        let haircut = claim_payout(quote, request.shares_locked, request.quoted_rate, U256::from(SCALE / 2), true);
+        assert_eq!(check_emergency_claim(&request, owner(), haircut, U256::from(500u64)), Ok(()));
+
+        // Ownership and status rules still apply
+        let mut claimed = request.clone();
+        claimed.status = WithdrawStatus::Claimed;
+        assert_eq!(check_emergency_claim(&claimed, owner(), quote, U256::MAX), Err(CdpError::LstAlreadyClaimed));
+    }
 }
@@ -0,0 +1,192 @@
+//! Shared deployment fixture for integration tests.
+//!
+//! Deploys the registry, gUSD, the stCSPR ybToken with its withdraw queue and
+//! both branches on the Odra VM. Test accounts stand in for the router and the liquidation and
+//! redemption engines so branch entrypoints can be driven directly; `deploy_with_router` puts a
+//! Router contract in the router role instead.
+
+use cspr_cdp_contracts::branch_cspr::{BranchCspr, BranchCsprHostRef, BranchCsprInitArgs};
+use cspr_cdp_contracts::branch_scspr::{BranchScspr, BranchScsprHostRef, BranchScsprInitArgs};
+use cspr_cdp_contracts::redemption_engine::{RedemptionEngine, RedemptionEngineHostRef, RedemptionEngineInitArgs};
+use cspr_cdp_contracts::registry::{Registry, RegistryHostRef, RegistryInitArgs};
+use cspr_cdp_contracts::router::{Router, RouterHostRef, RouterInitArgs};
+use cspr_cdp_contracts::scspr_ybtoken::{ScsprYbToken, ScsprYbTokenHostRef, ScsprYbTokenInitArgs};
+use cspr_cdp_contracts::stablecoin::{CsprUsd, CsprUsdHostRef, CsprUsdInitArgs};
+use cspr_cdp_contracts::styks_oracle::{CSPR_USD_FEED_ID, PRICE_SCALE};
+use cspr_cdp_contracts::types::ComponentId;
+use cspr_cdp_contracts::withdraw_queue::{WithdrawQueue, WithdrawQueueHostRef, WithdrawQueueInitArgs};
+use odra::casper_types::U256;
+use odra::host::{Deployer, HostEnv, NoArgs};
+use odra::prelude::*;
+
+use crate::mocks::MockStyksFeed;
+
+/// Minimum collateralization ratio used by the fixture (110%)
+pub const MCR_BPS: u32 = 11000;
+
+/// Whole gUSD amount (18 decimals)
+pub fn gusd(n: u64) -> U256 {
+    U256::from(n) * U256::from(10u64).pow(U256::from(18))
+}
+
+/// Whole CSPR or stCSPR amount (9 decimals)
+pub fn cspr(n: u64) -> U256 {
+    U256::from(n) * U256::from(1_000_000_000u64)
+}
+
+/// Deployed contracts and the accounts playing protocol roles
+pub struct Fixture {
+    pub env: HostEnv,
+    /// Registry admin and contract deployer
+    pub admin: Address,
+    /// Address registered as the router
+    pub router: Address,
+    /// Account registered as the liquidation engine
+    pub liquidation_engine: Address,
+    /// Account registered as the redemption engine
+    pub redemption_engine: Address,
+    /// Account registered as the treasury
+    pub treasury: Address,
+    pub registry: RegistryHostRef,
+    pub stablecoin: CsprUsdHostRef,
+    pub scspr: ScsprYbTokenHostRef,
+    pub withdraw_queue: WithdrawQueueHostRef,
+    pub branch_cspr: BranchCsprHostRef,
+    pub branch_scspr: BranchScsprHostRef,
+}
+
+impl Fixture {
+    /// Deploy and wire the fixture contracts
+    pub fn deploy() -> Self {
+        Self::deploy_with(false).0
+    }
+
+    /// Deploy with a Router contract as the router, allowed to mint and burn gUSD
+    pub fn deploy_with_router() -> (Self, RouterHostRef) {
+        let (fixture, router) = Self::deploy_with(true);
+        (fixture, router.expect("router deployed"))
+    }
+
+    fn deploy_with(router_contract: bool) -> (Self, Option<RouterHostRef>) {
+        let env = odra_test::env();
+        let admin = env.get_account(0);
+        let liquidation_engine = env.get_account(2);
+        let treasury = env.get_account(3);
+        let redemption_engine = env.get_account(4);
+        env.set_caller(admin);
+
+        let mut registry = Registry::deploy(
+            &env,
+            RegistryInitArgs {
+                admin: admin.into(),
+                mcr_bps: MCR_BPS,
+                min_debt: gusd(1),
+                borrowing_fee_bps: 0,
+                redemption_fee_bps: 50,
+                liquidation_penalty_bps: 1000,
+                interest_min_bps: 0,
+                interest_max_bps: 4000,
+            },
+        );
+        let registry_addr = registry.address();
+        let router_ref = router_contract.then(|| Router::deploy(&env, RouterInitArgs { registry: registry_addr }));
+        let router = match &router_ref {
+            Some(contract) => contract.address(),
+            None => env.get_account(1),
+        };
+        let mut stablecoin = CsprUsd::deploy(&env, CsprUsdInitArgs { registry: registry_addr });
+        let mut scspr = ScsprYbToken::deploy(
+            &env,
+            ScsprYbTokenInitArgs {
+                admin,
+                operator: admin,
+            },
+        );
+        let withdraw_queue = WithdrawQueue::deploy(
+            &env,
+            WithdrawQueueInitArgs {
+                ybtoken: scspr.address(),
+                admin,
+            },
+        );
+        scspr.set_withdraw_queue(withdraw_queue.address());
+        let branch_cspr = BranchCspr::deploy(
+            &env,
+            BranchCsprInitArgs {
+                registry: registry_addr,
+                router,
+            },
+        );
+        let branch_scspr = BranchScspr::deploy(
+            &env,
+            BranchScsprInitArgs {
+                registry: registry_addr,
+                router,
+                scspr_token: scspr.address(),
+            },
+        );
+
+        registry.set_router(router);
+        registry.set_stablecoin(stablecoin.address());
+        registry.set_treasury(treasury);
+        registry.set_liquidation_engine(liquidation_engine);
+        registry.set_component(ComponentId::RedemptionEngine, redemption_engine);
+        registry.register_branch_cspr(branch_cspr.address(), 9, MCR_BPS);
+        registry.register_branch_scspr(branch_scspr.address(), scspr.address(), 9, MCR_BPS);
+        if router_ref.is_some() {
+            stablecoin.add_minter(router);
+        }
+
+        let fixture = Self {
+            env,
+            admin,
+            router,
+            liquidation_engine,
+            redemption_engine,
+            treasury,
+            registry,
+            stablecoin,
+            scspr,
+            withdraw_queue,
+            branch_cspr,
+            branch_scspr,
+        };
+        (fixture, router_ref)
+    }
+
+    /// The n-th account not used for a protocol role
+    pub fn user(&self, n: usize) -> Address {
+        self.env.get_account(5 + n)
+    }
+
+    /// Deploy a RedemptionEngine over both branches and register it as the redemption engine
+    ///
+    /// Prices come from a mock Styks feed at $1 per CSPR.
+    pub fn deploy_redemption_engine(&mut self) -> RedemptionEngineHostRef {
+        self.env.set_caller(self.admin);
+        let mut feed = MockStyksFeed::deploy(&self.env, NoArgs);
+        feed.set_price(CSPR_USD_FEED_ID.to_string(), U256::from(PRICE_SCALE));
+        let mut engine = RedemptionEngine::deploy(
+            &self.env,
+            RedemptionEngineInitArgs {
+                registry: self.registry.address(),
+                router: self.router,
+                stablecoin: self.stablecoin.address(),
+                treasury: self.treasury,
+                styks_oracle: feed.address(),
+            },
+        );
+        engine.set_branch_cspr(self.branch_cspr.address());
+        engine.set_branch_scspr(self.branch_scspr.address());
+        engine.set_scspr_token(self.scspr.address());
+        self.registry.set_component(ComponentId::RedemptionEngine, engine.address());
+        self.redemption_engine = engine.address();
+        engine
+    }
+
+    /// Open a CSPR vault for `owner` through the router account
+    pub fn open_cspr_vault(&mut self, owner: Address, collateral: U256, debt: U256, rate_bps: u32) -> u64 {
+        self.env.set_caller(self.router);
+        self.branch_cspr.open_vault(owner, collateral, debt, rate_bps)
+    }
+}
@@ -0,0 +1,45 @@
+//! Test doubles for contracts the protocol talks to but does not ship.
+
+use cspr_cdp_contracts::styks_oracle::StyksTwapPrice;
+use odra::casper_types::U256;
+use odra::prelude::*;
+
+/// Styks price feed returning a fixed TWAP per feed id
+#[odra::module]
+pub struct MockStyksFeed {
+    prices: Mapping<String, U256>,
+}
+
+#[odra::module]
+impl MockStyksFeed {
+    /// Set the TWAP returned for `price_feed_id`
+    pub fn set_price(&mut self, price_feed_id: String, price: U256) {
+        self.prices.set(&price_feed_id, price);
+    }
+
+    /// Same shape as the Styks entrypoint; unset feeds have no price
+    pub fn get_twap_price(&self, price_feed_id: String) -> Option<StyksTwapPrice> {
+        self.prices.get(&price_feed_id).map(|price| StyksTwapPrice {
+            price,
+            timestamp: self.env().get_block_time(),
+            num_observations: 1,
+        })
+    }
+}
+
+/// Forwards attached CSPR to another contract
+///
+/// Host accounts can't transfer CSPR to a contract directly, so tests route
+/// funding through this.
+#[odra::module]
+pub struct CsprForwarder {}
+
+#[odra::module]
+impl CsprForwarder {
+    /// Send the attached CSPR to `to`
+    #[odra(payable)]
+    pub fn forward(&mut self, to: Address) {
+        let amount = self.env().attached_value();
+        self.env().transfer_tokens(&to, &amount);
+    }
+}